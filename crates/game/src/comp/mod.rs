@@ -2,3 +2,7 @@ mod toolbar;
 pub use toolbar::*;
 mod err_page;
 pub use err_page::*;
+mod research;
+pub use research::*;
+mod text_input;
+pub use text_input::*;
@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+
+use game_core::{Inventory, ResourceAmount};
+use sui::{Layable, LayableExt, core::ReturnEvent};
+
+use crate::levels::Research;
+
+#[derive(Clone, Debug)]
+/// the ReturnEvent sent back by the research panel, with the id of the node to research
+pub struct StartResearch(pub String);
+
+/// lists every research node with a button to research it. listen to [StartResearch] to have it working
+pub fn research_panel(
+	research: &Research,
+	inventory: &Inventory,
+) -> impl Layable + Debug + 'static {
+	let Research { tree, progress } = research;
+
+	let rows = tree.nodes.iter().map(|node| {
+		let researched = tree.is_researched(progress, &node.id);
+		let available = tree.is_available(progress, &node.id);
+
+		let label = if researched {
+			"researched".to_string()
+		} else if available {
+			let cost = node
+				.cost
+				.iter()
+				.map(ResourceAmount::to_string)
+				.collect::<Vec<_>>()
+				.join(", ");
+			format!("research ({cost})")
+		} else {
+			let requires = node
+				.requires
+				.iter()
+				.map(|req| tree.node(req).map(|req| req.name.as_str()).unwrap_or(req))
+				.collect::<Vec<_>>()
+				.join(", ");
+			format!("requires {requires}")
+		};
+		let disabled = researched || !available || !inventory.can_afford(&node.cost);
+
+		let id = node.id.clone();
+		let button = comp_extra::button_explicit(label, disabled, move || {
+			ReturnEvent::new(StartResearch(id.clone()))
+		});
+
+		let text = sui::div([
+			sui::Text::new(node.name.clone(), 24).into_comp(),
+			sui::Text::new(node.desc.clone(), 16).into_comp(),
+		]);
+		sui::div_h([
+			sui::custom_only_debug(text.margin(4)),
+			sui::custom_only_debug(button),
+		])
+	});
+	let rows = rows.collect::<Vec<_>>();
+
+	let title = sui::Text::new("research", 32).margin(4);
+	let panel = sui::div([
+		sui::custom_only_debug(title),
+		sui::custom_only_debug(sui::div(rows)),
+	]);
+	let panel = panel.margin(8);
+	let panel = panel.with_background(sui::comp::Color::new(sui::color(0, 0, 0, 200)));
+	panel
+}
@@ -0,0 +1,82 @@
+use std::fmt::Debug;
+
+use sui::{
+	Layable, LayableExt,
+	core::{Event, KeyboardEvent, ReturnEvent},
+	raylib::ffi::KeyboardKey,
+};
+use utils::NoDebug;
+
+/// a single line of text the player can type into. enter submits it
+#[derive(Debug)]
+pub struct TextInput<F: FnMut(String) -> ReturnEvent> {
+	text: String,
+	max_len: usize,
+	on_submit: NoDebug<F>,
+
+	// keys are reported every frame they're held down, these make them act only once per press
+	backspace_held: bool,
+	enter_held: bool,
+}
+impl<F: FnMut(String) -> ReturnEvent> TextInput<F> {
+	pub fn new(text: impl Into<String>, max_len: usize, on_submit: F) -> Self {
+		Self {
+			text: text.into(),
+			max_len,
+			on_submit: NoDebug::new(on_submit),
+			backspace_held: false,
+			enter_held: false,
+		}
+	}
+
+	fn display(&self) -> impl Layable + Debug {
+		let text = sui::Text::new(format!("{}_", self.text), 24);
+		let text = text.margin(4).margin_h(4);
+		let text = text.with_background(sui::comp::Color::new(sui::Color::BLACK));
+		text.margin(1)
+			.with_background(sui::comp::Color::new(sui::color(25, 25, 25, 255)))
+	}
+}
+impl<F: FnMut(String) -> ReturnEvent> Layable for TextInput<F> {
+	fn size(&self) -> (i32, i32) {
+		self.display().size()
+	}
+	fn render(&self, d: &mut sui::Handle, det: sui::Details, scale: f32) {
+		self.display().render(d, det, scale);
+	}
+
+	fn pass_events(
+		&mut self,
+		events: impl Iterator<Item = Event>,
+		_det: sui::Details,
+		_scale: f32,
+		ret_events: &mut Vec<ReturnEvent>,
+	) {
+		let (mut backspace, mut enter) = (false, false);
+		for event in events {
+			match event {
+				Event::KeyboardEvent(_, KeyboardEvent::CharPressed(c)) => {
+					if !c.is_control() && self.text.chars().count() < self.max_len {
+						self.text.push(c);
+					}
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_BACKSPACE)) => {
+					backspace = true;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_ENTER)) => {
+					enter = true;
+				}
+				_ => {}
+			}
+		}
+
+		if backspace && !self.backspace_held {
+			self.text.pop();
+		}
+		if enter && !self.enter_held {
+			ret_events.push((self.on_submit)(self.text.clone()));
+		}
+		self.backspace_held = backspace;
+		self.enter_held = enter;
+	}
+}
@@ -21,10 +21,18 @@ pub fn toolbar(textures: &Textures) -> impl Layable + Clone + Debug + 'static {
 pub fn toolbar_from_tools(
 	textures: &Textures,
 	tools: impl Iterator<Item = Tool>,
+) -> impl Layable + Clone + Debug + 'static {
+	toolbar_with_locked(textures, tools.map(|tool| (tool, false)))
+}
+/// like [toolbar_from_tools], but tools marked as locked are greyed out \
+/// locked tools still send [SelectTool], so check what you receive
+pub fn toolbar_with_locked(
+	textures: &Textures,
+	tools: impl Iterator<Item = (Tool, bool)>,
 ) -> impl Layable + Clone + Debug + 'static {
 	let tooltip_data = TooltipData::default();
 
-	let toolbar = tools.map(|tool| {
+	let toolbar = tools.map(|(tool, locked)| {
 		let texture = match tool.clone() {
 			Tool::PlaceBuilding(building) => sui::custom(building.tool_icon_render(textures)),
 
@@ -38,8 +46,20 @@ pub fn toolbar_from_tools(
 		};
 
 		let texture = texture.fix_wh_square(64);
+		let overlay_color = if !locked {
+			sui::color(0, 0, 0, 0)
+		} else {
+			sui::color(0, 0, 0, 180)
+		};
+		let texture = texture.overlay(sui::comp::Color::new(overlay_color));
 		let texture = texture.margin(4);
-		let texture = comp_extra::TooltipOnHover::new(tool.name(), tooltip_data.clone(), texture);
+
+		let tooltip = if !locked {
+			tool.name()
+		} else {
+			format!("{} (locked)", tool.name()).into()
+		};
+		let texture = comp_extra::TooltipOnHover::new(tooltip, tooltip_data.clone(), texture);
 
 		// sui::Text::new(tool.name(), 24)
 		texture.clickable(move |_| SelectTool(tool.clone()))
@@ -1,5 +1,8 @@
 use anyhow::{Context, anyhow};
-use game_core::{GAME_TICK_FREQUENCY, GameData, GameProvider};
+use game_core::{
+	CostTable, GAME_TICK_FREQUENCY, GameData, GameProvider, Inventory, ResourceAmount,
+	ResourceKind, SimSpeed, TimeControl, ticks_duration,
+};
 use stage_manager_remote::{RemoteStage, RemoteStageChange};
 use std::{
 	fmt::Debug,
@@ -14,19 +17,68 @@ use sui::{
 use tokio::sync::{broadcast, mpsc};
 
 use crate::{
-	comp::{SelectTool, toolbar},
-	game::{Timer, Tool, timer::TimerRenderable},
+	comp::{
+		SelectTool, StartResearch, research_panel, toolbar, toolbar_from_tools, toolbar_with_locked,
+	},
+	game::{Timer, Tool, timer::TimerRenderable, tools},
+	levels::{Research, ResearchProgress},
 	textures::Textures,
 	utils::ReturnEvents,
 	world::{
 		EResource,
-		buildings::BuildingsMap,
-		maps::{SIZE, Tilemap, TilemapExt},
+		buildings::{BuildingKind, BuildingsMap},
+		maps::{SIZE, Tilemap, TilemapExt, ascii},
 		render::TILE_RENDER_SIZE,
 	},
 };
 use utils::NoDebug;
 
+/// the most simulation ticks a single frame can run before the simulation gives up on catching up
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// what the player starts free play with, enough for a first extractor, smelter and core
+const FREE_PLAY_START: [ResourceAmount; 1] = [ResourceAmount {
+	resource: ResourceKind::Iron,
+	amount: 40,
+}];
+
+/// newly generated free play worlds cost resources to build in
+fn free_play_data(tilemap: Tilemap, buildings: BuildingsMap) -> GameData {
+	GameData::new(tilemap, buildings).with_economy(
+		Inventory::from_amounts(&FREE_PLAY_START),
+		CostTable::default(),
+	)
+}
+
+/// everything about a [Game] that a session save needs, besides what the level script keeps track of
+#[derive(Clone, Debug)]
+pub struct GameSnapshot {
+	pub data: GameData,
+	pub timer: Option<Timer>,
+	pub camera_at: (f32, f32),
+	pub scale: f32,
+	pub tool: Tool,
+}
+
+/// the hotkeys handled by the handler given to [Game::enable_save_hotkeys]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SaveHotkey {
+	/// F5
+	QuickSave,
+	/// F9
+	QuickLoad,
+	/// F6, for opening the save browser
+	Browse,
+}
+
+/// hands the game data to a handler every `interval`, and when the player leaves
+#[derive(Debug)]
+struct Autosave {
+	interval: Duration,
+	last: Instant,
+	handler: NoDebug<Box<dyn FnMut(GameData) + Send>>,
+}
+
 /// Singleplayer, self-contained game renderer \
 /// quite versatile now, many features are available opt-in, so `Game` can be used
 /// to render both the most primitive, and the most complex remote-controlled game too
@@ -43,9 +95,27 @@ pub struct Game<G: GameProvider> {
 
 	timer: Option<Timer>,
 	paused: bool,
+	speed: SimSpeed,
 	can_toggle_time: bool,
 
 	save_handler: Option<NoDebug<Box<dyn FnMut(GameData) + Send>>>,
+	autosave: Option<Autosave>,
+	save_hotkeys:
+		Option<NoDebug<Box<dyn FnMut(SaveHotkey, GameData) -> Option<ReturnEvent> + Send>>>,
+	/// hotkeys are reported every frame they're held down, this makes them act only once per press
+	save_hotkey_held: Option<SaveHotkey>,
+	/// like save_hotkey_held, for F3 printing the world to the terminal
+	dump_held: bool,
+	/// called when the player leaves the game with escape
+	exit_handler: Option<NoDebug<Box<dyn FnMut(GameSnapshot) + Send>>>,
+
+	/// the buildings the level lets the player place, every one if None
+	allowed_tools: Option<Vec<BuildingKind>>,
+	/// locks tools until they're researched, if enabled
+	research: Option<Research>,
+	research_handler: Option<NoDebug<Box<dyn FnMut(&ResearchProgress) + Send>>>,
+	/// rebuilt every tick while open, so affordability stays up to date
+	research_panel: Option<DynamicLayable<'static>>,
 
 	/// camera center position in world coordinates
 	camera_at: (f32, f32),
@@ -54,14 +124,17 @@ pub struct Game<G: GameProvider> {
 	scale_velocity: f32,
 
 	last_tick: Instant,
-	last_game_tick: Instant,
+	/// simulation time that has passed but hasn't been ticked yet
+	tick_accumulator: Duration,
+	/// the tick count of the provider the last time we looked, used to advance the timer
+	last_seen_ticks: u64,
 }
 impl Game<GameData> {
 	pub fn new_worldgen(textures: Textures) -> anyhow::Result<Self> {
 		let tilemap = game_worldgen::gen_world(SIZE, SIZE)?;
 		let buildings = BuildingsMap::new_default(SIZE, SIZE);
 
-		let game = Self::from_maps(textures, tilemap, buildings);
+		let game = Self::new(textures, free_play_data(tilemap, buildings));
 		Ok(game)
 	}
 	pub fn from_maps(textures: Textures, tilemap: Tilemap, buildings: BuildingsMap) -> Self {
@@ -83,7 +156,7 @@ impl Game<game_multithread::MultithreadedGame> {
 	pub fn new_multithread_worldgen(textures: Textures) -> anyhow::Result<Self> {
 		let tilemap = game_worldgen::gen_world(SIZE, SIZE)?;
 		let buildings = BuildingsMap::new_default(SIZE, SIZE);
-		let data = GameData::new(tilemap, buildings);
+		let data = free_play_data(tilemap, buildings);
 
 		let game = Self::new_multithread(textures, data);
 		Ok(game)
@@ -103,6 +176,7 @@ impl<G: GameProvider> Game<G> {
 		tool_use_tx: broadcast::Sender<(Tool, (i32, i32))>,
 	) -> Self {
 		let (width, height) = game.data().world_size();
+		let last_seen_ticks = game.data().ticks();
 
 		Self {
 			toolbar: sui::custom(toolbar(&textures)),
@@ -111,17 +185,28 @@ impl<G: GameProvider> Game<G> {
 			timer: None,
 			goal_display: None,
 			paused: false,
+			speed: SimSpeed::default(),
 			can_toggle_time: true,
 			game,
 			tool: Default::default(),
 			tool_use_tx,
 			save_handler: None,
+			autosave: None,
+			save_hotkeys: None,
+			save_hotkey_held: None,
+			dump_held: false,
+			exit_handler: None,
+			allowed_tools: None,
+			research: None,
+			research_handler: None,
+			research_panel: None,
 			camera_at: (width as f32 / 2.0, height as f32 / 2.0),
 			camera_velocity: (0.0, 0.0),
 			scale: 1.0,
 			scale_velocity: 0.0,
 			last_tick: Instant::now(),
-			last_game_tick: Instant::now(),
+			tick_accumulator: Duration::ZERO,
+			last_seen_ticks,
 		}
 	}
 
@@ -193,6 +278,194 @@ impl<G: GameProvider> Game<G> {
 		self.save_handler = None;
 	}
 
+	/// calls `handler` with the game data every `interval` of wall clock time and when the player
+	/// leaves the game. the handler runs on the render thread, so it should only hand the data off
+	pub fn enable_autosave<F: FnMut(GameData) + Send + 'static>(
+		&mut self,
+		interval: Duration,
+		handler: F,
+	) {
+		self.autosave = Some(Autosave {
+			interval,
+			last: Instant::now(),
+			handler: NoDebug::new(Box::new(handler)),
+		});
+	}
+	pub fn disable_autosave(&mut self) {
+		self.autosave = None;
+	}
+	fn autosave(&mut self) {
+		if self.autosave.is_none() {
+			return;
+		}
+		let game_data = self.data().clone();
+		if let Some(autosave) = &mut self.autosave {
+			autosave.last = Instant::now();
+			(autosave.handler)(game_data);
+		}
+	}
+
+	/// calls `handler` with the game data when a [SaveHotkey] is pressed. the event it returns, like
+	/// a stage change for loading a save, is passed on
+	pub fn enable_save_hotkeys<
+		F: FnMut(SaveHotkey, GameData) -> Option<ReturnEvent> + Send + 'static,
+	>(
+		&mut self,
+		handler: F,
+	) {
+		self.save_hotkeys = Some(NoDebug::new(Box::new(handler)));
+	}
+	pub fn disable_save_hotkeys(&mut self) {
+		self.save_hotkeys = None;
+	}
+
+	/// called with a snapshot of the game right before the player leaves it for the main menu
+	pub fn enable_exit_handler<F: FnMut(GameSnapshot) + Send + 'static>(&mut self, handler: F) {
+		self.exit_handler = Some(NoDebug::new(Box::new(handler)));
+	}
+	pub fn disable_exit_handler(&mut self) {
+		self.exit_handler = None;
+	}
+
+	pub fn snapshot(&self) -> GameSnapshot {
+		GameSnapshot {
+			data: self.data().clone(),
+			timer: self.timer.clone(),
+			camera_at: self.camera_at,
+			scale: self.scale,
+			tool: self.tool.clone(),
+		}
+	}
+	/// puts the camera, the tool and the timer back where a snapshot had them. the data has to be
+	/// given to the provider separately
+	pub fn restore_snapshot_view(
+		&mut self,
+		timer: Option<Timer>,
+		camera_at: (f32, f32),
+		scale: f32,
+		tool: Tool,
+	) {
+		self.timer = timer;
+		self.camera_at = camera_at;
+		self.scale = scale;
+		if self.is_tool_unlocked(&tool) {
+			self.tool = tool;
+		}
+
+		if self.paused {
+			self.pause_time();
+		} else {
+			self.resume_time();
+		}
+	}
+
+	/// locks every tool that isn't unlocked in the research tree. opening the research panel with R
+	/// lets the player research more
+	pub fn enable_research(&mut self, research: Research) {
+		self.research = Some(research);
+		self.rebuild_toolbar();
+	}
+	pub fn disable_research(&mut self) {
+		self.research = None;
+		self.research_panel = None;
+		self.rebuild_toolbar();
+	}
+	pub fn research_progress(&self) -> Option<&ResearchProgress> {
+		self.research.as_ref().map(|research| &research.progress)
+	}
+	/// called with the new progress every time something gets researched, for saving it
+	pub fn enable_research_handler<F: FnMut(&ResearchProgress) + Send + 'static>(
+		&mut self,
+		handler: F,
+	) {
+		self.research_handler = Some(NoDebug::new(Box::new(handler)));
+	}
+
+	/// limits the toolbar to tools placing these buildings. the eraser is always allowed
+	pub fn set_allowed_tools(&mut self, allowed_tools: Option<Vec<BuildingKind>>) {
+		self.allowed_tools = allowed_tools;
+		self.rebuild_toolbar();
+	}
+	pub fn is_tool_allowed(&self, tool: &Tool) -> bool {
+		let kind = tool.building().kind();
+		match &self.allowed_tools {
+			_ if kind == BuildingKind::Nothing => true,
+			Some(allowed) => allowed.contains(&kind),
+			None => true,
+		}
+	}
+
+	pub fn is_tool_unlocked(&self, tool: &Tool) -> bool {
+		let researched = match &self.research {
+			Some(research) => research.is_tool_unlocked(tool),
+			None => true,
+		};
+		researched && self.is_tool_allowed(tool)
+	}
+
+	/// pays for and researches the node with the given id, if it's available
+	pub fn research(&mut self, id: &str) {
+		let Some(research) = &mut self.research else {
+			return;
+		};
+		let Some(node) = research.tree.node(id) else {
+			mklogger::eprintln!("no research node with id {id:?}");
+			return;
+		};
+		if !research.tree.is_available(&research.progress, id) {
+			mklogger::eprintln!("{} can't be researched right now", node.name);
+			return;
+		}
+
+		let cost = node.cost.clone();
+		let paid = self.game.modify(|data| data.inventory.try_spend(&cost));
+		if let Err(missing) = paid {
+			let missing = missing
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>()
+				.join(", ");
+			mklogger::eprintln!("can't afford {}: missing {missing}", node.name);
+			return;
+		}
+
+		mklogger::println!("researched {}", node.name);
+		research.progress.researched.insert(id.to_string());
+		if let Some(handler) = &mut self.research_handler {
+			handler(&research.progress);
+		}
+		self.rebuild_toolbar();
+	}
+
+	pub fn toggle_research_panel(&mut self) {
+		if self.research_panel.is_some() {
+			self.research_panel = None;
+		} else {
+			self.rebuild_research_panel();
+		}
+	}
+	fn rebuild_research_panel(&mut self) {
+		let panel = match &self.research {
+			Some(research) => research_panel(research, &self.data().inventory),
+			None => return,
+		};
+		self.research_panel = Some(sui::custom_only_debug(panel));
+	}
+
+	fn rebuild_toolbar(&mut self) {
+		let tools = tools().filter(|tool| self.is_tool_allowed(tool));
+		self.toolbar = match &self.research {
+			Some(research) => {
+				let tools = tools.map(|tool| {
+					let locked = !research.is_tool_unlocked(&tool);
+					(tool, locked)
+				});
+				sui::custom(toolbar_with_locked(&self.textures, tools))
+			}
+			None => sui::custom(toolbar_from_tools(&self.textures, tools)),
+		};
+	}
+
 	/// sets and starts the timer if the game is started; use self.pause_time() to spawn in paused
 	pub fn enable_timer(&mut self, target: Duration) {
 		self.timer = Some(Timer::new(target));
@@ -206,8 +479,12 @@ impl<G: GameProvider> Game<G> {
 	pub fn disable_timer(&mut self) {
 		self.timer = None;
 	}
+	pub fn timer(&self) -> Option<&Timer> {
+		self.timer.as_ref()
+	}
 
-	/// sets whether the user can toggle the passage of time with the spacebar
+	/// sets whether the user can control the passage of time: pausing with the spacebar,
+	/// changing the speed and stepping
 	pub fn set_can_toggle_time(&mut self, can_switch_time: bool) {
 		self.can_toggle_time = can_switch_time;
 	}
@@ -217,12 +494,14 @@ impl<G: GameProvider> Game<G> {
 		if let Some(timer) = &mut self.timer {
 			timer.pause();
 		}
+		self.sync_time_control();
 	}
 	pub fn resume_time(&mut self) {
 		self.paused = false;
 		if let Some(timer) = &mut self.timer {
 			timer.resume();
 		}
+		self.sync_time_control();
 	}
 	pub fn toggle_time(&mut self) {
 		if self.paused {
@@ -235,6 +514,41 @@ impl<G: GameProvider> Game<G> {
 		self.paused
 	}
 
+	pub fn set_speed(&mut self, speed: SimSpeed) {
+		self.speed = speed;
+		self.sync_time_control();
+	}
+	pub fn speed(&self) -> SimSpeed {
+		self.speed
+	}
+
+	/// advances the simulation by a single tick. only does anything while paused
+	pub fn step(&mut self) {
+		if !self.paused {
+			return;
+		}
+		self.game.step();
+	}
+
+	/// advances the timer by the simulation time that passed since the last call
+	fn sync_timer(&mut self) {
+		let ticks = self.data().ticks();
+		// saturating so a provider with fewer ticks (like after loading a save) doesn't underflow
+		let passed = ticks.saturating_sub(self.last_seen_ticks);
+		self.last_seen_ticks = ticks;
+
+		if let Some(timer) = &mut self.timer {
+			timer.advance(ticks_duration(passed));
+		}
+	}
+
+	fn sync_time_control(&mut self) {
+		self.game.set_time_control(TimeControl {
+			paused: self.paused,
+			speed: self.speed,
+		});
+	}
+
 	pub fn tile_resource_at(&self, pos: (i32, i32)) -> Option<EResource> {
 		self.data().tile_resource_at(pos)
 	}
@@ -288,6 +602,18 @@ impl<G: GameProvider> Game<G> {
 		}
 	}
 
+	pub fn research_panel_det(&self, det: Details) -> Option<Details> {
+		let panel = self.research_panel.as_ref()?;
+		let (w, h) = panel.size();
+		let l_det = Details {
+			x: det.x + (det.aw - w).max(0) / 2,
+			y: det.y + (det.ah - h).max(0) / 2,
+			aw: w.min(det.aw),
+			ah: h.min(det.ah),
+		};
+		Some(l_det)
+	}
+
 	fn wrap_as_world<L: Layable + Debug + Clone>(
 		&self,
 		layable: L,
@@ -336,10 +662,23 @@ impl<G: GameProvider> Layable for Game<G> {
 			} else {
 				sui::Comp::Space(sui::comp::Space::new(0, 0))
 			};
+			let speed = if self.speed != SimSpeed::Normal {
+				sui::Text::new(format!("speed: {}", self.speed.name()), 24).into_comp()
+			} else {
+				sui::Comp::Space(sui::comp::Space::new(0, 0))
+			};
+			// levels where building is free don't need to show the inventory
+			let inventory = if !data.costs.buildings.is_empty() || !data.inventory.is_empty() {
+				sui::Text::new(format!("inventory: {}", data.inventory), 24).into_comp()
+			} else {
+				sui::Comp::Space(sui::comp::Space::new(0, 0))
+			};
 			let ui = sui::div([
 				sui::custom(self.toolbar.immutable_wrap()).into_comp(),
 				sui::Text::new(format!("tool: {:?}", self.tool), 24).into_comp(),
+				inventory,
 				timer,
+				speed,
 			]);
 			let comp = world_as_comp.overlay(ui);
 
@@ -356,10 +695,15 @@ impl<G: GameProvider> Layable for Game<G> {
 				goal_display.render(d, l_det, 1.0);
 			}
 		}
+		if let Some(research_panel) = &self.research_panel {
+			let l_det = self.research_panel_det(det).unwrap();
+			research_panel.render(d, l_det, 1.0);
+		}
 	}
 
 	fn tick(&mut self) {
-		let delta = self.last_tick.elapsed().as_secs_f32();
+		let frame_time = self.last_tick.elapsed();
+		let delta = frame_time.as_secs_f32();
 
 		let tile_render_size = TILE_RENDER_SIZE as f32 * self.real_scale();
 
@@ -402,14 +746,23 @@ impl<G: GameProvider> Layable for Game<G> {
 		}
 
 		if !self.paused {
-			if self.last_game_tick.elapsed() >= GAME_TICK_FREQUENCY {
+			// fixed timestep: run as many simulation ticks as the time passed requires
+			self.tick_accumulator += frame_time.mul_f32(self.speed.multiplier());
+
+			let mut ticks = 0;
+			while self.tick_accumulator >= GAME_TICK_FREQUENCY {
+				if ticks >= MAX_TICKS_PER_FRAME {
+					// we're too far behind to catch up. drop the backlog instead of making every
+					// frame slower by trying to
+					self.tick_accumulator = Duration::ZERO;
+					break;
+				}
 				self.game.standard_tick();
-				self.last_game_tick = Instant::now();
-			}
-			if let Some(timer) = &mut self.timer {
-				timer.tick();
+				self.tick_accumulator -= GAME_TICK_FREQUENCY;
+				ticks += 1;
 			}
 		}
+		self.sync_timer();
 
 		if let Some(tips) = &mut self.tips {
 			tips.tick();
@@ -417,6 +770,16 @@ impl<G: GameProvider> Layable for Game<G> {
 		if let Some(goal_display) = &mut self.goal_display {
 			goal_display.tick();
 		}
+		if self.research_panel.is_some() {
+			self.rebuild_research_panel();
+		}
+		let autosave_due = self
+			.autosave
+			.as_ref()
+			.is_some_and(|autosave| autosave.last.elapsed() >= autosave.interval);
+		if autosave_due {
+			self.autosave();
+		}
 		self.last_tick = Instant::now();
 	}
 
@@ -429,12 +792,32 @@ impl<G: GameProvider> Layable for Game<G> {
 	) {
 		let mut ctrl = false;
 		let mut s = false;
+		let mut save_hotkey = None;
+		let mut dump = false;
 
 		let move_amount = 0.1;
 		for event in events {
 			match event {
 				Event::MouseEvent(m_event) => {
-					let (_, mouse_y) = m_event.at();
+					let (mouse_x, mouse_y) = m_event.at();
+
+					// the research panel sits on top of everything else
+					if let Some(l_det) = self.research_panel_det(det) {
+						if l_det.is_inside(mouse_x, mouse_y) {
+							let responses =
+								self.research_panel.as_mut().unwrap().pass_events_simple(
+									std::iter::once(Event::MouseEvent(m_event)),
+									l_det,
+									1.0,
+								);
+							for response in responses {
+								if let Some(StartResearch(id)) = response.take() {
+									self.research(&id);
+								}
+							}
+							continue;
+						}
+					}
 
 					let mut pass_to_tips = false;
 					if let Some(tips) = &self.tips {
@@ -478,6 +861,10 @@ impl<G: GameProvider> Layable for Game<G> {
 											if toolbar_resp.can_take::<SelectTool>() =>
 										{
 											if let Some(SelectTool(tool)) = toolbar_resp.take() {
+												if !self.is_tool_unlocked(&tool) {
+													mklogger::println!("{} is locked", tool.name());
+													continue;
+												}
 												mklogger::println!("selected {tool:?}");
 												self.tool = tool;
 												continue;
@@ -531,16 +918,53 @@ impl<G: GameProvider> Layable for Game<G> {
 					ctrl = true;
 				}
 
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_F5)) => {
+					save_hotkey = Some(SaveHotkey::QuickSave);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_F6)) => {
+					save_hotkey = Some(SaveHotkey::Browse);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_F9)) => {
+					save_hotkey = Some(SaveHotkey::QuickLoad);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_F3)) => {
+					dump = true;
+				}
+
 				Event::KeyboardEvent(_, KeyboardEvent::CharPressed(' ')) => {
 					if self.can_toggle_time {
 						self.toggle_time();
 					}
 				}
+				Event::KeyboardEvent(_, KeyboardEvent::CharPressed(c @ '1'..='4')) => {
+					if self.can_toggle_time {
+						let i = c as usize - '1' as usize;
+						if let Some(speed) = SimSpeed::all().nth(i) {
+							self.set_speed(speed);
+						}
+					}
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::CharPressed('r' | 'R')) => {
+					self.toggle_research_panel();
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::CharPressed('.')) => {
+					if self.can_toggle_time {
+						self.step();
+					}
+				}
 
 				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_ESCAPE)) => {
 					// *self.tilemap.tiles_mut() = worldgen::gen_tiles();
 					// TODO reimplement
 
+					self.autosave();
+					if self.exit_handler.is_some() {
+						let snapshot = self.snapshot();
+						if let Some(handler) = &mut self.exit_handler {
+							handler(snapshot)
+						}
+					}
+
 					let future = async { crate::scripts::main::main().await };
 					let loader = stage_manager_loaders::Loader::new_overlay(
 						sui::comp::Space::new(10, 10),
@@ -562,6 +986,24 @@ impl<G: GameProvider> Layable for Game<G> {
 				}
 			}
 		}
+
+		let pressed = save_hotkey.filter(|hotkey| self.save_hotkey_held != Some(*hotkey));
+		self.save_hotkey_held = save_hotkey;
+		if let Some(hotkey) = pressed {
+			if self.save_hotkeys.is_some() {
+				let game_data = self.data().clone();
+				if let Some(handler) = &mut self.save_hotkeys {
+					ret_events.extend(handler(hotkey, game_data));
+				}
+			}
+		}
+
+		if dump && !self.dump_held {
+			let data = self.data();
+			let world = ascii::print_world(&data.tilemap, &data.buildings);
+			mklogger::println!("world after {} ticks:\n{world}", data.ticks());
+		}
+		self.dump_held = dump;
 	}
 }
 
@@ -1,34 +1,226 @@
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fmt::Debug,
+	time::Duration,
+};
 
 use anyhow::anyhow;
+use game_core::{ResourceKind, buildings::Delivery, ticks_in};
 use rust_i18n::t;
+use serde::{Deserialize, Serialize};
 use stage_manager_remote::RemoteStageChange;
 use sui::{Layable, LayableExt};
 use tokio::sync::mpsc::{self, error::TryRecvError};
 
 use crate::{
 	textures::{TextureID, Textures},
-	world::{EResource, Resource},
+	world::Resource,
 };
 
-#[derive(Debug)]
+/// something that has to be delivered into the level's sinks
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+	/// `amount` of `resource` delivered in total
+	Deliver { resource: ResourceKind, amount: u32 },
+	/// at least `per_sec` of `resource` every second, kept up for the last `sustain_secs`. checked
+	/// second by second (or per resource, for rates below one a second), so a burst doesn't count
+	Rate {
+		resource: ResourceKind,
+		per_sec: f32,
+		sustain_secs: f32,
+	},
+	/// every requirement met at the same time. an empty list is never met
+	AllOf(Vec<Requirement>),
+	/// at least one of the requirements met. an empty list is never met
+	AnyOf(Vec<Requirement>),
+}
+impl Requirement {
+	pub fn is_met(&self, deliveries: &Deliveries) -> bool {
+		match self {
+			Self::Deliver { resource, amount } => deliveries.total(*resource) >= *amount,
+			Self::Rate {
+				resource,
+				per_sec,
+				sustain_secs,
+			} => {
+				let window = Self::window_ticks(*sustain_secs);
+				let needed = (per_sec * sustain_secs).ceil().max(0.0) as u32;
+
+				// the window is split into intervals that each have to keep up, so a burst
+				// followed by nothing isn't a sustained rate
+				let interval_secs = (1.0 / per_sec.max(f32::EPSILON)).max(1.0);
+				let intervals = ((sustain_secs / interval_secs).floor() as u64).max(1);
+				let needed_per_interval = needed / intervals as u32;
+				let start = deliveries.now().saturating_sub(window);
+				let every_interval = (0..intervals).all(|i| {
+					let from = start + window * i / intervals;
+					let to = start + window * (i + 1) / intervals;
+					deliveries.count_between(*resource, from, to) >= needed_per_interval
+				});
+
+				// the rate has to be kept up for the whole window, not just a lucky start
+				deliveries.now() >= window
+					&& deliveries.count_in_window(*resource, window) >= needed
+					&& every_interval
+			}
+			Self::AllOf(all) => !all.is_empty() && all.iter().all(|req| req.is_met(deliveries)),
+			Self::AnyOf(any) => any.iter().any(|req| req.is_met(deliveries)),
+		}
+	}
+
+	fn window_ticks(sustain_secs: f32) -> u64 {
+		ticks_in(Duration::from_secs_f32(sustain_secs.max(0.0))) as u64
+	}
+	/// the longest stretch of ticks any rate requirement looks back on
+	pub fn longest_window(&self) -> u64 {
+		match self {
+			Self::Deliver { .. } => 0,
+			Self::Rate { sustain_secs, .. } => Self::window_ticks(*sustain_secs),
+			Self::AllOf(reqs) | Self::AnyOf(reqs) => {
+				reqs.iter().map(Self::longest_window).max().unwrap_or(0)
+			}
+		}
+	}
+
+	/// every [Self::Deliver] and [Self::Rate] in the tree, in order
+	pub fn leaves(&self) -> Vec<&Requirement> {
+		match self {
+			Self::Deliver { .. } | Self::Rate { .. } => vec![self],
+			Self::AllOf(reqs) | Self::AnyOf(reqs) => reqs.iter().flat_map(Self::leaves).collect(),
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GoalStatus {
+	Pending,
+	Reached,
+	/// the deadline passed before the goal was reached
+	Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// defines what the goal is for the level
 pub struct Goal {
-	resources: HashMap<EResource, i32>,
+	pub requirement: Requirement,
+	/// seconds of simulation time the goal has to be reached in
+	#[serde(default)]
+	pub deadline_secs: Option<f32>,
+}
+impl Default for Goal {
+	/// a goal that's never reached
+	fn default() -> Self {
+		Self::new(Requirement::AllOf(Vec::new()))
+	}
 }
 impl Goal {
-	pub fn new<I: IntoIterator<Item = (EResource, i32)>>(iter: I) -> Self {
+	pub fn new(requirement: Requirement) -> Self {
+		Self {
+			requirement,
+			deadline_secs: None,
+		}
+	}
+	/// every resource delivered in the given amounts
+	pub fn deliver_all<I: IntoIterator<Item = (ResourceKind, u32)>>(iter: I) -> Self {
+		let all = iter
+			.into_iter()
+			.map(|(resource, amount)| Requirement::Deliver { resource, amount });
+		Self::new(Requirement::AllOf(all.collect()))
+	}
+	pub fn with_deadline(mut self, deadline: Duration) -> Self {
+		self.deadline_secs = Some(deadline.as_secs_f32());
+		self
+	}
+
+	pub fn deadline(&self) -> Option<Duration> {
+		self.deadline_secs
+			.map(|secs| Duration::from_secs_f32(secs.max(0.0)))
+	}
+	fn deadline_ticks(&self) -> Option<u64> {
+		self.deadline().map(|deadline| ticks_in(deadline) as u64)
+	}
+
+	/// the status right now. isn't latched, so a rate goal can go back to pending
+	pub fn status(&self, deliveries: &Deliveries) -> GoalStatus {
+		let late = self
+			.deadline_ticks()
+			.map(|deadline| deliveries.now() > deadline)
+			.unwrap_or(false);
+
+		if late {
+			GoalStatus::Failed
+		} else if self.requirement.is_met(deliveries) {
+			GoalStatus::Reached
+		} else {
+			GoalStatus::Pending
+		}
+	}
+}
+
+/// everything delivered so far, with the recent deliveries timestamped for rate requirements
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Deliveries {
+	totals: BTreeMap<ResourceKind, u32>,
+	/// oldest first, only as far back as `keep_ticks`
+	recent: VecDeque<(u64, ResourceKind)>,
+	keep_ticks: u64,
+	now: u64,
+}
+impl Deliveries {
+	/// `keep_ticks` is how far back deliveries are remembered with their time
+	pub fn new(keep_ticks: u64) -> Self {
 		Self {
-			resources: iter.into_iter().collect(),
+			keep_ticks,
+			..Default::default()
 		}
 	}
-	pub fn from_hashmap(resources: HashMap<EResource, i32>) -> Self {
-		Self { resources }
+	pub fn set_keep_ticks(&mut self, keep_ticks: u64) {
+		self.keep_ticks = keep_ticks;
+		self.prune();
+	}
+
+	pub fn record(&mut self, resource: ResourceKind, tick: u64) {
+		*self.totals.entry(resource).or_default() += 1;
+		self.recent.push_back((tick, resource));
+		self.advance_to(tick);
+	}
+	/// moves the current time forward. time never goes backwards
+	pub fn advance_to(&mut self, tick: u64) {
+		self.now = self.now.max(tick);
+		self.prune();
+	}
+	fn prune(&mut self) {
+		let oldest = self.now.saturating_sub(self.keep_ticks);
+		while let Some((tick, _)) = self.recent.front() {
+			if *tick > oldest {
+				break;
+			}
+			self.recent.pop_front();
+		}
 	}
 
-	pub fn resources(&self) -> &HashMap<EResource, i32> {
-		&self.resources
+	pub fn now(&self) -> u64 {
+		self.now
+	}
+	pub fn total(&self, resource: ResourceKind) -> u32 {
+		self.totals.get(&resource).copied().unwrap_or_default()
+	}
+	pub fn totals(&self) -> impl Iterator<Item = (ResourceKind, u32)> + '_ {
+		self.totals
+			.iter()
+			.map(|(resource, total)| (*resource, *total))
+	}
+	/// how many of `resource` arrived in the last `window` ticks, now included
+	pub fn count_in_window(&self, resource: ResourceKind, window: u64) -> u32 {
+		self.count_between(resource, self.now.saturating_sub(window), self.now)
+	}
+	/// how many of `resource` arrived after tick `from`, up to and including tick `to`
+	pub fn count_between(&self, resource: ResourceKind, from: u64, to: u64) -> u32 {
+		self.recent
+			.iter()
+			.filter(|(tick, kind)| *tick > from && *tick <= to && *kind == resource)
+			.count() as u32
 	}
 }
 
@@ -39,12 +231,14 @@ struct RenderData {
 }
 
 #[derive(Debug)]
-/// the wrapper for the resource receiver to make checking for the goal easier
+/// the wrapper for the delivery receiver to make checking for the goal easier
 pub struct ResourceCounter {
 	goal: Goal,
+	/// latched: once reached or failed, it stays that way until the goal is changed
+	status: GoalStatus,
 
-	resource_rx: mpsc::Receiver<EResource>,
-	resources: HashMap<EResource, i32>,
+	delivery_rx: mpsc::Receiver<Delivery>,
+	deliveries: Deliveries,
 
 	render_data: Option<RenderData>,
 }
@@ -64,44 +258,57 @@ impl ResourceCounter {
 	fn progress_into_component(&self) -> Option<impl Layable + Debug + Clone + 'static> {
 		match &self.render_data {
 			Some(render_data) => {
-				let non_target_resources = self.resources.iter().filter_map(|(res, count)| {
-					if !self.goal.resources.contains_key(res) {
-						Some((res, count))
-					} else {
-						None
+				let leaves = self.goal.requirement.leaves();
+				let target_rows = leaves.iter().filter_map(|req| match req {
+					Requirement::Deliver { resource, amount } => {
+						let received = self.deliveries.total(*resource);
+						Some((*resource, format!("{received} / {amount}")))
 					}
+					Requirement::Rate {
+						resource,
+						per_sec,
+						sustain_secs,
+					} => {
+						let window = Requirement::window_ticks(*sustain_secs);
+						let received = self.deliveries.count_in_window(*resource, window);
+						let rate = received as f32 / sustain_secs.max(f32::EPSILON);
+						Some((*resource, format!("{rate:.1}/s / {per_sec:.1}/s")))
+					}
+					_ => None,
 				});
-				let target_resources = self.goal.resources.iter();
-
-				let non_target_resources = non_target_resources.map(|(res, _)| (res, None));
-				let target_resources = target_resources.map(|(res, count)| (res, Some(count)));
-				let all_resources = target_resources.chain(non_target_resources);
-
-				let rows = all_resources.map(|(resource, target)| {
-					let received = self.resources.get(resource).copied().unwrap_or_default();
-
-					let texture = render_data.textures.texture_for(resource.texture_id());
-					let texture = match texture {
-						Some(a) => Some(a.clone()),
-						None => render_data
-							.textures
-							.texture_for(TextureID::Transparent)
-							.cloned(),
-					};
-					let texture = texture.fix_wh_square(16);
-					let texture = texture.margin(2);
-
-					let target = match target {
-						Some(target) => format!(" / {target}"),
-						None => format!(""),
-					};
-					let count = format!("{received}{target}");
-					let count = sui::Text::new(count, 18);
-					let count = count.centered().margin(2);
-
-					let row = sui::div_h([sui::custom(texture), sui::custom(count)]);
-					row
-				});
+				let target_rows = target_rows.collect::<Vec<_>>();
+
+				let non_target_rows = self
+					.deliveries
+					.totals()
+					.filter(|(resource, _)| !target_rows.iter().any(|(res, _)| res == resource))
+					.map(|(resource, received)| (resource, format!("{received}")))
+					.collect::<Vec<_>>();
+
+				let rows =
+					target_rows
+						.into_iter()
+						.chain(non_target_rows)
+						.map(|(resource, count)| {
+							let texture = render_data
+								.textures
+								.texture_for(resource.resource().texture_id());
+							let texture = match texture {
+								Some(a) => Some(a.clone()),
+								None => render_data
+									.textures
+									.texture_for(TextureID::Transparent)
+									.cloned(),
+							};
+							let texture = texture.fix_wh_square(16);
+							let texture = texture.margin(2);
+
+							let count = sui::Text::new(count, 18);
+							let count = count.centered().margin(2);
+
+							let row = sui::div_h([sui::custom(texture), sui::custom(count)]);
+							row
+						});
 				let rows = rows.collect::<Vec<_>>();
 				let rows = sui::div(rows);
 				let rows = rows.margin(4);
@@ -139,24 +346,42 @@ impl ResourceCounter {
 	}
 }
 impl ResourceCounter {
-	pub fn new(goal: Goal, resource_rx: mpsc::Receiver<EResource>) -> Self {
+	pub fn new(goal: Goal, delivery_rx: mpsc::Receiver<Delivery>) -> Self {
+		let deliveries = Deliveries::new(goal.requirement.longest_window());
 		Self {
 			goal,
-			resource_rx,
+			status: GoalStatus::Pending,
+			delivery_rx,
+			deliveries,
 
-			resources: Default::default(),
 			render_data: None,
 		}
 	}
 
+	/// picks up where a saved session left off, with what was already delivered
+	pub fn with_deliveries(mut self, deliveries: Deliveries) -> Self {
+		self.deliveries = deliveries;
+		self.deliveries
+			.set_keep_ticks(self.goal.requirement.longest_window());
+		self.evaluate();
+		self
+	}
+
+	fn record(&mut self, delivery: Delivery) {
+		self.deliveries
+			.record(delivery.resource.kind(), delivery.tick);
+		self.evaluate();
+	}
+	fn evaluate(&mut self) {
+		if self.status == GoalStatus::Pending {
+			self.status = self.goal.status(&self.deliveries);
+		}
+	}
+
 	pub fn tick(&mut self) -> anyhow::Result<()> {
 		loop {
-			match self.resource_rx.try_recv() {
-				Ok(resource) => {
-					// add the received resource to the counter
-					let count = self.resources.get(&resource).copied().unwrap_or_default();
-					self.resources.insert(resource, count + 1);
-				}
+			match self.delivery_rx.try_recv() {
+				Ok(delivery) => self.record(delivery),
 				Err(TryRecvError::Empty) => break,
 				Err(TryRecvError::Disconnected) => {
 					return Err(mklogger::anyhow!(
@@ -169,35 +394,252 @@ impl ResourceCounter {
 	}
 	pub async fn tick_next(&mut self) -> anyhow::Result<()> {
 		let next = self
-			.resource_rx
+			.delivery_rx
 			.recv()
 			.await
 			.ok_or_else(|| mklogger::anyhow!("resource sender probably got dropped"))?;
-
-		let count = self.resources.get(&next).copied().unwrap_or_default();
-		self.resources.insert(next, count + 1);
+		self.record(next);
 
 		Ok(())
 	}
+	/// moves the goal's clock up to the world's `ticks`, the same clock deliveries are stamped with,
+	/// so a deadline can pass without anything being delivered
+	pub fn check_ticks(&mut self, ticks: u64) {
+		self.deliveries.advance_to(ticks);
+		self.evaluate();
+	}
 
 	pub fn goal(&self) -> &Goal {
 		&self.goal
 	}
+	/// replaces the goal and resets its status. what was delivered so far still counts
 	pub fn set_goal(&mut self, goal: Goal) {
+		self.deliveries
+			.set_keep_ticks(goal.requirement.longest_window());
 		self.goal = goal;
+		self.status = GoalStatus::Pending;
+		self.evaluate();
+	}
+	pub fn deliveries(&self) -> &Deliveries {
+		&self.deliveries
 	}
 
 	/// does not tick the underlying counter so call self.tick or else it won't
 	/// work and it'll be confusing
+	pub fn status(&self) -> GoalStatus {
+		self.status
+	}
 	pub fn is_reached(&self) -> bool {
-		self.goal
-			.resources
-			.iter()
-			.filter(|(resource, required_count)| {
-				let count = self.resources.get(resource).copied().unwrap_or_default();
+		self.status == GoalStatus::Reached
+	}
+	pub fn is_failed(&self) -> bool {
+		self.status == GoalStatus::Failed
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::world::EResource;
+
+	const IRON: ResourceKind = ResourceKind::Iron;
+	const COAL: ResourceKind = ResourceKind::Coal;
+
+	fn deliver(resource: ResourceKind, amount: u32) -> Requirement {
+		Requirement::Deliver { resource, amount }
+	}
+	fn secs(secs: u64) -> u64 {
+		ticks_in(Duration::from_secs(secs)) as u64
+	}
+
+	#[test]
+	fn deliver_counts_totals() {
+		let goal = Goal::new(deliver(IRON, 3));
+		let mut deliveries = Deliveries::default();
+
+		deliveries.record(IRON, 1);
+		deliveries.record(COAL, 2);
+		deliveries.record(IRON, 3);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+
+		deliveries.record(IRON, 4);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Reached);
+	}
+
+	#[test]
+	fn all_of_needs_every_requirement() {
+		let goal = Goal::deliver_all([(IRON, 2), (COAL, 2)]);
+		let mut deliveries = Deliveries::default();
+
+		deliveries.record(IRON, 1);
+		deliveries.record(IRON, 2);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+
+		deliveries.record(COAL, 3);
+		deliveries.record(COAL, 4);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Reached);
+	}
+
+	#[test]
+	fn any_of_needs_one_requirement() {
+		let goal = Goal::new(Requirement::AnyOf(vec![deliver(IRON, 2), deliver(COAL, 2)]));
+		let mut deliveries = Deliveries::default();
+
+		deliveries.record(COAL, 1);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+		deliveries.record(COAL, 2);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Reached);
+	}
+
+	#[test]
+	fn empty_goals_are_never_reached() {
+		let mut deliveries = Deliveries::default();
+		deliveries.record(IRON, 1);
+
+		assert_eq!(Goal::default().status(&deliveries), GoalStatus::Pending);
+		let any_of = Goal::new(Requirement::AnyOf(Vec::new()));
+		assert_eq!(any_of.status(&deliveries), GoalStatus::Pending);
+	}
+
+	#[test]
+	fn rate_has_to_be_sustained() {
+		// 1 iron every second for 4 seconds
+		let rate = Requirement::Rate {
+			resource: IRON,
+			per_sec: 1.0,
+			sustain_secs: 4.0,
+		};
+		let goal = Goal::new(rate.clone());
+		let mut deliveries = Deliveries::new(rate.longest_window());
+
+		// fast enough, but not for long enough yet
+		for i in 0..4 {
+			deliveries.record(IRON, i + 1);
+		}
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+
+		for sec in 1..=4 {
+			deliveries.record(IRON, secs(sec));
+		}
+		assert_eq!(goal.status(&deliveries), GoalStatus::Reached);
+	}
+
+	#[test]
+	fn bursts_arent_sustained() {
+		let rate = Requirement::Rate {
+			resource: IRON,
+			per_sec: 1.0,
+			sustain_secs: 4.0,
+		};
+		let goal = Goal::new(rate.clone());
+		let mut deliveries = Deliveries::new(rate.longest_window());
+
+		// a whole window's worth at once, then nothing
+		for _ in 0..4 {
+			deliveries.record(IRON, secs(1));
+		}
+		deliveries.advance_to(secs(4));
+		assert_eq!(deliveries.count_in_window(IRON, rate.longest_window()), 4);
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+
+		// slow rates are checked per resource instead of per second
+		let slow = Requirement::Rate {
+			resource: IRON,
+			per_sec: 0.5,
+			sustain_secs: 4.0,
+		};
+		let mut deliveries = Deliveries::new(slow.longest_window());
+		deliveries.record(IRON, secs(1));
+		deliveries.record(IRON, secs(3));
+		deliveries.advance_to(secs(4));
+		assert!(slow.is_met(&deliveries));
+	}
+
+	#[test]
+	fn rate_too_slow() {
+		let rate = Requirement::Rate {
+			resource: IRON,
+			per_sec: 1.0,
+			sustain_secs: 4.0,
+		};
+		let goal = Goal::new(rate.clone());
+		let mut deliveries = Deliveries::new(rate.longest_window());
+
+		// one every two seconds, for a long time
+		for sec in (2..=20).step_by(2) {
+			deliveries.record(IRON, secs(sec));
+		}
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+		// old deliveries are pruned
+		assert!(deliveries.recent.len() <= 3);
+	}
+
+	#[test]
+	fn deadline_fails_the_goal() {
+		let goal = Goal::new(deliver(IRON, 2)).with_deadline(Duration::from_secs(10));
+		let mut deliveries = Deliveries::default();
+
+		deliveries.record(IRON, secs(5));
+		assert_eq!(goal.status(&deliveries), GoalStatus::Pending);
+		deliveries.record(IRON, secs(11));
+		assert_eq!(goal.status(&deliveries), GoalStatus::Failed);
+	}
+
+	#[test]
+	fn counter_latches_status() {
+		let (tx, rx) = mpsc::channel(10);
+		let rate = Requirement::Rate {
+			resource: IRON,
+			per_sec: 1.0,
+			sustain_secs: 2.0,
+		};
+		let mut counter =
+			ResourceCounter::new(Goal::new(rate).with_deadline(Duration::from_secs(10)), rx);
+
+		for sec in 1..=2 {
+			let delivery = Delivery {
+				resource: EResource::iron(),
+				tick: secs(sec),
+			};
+			tx.try_send(delivery).unwrap();
+		}
+		counter.tick().unwrap();
+		assert!(counter.is_reached());
+
+		// the rate dropped off and the deadline passed, but it was already reached
+		counter.check_ticks(secs(20));
+		assert!(counter.is_reached());
+	}
+
+	#[test]
+	fn requirements_from_toml() {
+		#[derive(Deserialize)]
+		struct Level {
+			goal: Goal,
+		}
+		let level: Level = toml::from_str(
+			r#"
+			[goal]
+			deadline_secs = 120
+			requirement = { all_of = [
+				{ deliver = { resource = "iron", amount = 10 } },
+				{ rate = { resource = "coal", per_sec = 2, sustain_secs = 30 } },
+			] }
+			"#,
+		)
+		.unwrap();
 
-				**required_count <= count
-			})
-			.count() > 0
+		assert_eq!(level.goal.deadline(), Some(Duration::from_secs(120)));
+		assert_eq!(
+			level.goal.requirement,
+			Requirement::AllOf(vec![
+				deliver(IRON, 10),
+				Requirement::Rate {
+					resource: COAL,
+					per_sec: 2.0,
+					sustain_secs: 30.0,
+				},
+			])
+		);
 	}
 }
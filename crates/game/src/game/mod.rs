@@ -5,6 +5,6 @@ mod runner;
 pub use runner::*;
 pub mod goal;
 pub use game_core::prov::*;
-pub use goal::Goal;
+pub use goal::{Goal, GoalStatus, Requirement};
 mod game;
 pub use game::*;
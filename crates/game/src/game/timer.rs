@@ -1,64 +1,52 @@
-use std::{
-	fmt::Debug,
-	time::{Duration, Instant},
-};
+use std::{fmt::Debug, time::Duration};
 
+use serde::{Deserialize, Serialize};
 use sui::{Compatible, Layable, LayableExt};
 
-#[derive(Clone, Debug)]
-pub enum Pause {
-	Paused,
-	Ticking { last_tick: Instant },
-}
-
-#[derive(Clone, Debug)]
+/// counts simulation time towards a target \
+/// it doesn't measure time by itself, `Game` advances it by the time every simulation tick takes,
+/// so a sped up or lagging simulation keeps the timer in sync
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Timer {
 	target: Duration,
 	elapsed: Duration,
-	pause: Pause,
+	paused: bool,
 }
 impl Timer {
 	pub fn new(target: Duration) -> Self {
 		Self {
 			target,
 			elapsed: Duration::default(),
-			pause: Pause::Paused,
+			paused: true,
 		}
 	}
 
 	pub fn pause(&mut self) {
-		self.pause = Pause::Paused;
+		self.paused = true;
 	}
 	pub fn is_paused(&self) -> bool {
-		match self.pause {
-			Pause::Paused => true,
-			_ => false,
-		}
+		self.paused
 	}
 	pub fn resume(&mut self) {
-		self.pause = Pause::Ticking {
-			last_tick: Instant::now(),
-		};
+		self.paused = false;
 	}
 	pub fn is_ticking(&self) -> bool {
-		match self.pause {
-			Pause::Ticking { .. } => true,
-			_ => false,
-		}
+		!self.paused
 	}
 
-	pub fn tick(&mut self) {
-		match &mut self.pause {
-			Pause::Paused => {}
-			Pause::Ticking { last_tick } => {
-				let now = Instant::now();
-				self.elapsed += last_tick.elapsed();
-				*last_tick = now;
-			}
-		}
+	/// adds `by` to the elapsed time, even if paused. \
+	/// the paused state is only informational, since the simulation can still be stepped while paused
+	pub fn advance(&mut self, by: Duration) {
+		self.elapsed += by;
+	}
+
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+	pub fn target(&self) -> Duration {
+		self.target
 	}
 
-	/// you need to be calling timer.tick() for this to work
 	pub fn is_finished(&self) -> bool {
 		self.elapsed > self.target
 	}
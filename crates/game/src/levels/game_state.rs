@@ -1,11 +1,124 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf, sync::LazyLock, time::Duration};
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use utils::persist;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+use super::ResearchProgress;
+
+/// held through every [GameState::modify], so two writers can't each save their own copy of the
+/// state over the other's
+static WRITE_LOCK: LazyLock<tokio::sync::Mutex<()>> = LazyLock::new(Default::default);
+
+/// the first bytes of every versioned state file. files without it are from before versioning
+const MAGIC: &[u8; 4] = b"CGST";
+/// the version written by this version of the game. bump it and add a migration to
+/// [GameState::decode] whenever the layout of [GameState] changes
+pub const GAME_STATE_VERSION: u16 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+/// the player's progress, kept between runs. written as [MAGIC], the version as a little endian
+/// u16, then the bincode of the state
 pub struct GameState {
 	pub tutorial_completed: bool,
+	/// research progress for every campaign, by campaign id
+	pub research: HashMap<String, ResearchProgress>,
+	/// campaign level progress, by level id
+	pub levels: HashMap<String, LevelProgress>,
+	/// wall clock time spent in the game, over every run
+	pub playtime: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelProgress {
+	pub completed: bool,
+	/// the least simulation time the level was completed in
+	pub best_time: Option<Duration>,
+	/// the most stars the level was completed with, 0 to 3
+	pub stars: u8,
+}
+
+/// layouts of state files from before the versioned header, all of them version 0
+mod v0 {
+	use super::*;
+
+	/// from before research was added
+	#[derive(Deserialize)]
+	pub struct Tutorial {
+		pub tutorial_completed: bool,
+	}
+	/// from before campaign levels were added
+	#[derive(Deserialize)]
+	pub struct Research {
+		pub tutorial_completed: bool,
+		pub research: HashMap<String, ResearchProgress>,
+	}
+	/// from before stars and playtime were added
+	#[derive(Deserialize)]
+	pub struct Levels {
+		pub tutorial_completed: bool,
+		pub research: HashMap<String, ResearchProgress>,
+		pub levels: HashMap<String, LevelProgress>,
+	}
+	#[derive(Deserialize)]
+	pub struct LevelProgress {
+		pub completed: bool,
+		pub best_time: Option<Duration>,
+	}
+
+	impl From<Tutorial> for GameState {
+		fn from(state: Tutorial) -> Self {
+			Self {
+				tutorial_completed: state.tutorial_completed,
+				..Default::default()
+			}
+		}
+	}
+	impl From<Research> for GameState {
+		fn from(state: Research) -> Self {
+			Self {
+				tutorial_completed: state.tutorial_completed,
+				research: state.research,
+				..Default::default()
+			}
+		}
+	}
+	impl From<Levels> for GameState {
+		fn from(state: Levels) -> Self {
+			let levels = state.levels.into_iter().map(|(id, progress)| {
+				let progress = super::LevelProgress {
+					completed: progress.completed,
+					best_time: progress.best_time,
+					// the best we can do without knowing how fast it was
+					stars: if progress.completed { 1 } else { 0 },
+				};
+				(id, progress)
+			});
+			Self {
+				tutorial_completed: state.tutorial_completed,
+				research: state.research,
+				levels: levels.collect(),
+				..Default::default()
+			}
+		}
+	}
+
+	/// tries every layout, newest first
+	pub fn decode(bytes: &[u8]) -> anyhow::Result<GameState> {
+		let config = bincode::config::standard();
+
+		let levels = bincode::serde::decode_from_slice::<Levels, _>(bytes, config);
+		if let Ok((state, _)) = levels {
+			return Ok(state.into());
+		}
+		let research = bincode::serde::decode_from_slice::<Research, _>(bytes, config);
+		if let Ok((state, _)) = research {
+			return Ok(state.into());
+		}
+		let (state, _) = bincode::serde::decode_from_slice::<Tutorial, _>(bytes, config)
+			.with_context(|| format!("while decoding an unversioned GameState"))?;
+		Ok(state.into())
+	}
 }
 
 impl GameState {
@@ -22,48 +135,202 @@ impl GameState {
 		}
 	}
 
+	/// decodes any version of the state file, migrating it to the current layout
+	pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+		let Some(rest) = bytes.strip_prefix(MAGIC) else {
+			return v0::decode(bytes);
+		};
+		let (version, payload) = match rest {
+			[a, b, payload @ ..] => (u16::from_le_bytes([*a, *b]), payload),
+			_ => return Err(mklogger::anyhow!("GameState header is cut short")),
+		};
+
+		let config = bincode::config::standard();
+		match version {
+			1 => {
+				let (state, _) = bincode::serde::decode_from_slice(payload, config)
+					.with_context(|| format!("while decoding GameState version {version}"))?;
+				Ok(state)
+			}
+			_ => Err(mklogger::anyhow!(
+				"GameState version {version} isn't supported, the newest known version is {GAME_STATE_VERSION}"
+			)),
+		}
+	}
+	pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(MAGIC);
+		bytes.extend_from_slice(&GAME_STATE_VERSION.to_le_bytes());
+		bincode::serde::encode_into_std_write(self, &mut bytes, bincode::config::standard())
+			.with_context(|| format!("while encoding GameState"))?;
+		Ok(bytes)
+	}
+
+	/// falls back to the backup of the previous save if the state file can't be read
 	pub async fn load_with_error() -> anyhow::Result<Self> {
 		let path = Self::get_fs_path()?;
-		let file = tokio::fs::OpenOptions::new()
-			.read(true)
-			.open(&path)
+		tokio::task::spawn_blocking(move || persist::read_with_backup(&path, Self::decode))
 			.await
-			.with_context(|| format!("while reading GameState from {}", path.display()))?;
-		let mut file = file.into_std().await;
-
-		let decoded: GameState =
-			bincode::serde::decode_from_std_read(&mut file, bincode::config::standard())
-				.with_context(|| format!("while decoding GameState from {}", path.display()))?;
-
-		Ok(decoded)
+			.with_context(|| format!("GameState loading task panicked"))?
+			.with_context(|| format!("while loading GameState"))
 	}
+	/// falls back to the default state if there's none. a state file that can't be read is copied
+	/// next to it with a `.broken` extension first, so the next save doesn't wipe it
 	pub async fn load() -> Self {
 		let with_error = Self::load_with_error().await;
 		match with_error {
 			Ok(a) => a,
 			Err(err) => {
 				mklogger::eprintln!("failed to load GameState:\n{err}");
+				if let Err(err) = Self::keep_broken().await {
+					mklogger::eprintln!("failed to keep the broken GameState:\n{err}");
+				}
 				Self::default()
 			}
 		}
 	}
+	async fn keep_broken() -> anyhow::Result<()> {
+		let path = Self::get_fs_path()?;
+		if !tokio::fs::try_exists(&path).await? {
+			return Ok(());
+		}
+
+		let mut broken = path.clone().into_os_string();
+		broken.push(".broken");
+		tokio::fs::copy(&path, &broken)
+			.await
+			.with_context(|| format!("while copying {} aside", path.display()))?;
+		Ok(())
+	}
+
+	/// loads the state, changes it and saves it back, one writer at a time
+	pub async fn modify<R>(f: impl FnOnce(&mut Self) -> R) -> anyhow::Result<R> {
+		let _guard = WRITE_LOCK.lock().await;
+		let mut state = Self::load().await;
+		let ret = f(&mut state);
+		state.save().await?;
+		Ok(ret)
+	}
+
+	pub fn research(&self, campaign: &str) -> ResearchProgress {
+		self.research.get(campaign).cloned().unwrap_or_default()
+	}
+
+	pub fn level(&self, id: &str) -> LevelProgress {
+		self.levels.get(id).cloned().unwrap_or_default()
+	}
+	/// marks the level completed, keeping the best time and the most stars
+	pub fn complete_level(&mut self, id: &str, time: Duration, stars: u8) {
+		let progress = self.levels.entry(id.to_string()).or_default();
+		progress.completed = true;
+		progress.best_time = match progress.best_time {
+			Some(best) => Some(best.min(time)),
+			None => Some(time),
+		};
+		progress.stars = progress.stars.max(stars);
+	}
+	pub fn total_stars(&self) -> u32 {
+		self.levels
+			.values()
+			.map(|progress| progress.stars as u32)
+			.sum()
+	}
 
 	pub async fn save(&self) -> anyhow::Result<()> {
 		let path = Self::get_fs_path()?;
-		let file = tokio::fs::OpenOptions::new()
-			.write(true)
-			.create(true)
-			.open(&path)
+		let bytes = self.encode()?;
+		tokio::task::spawn_blocking(move || persist::write_atomic(&path, &bytes))
 			.await
-			.with_context(|| {
-				format!(
-					"while opening the file we want to write GameState into ({})",
-					path.display()
-				)
-			})?;
-		let mut file = file.into_std().await;
-
-		bincode::serde::encode_into_std_write(self, &mut file, bincode::config::standard())?;
-		Ok(())
+			.with_context(|| format!("GameState saving task panicked"))?
+			.with_context(|| format!("while saving GameState"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeSet;
+
+	use super::*;
+
+	fn research(ids: &[&str]) -> HashMap<String, ResearchProgress> {
+		let researched = ids.iter().map(|id| id.to_string()).collect::<BTreeSet<_>>();
+		HashMap::from([("free_play".to_string(), ResearchProgress { researched })])
+	}
+
+	#[test]
+	fn loads_v0_tutorial_only() {
+		let state = GameState::decode(include_bytes!("fixtures/v0_tutorial.cgstate")).unwrap();
+		assert_eq!(
+			state,
+			GameState {
+				tutorial_completed: true,
+				..Default::default()
+			}
+		);
+	}
+
+	#[test]
+	fn loads_v0_with_research() {
+		let state = GameState::decode(include_bytes!("fixtures/v0_research.cgstate")).unwrap();
+		assert!(state.tutorial_completed);
+		assert_eq!(state.research, research(&["smelting"]));
+		assert!(state.levels.is_empty());
+	}
+
+	#[test]
+	fn loads_v0_with_levels() {
+		let state = GameState::decode(include_bytes!("fixtures/v0_levels.cgstate")).unwrap();
+		assert_eq!(state.research, research(&["smelting"]));
+		assert_eq!(
+			state.level("coal_run"),
+			LevelProgress {
+				completed: true,
+				best_time: Some(Duration::from_secs(95)),
+				stars: 1,
+			}
+		);
+	}
+
+	#[test]
+	fn loads_v1() {
+		let state = GameState::decode(include_bytes!("fixtures/v1.cgstate")).unwrap();
+		assert_eq!(
+			state.level("coal_run"),
+			LevelProgress {
+				completed: true,
+				best_time: Some(Duration::from_secs(95)),
+				stars: 2,
+			}
+		);
+		assert_eq!(state.playtime, Duration::from_secs(3600));
+	}
+
+	#[test]
+	fn round_trip() {
+		let mut state = GameState {
+			tutorial_completed: true,
+			research: research(&["smelting", "logistics"]),
+			playtime: Duration::from_secs(42),
+			..Default::default()
+		};
+		state.complete_level("coal_run", Duration::from_secs(120), 1);
+		state.complete_level("coal_run", Duration::from_secs(90), 3);
+		state.complete_level("coal_run", Duration::from_secs(100), 2);
+
+		let decoded = GameState::decode(&state.encode().unwrap()).unwrap();
+		assert_eq!(decoded, state);
+		assert_eq!(
+			decoded.level("coal_run").best_time,
+			Some(Duration::from_secs(90))
+		);
+		assert_eq!(decoded.total_stars(), 3);
+	}
+
+	#[test]
+	fn rejects_newer_versions() {
+		let mut bytes = MAGIC.to_vec();
+		bytes.extend_from_slice(&(GAME_STATE_VERSION + 1).to_le_bytes());
+		bytes.extend_from_slice(&GameState::default().encode().unwrap()[6..]);
+		assert!(GameState::decode(&bytes).is_err());
 	}
 }
@@ -0,0 +1,117 @@
+use anyhow::Context;
+use asset_provider::Assets;
+use game_core::{
+	GameData,
+	buildings::{ChannelConsumer, Delivery, EBuilding, PlacedBuilding},
+	codec,
+	maps::{BuildingsMap, Tilemap},
+};
+use textures::Textures;
+use tokio::sync::mpsc;
+
+use super::{LevelMetadata, level_dir_key};
+use crate::game::Game;
+
+/// a level as stored in `levels/{id}/`: the tilemap and buildings from `level.cglf` and everything
+/// else from `level.toml`
+#[derive(Clone, Debug)]
+pub struct Level {
+	pub id: String,
+	pub metadata: LevelMetadata,
+	pub tilemap: Tilemap,
+	/// placed before the buildings of the metadata, which win where they overlap
+	pub buildings: Vec<PlacedBuilding>,
+}
+impl Level {
+	/// loads a level to be played. fails if it has no `[goal]`, as it could never be completed
+	pub async fn load<A: Assets>(assets: &A, id: &str) -> anyhow::Result<Self> {
+		let level = Self::load_scripted(assets, id).await?;
+		level
+			.metadata
+			.goal()
+			.with_context(|| format!("while loading level {id}"))?;
+		Ok(level)
+	}
+	/// loads a level run by a script that sets its own goal, like the tutorial, so its `[goal]`
+	/// can be left out
+	pub async fn load_scripted<A: Assets>(assets: &A, id: &str) -> anyhow::Result<Self> {
+		let metadata = LevelMetadata::load(assets, id).await?;
+
+		let key = format!("{}/level.cglf", level_dir_key(id));
+		let level = assets
+			.asset(&key)
+			.await
+			.with_context(|| format!("while reading level file {key}"))?;
+		let level = codec::decode_level(level.as_slice())
+			.with_context(|| format!("while deserializing level file {key}"))?;
+
+		Ok(Self {
+			id: id.to_string(),
+			metadata,
+			tilemap: level.tilemap,
+			buildings: level.buildings,
+		})
+	}
+
+	/// sets up the level's world: sinks, prebuilt buildings and the starting economy \
+	/// everything delivered into the sinks comes out of the returned receiver
+	pub fn build(&self) -> anyhow::Result<(GameData, mpsc::Receiver<Delivery>)> {
+		let (w, h) = self.tilemap.size();
+		let mut buildings = BuildingsMap::new_default(w, h);
+
+		let mut sink = ChannelConsumer::unconnected();
+		sink.protected = true;
+		for pos in self.metadata.sinks.iter().copied() {
+			let place = buildings
+				.at_mut(pos)
+				.with_context(|| format!("sink at {}, {} is outside of the level", pos.0, pos.1))?;
+			*place = EBuilding::ChannelConsumer(sink.clone());
+		}
+
+		let placed = self.buildings.iter().chain(&self.metadata.buildings);
+		for (i, placed) in placed.enumerate() {
+			placed.place(&mut buildings).with_context(|| {
+				format!("while placing building number {i} of level {}", self.id)
+			})?;
+		}
+
+		// sinks placed by the level file get connected too
+		let (deliveries_tx, deliveries_rx) = mpsc::channel(20);
+		buildings.connect_sinks(deliveries_tx);
+
+		let mut data = GameData::new(self.tilemap.clone(), buildings);
+		if let Some(start) = &self.metadata.start {
+			data = data.with_economy(start.inventory(), start.costs.clone());
+		}
+		Ok((data, deliveries_rx))
+	}
+
+	/// builds the level into a paused game with the level's tools and timer
+	pub fn game(
+		&self,
+		textures: Textures,
+	) -> anyhow::Result<(Game<GameData>, mpsc::Receiver<Delivery>)> {
+		let (data, deliveries_rx) = self.build()?;
+		Ok((self.game_with_data(textures, data), deliveries_rx))
+	}
+	/// like [Self::game], but with the world of a saved session. its sinks get connected to the
+	/// returned receiver
+	pub fn resume(
+		&self,
+		textures: Textures,
+		mut data: GameData,
+	) -> (Game<GameData>, mpsc::Receiver<Delivery>) {
+		let (deliveries_tx, deliveries_rx) = mpsc::channel(20);
+		data.buildings.connect_sinks(deliveries_tx);
+		(self.game_with_data(textures, data), deliveries_rx)
+	}
+	fn game_with_data(&self, textures: Textures, data: GameData) -> Game<GameData> {
+		let mut game = Game::new(textures, data);
+		game.set_allowed_tools(self.metadata.tools.clone());
+		game.pause_time();
+		if let Some(timer) = &self.metadata.timer {
+			game.enable_timer(timer.duration());
+		}
+		game
+	}
+}
@@ -1,6 +1,33 @@
+use std::{collections::HashSet, time::Duration};
+
 use anyhow::Context;
 use asset_provider::Assets;
+use game_core::{
+	CostTable, Inventory, ResourceAmount,
+	buildings::{BuildingKind, PlacedBuilding},
+};
 use serde::{Deserialize, Serialize};
+use utils::toml_text;
+
+use super::GameState;
+use crate::game::Goal;
+
+pub const LEVELS_TOML_KEY: &str = "levels/levels.toml";
+/// the folder of level `id` in the assets, with its `level.toml` and `level.cglf`
+pub fn level_dir_key(id: &str) -> String {
+	format!("levels/{id}")
+}
+
+/// level ids end up in folder names and game states, so they're kept to `a-z`, `0-9` and `_`
+pub fn check_level_id(id: &str) -> anyhow::Result<()> {
+	let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_';
+	if id.is_empty() || !id.chars().all(valid) {
+		return Err(mklogger::anyhow!(
+			"level id {id:?} can only have lowercase letters, digits and underscores"
+		));
+	}
+	Ok(())
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Levels {
@@ -8,25 +35,185 @@ pub struct Levels {
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Campaign {
+	#[serde(default = "Campaign::main_id")]
+	pub id: String,
 	pub tutorial: String,
+	/// in the order they're shown on the level select screen
+	#[serde(default)]
+	pub levels: Vec<CampaignLevel>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CampaignLevel {
+	pub id: String,
+	/// ids of the levels that have to be completed first. may contain the tutorial
+	#[serde(default)]
+	pub requires: Vec<String>,
+}
+
+impl Campaign {
+	fn main_id() -> String {
+		"main".to_string()
+	}
+
+	pub fn level(&self, id: &str) -> Option<&CampaignLevel> {
+		self.levels.iter().find(|level| level.id == id)
+	}
+
+	/// errors on duplicate ids and requirements that aren't levels of this campaign
+	pub fn validate(&self) -> anyhow::Result<()> {
+		let mut ids = HashSet::new();
+		for level in &self.levels {
+			if !ids.insert(level.id.as_str()) {
+				return Err(mklogger::anyhow!(
+					"level {:?} is listed twice in campaign {:?}",
+					level.id,
+					self.id
+				));
+			}
+		}
+		for level in &self.levels {
+			let missing = level
+				.requires
+				.iter()
+				.find(|req| **req != self.tutorial && !ids.contains(req.as_str()));
+			if let Some(missing) = missing {
+				return Err(mklogger::anyhow!(
+					"level {:?} requires {missing:?}, which isn't in campaign {:?}",
+					level.id,
+					self.id
+				));
+			}
+		}
+		Ok(())
+	}
+
+	pub fn is_completed(&self, state: &GameState, id: &str) -> bool {
+		if id == self.tutorial {
+			return state.tutorial_completed;
+		}
+		state.level(id).completed
+	}
+	/// true if every level the level requires is completed
+	pub fn is_unlocked(&self, state: &GameState, id: &str) -> bool {
+		match self.level(id) {
+			Some(level) => level
+				.requires
+				.iter()
+				.all(|req| self.is_completed(state, req)),
+			None => false,
+		}
+	}
 }
 
 impl Levels {
 	pub async fn load<A: Assets>(assets: &A) -> anyhow::Result<Self> {
 		let asset = assets
-			.asset("levels/levels.toml")
+			.asset(LEVELS_TOML_KEY)
 			.await
 			.with_context(|| format!("while reading levels/levels.toml from assets"))?;
 
 		let levels: Levels = toml::from_slice(asset.as_slice())
 			.with_context(|| format!("while parsing levels/level.toml from assets"))?;
+		levels
+			.campaign
+			.validate()
+			.with_context(|| format!("while validating levels/levels.toml"))?;
 		Ok(levels)
 	}
+
+	/// `toml` with `level` added to the end of the campaign, or with the requires of its entry
+	/// updated if it's already in it. None if nothing changes, like for the tutorial \
+	/// the file is edited as text, so the rest of it and its comments stay as they were
+	pub fn register(toml: &str, level: &CampaignLevel) -> anyhow::Result<Option<String>> {
+		check_level_id(&level.id)?;
+		let levels: Levels =
+			toml::from_str(toml).with_context(|| format!("while parsing levels/levels.toml"))?;
+		let old = levels.campaign.level(&level.id);
+		if level.id == levels.campaign.tutorial
+			|| old.is_some_and(|old| old.requires == level.requires)
+		{
+			return Ok(None);
+		}
+
+		let toml = match old {
+			Some(_) => {
+				let start = Self::entry_line(toml, &level.id).with_context(|| {
+					format!(
+						"couldn't find the [[campaign.levels]] entry of {:?}",
+						level.id
+					)
+				})?;
+				let requires = toml::Value::try_from(&level.requires)
+					.with_context(|| format!("while serializing campaign level {:?}", level.id))?;
+				toml_text::set_fields_at(toml, start, &format!("requires = {requires}"))
+			}
+			None => {
+				let entry = toml::to_string(level)
+					.with_context(|| format!("while serializing campaign level {:?}", level.id))?;
+				format!("{}\n\n[[campaign.levels]]\n{entry}", toml.trim_end())
+			}
+		};
+
+		let levels: Levels = toml::from_str(&toml)
+			.with_context(|| format!("while parsing levels/levels.toml with {:?}", level.id))?;
+		levels
+			.campaign
+			.validate()
+			.with_context(|| format!("while validating levels/levels.toml with {:?}", level.id))?;
+		let registered = levels.campaign.level(&level.id);
+		if !registered.is_some_and(|registered| registered.requires == level.requires) {
+			return Err(mklogger::anyhow!(
+				"couldn't write {:?} into levels/levels.toml, edit it by hand",
+				level.id
+			));
+		}
+		Ok(Some(toml))
+	}
+	/// the line of the `[[campaign.levels]]` header of level `id` in `toml`
+	fn entry_line(toml: &str, id: &str) -> Option<usize> {
+		let lines = toml.lines().collect::<Vec<_>>();
+		let is_header = |line: &str| line.trim() == "[[campaign.levels]]";
+		(0..lines.len())
+			.filter(|i| is_header(lines[*i]))
+			.find(|start| {
+				// the entry goes on until the next header
+				let end = lines[start + 1..]
+					.iter()
+					.position(|line| line.trim_start().starts_with('['))
+					.map_or(lines.len(), |len| start + 1 + len);
+				let entry = lines[start + 1..end].join("\n");
+				toml::from_str::<CampaignLevel>(&entry).is_ok_and(|entry| entry.id == id)
+			})
+	}
 }
 
+/// the level.toml format version written by this version of the game
+pub const LEVEL_TOML_VERSION: u32 = 2;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+/// everything in a level's `level.toml`. only `[level]` is required, so version 1 files,
+/// which had nothing else, still load. playing a level needs a `[goal]` too, see [Self::goal]
 pub struct LevelMetadata {
+	#[serde(default = "LevelMetadata::v1")]
+	pub version: u32,
+	/// positions of the protected channel consumers resources have to be delivered into
+	#[serde(default)]
+	pub sinks: Vec<(i32, i32)>,
+	/// the tools the player is allowed to use. the eraser is always allowed, every tool is if not given
+	#[serde(default)]
+	pub tools: Option<Vec<BuildingKind>>,
+
 	pub level: LevelMetadataInner,
+	/// levels without a `[start]` section are free to build in
+	#[serde(default)]
+	pub start: Option<LevelStart>,
+	#[serde(default)]
+	pub timer: Option<LevelTimer>,
+	#[serde(default)]
+	pub goal: Option<Goal>,
+	/// buildings placed before the level starts
+	#[serde(default)]
+	pub buildings: Vec<PlacedBuilding>,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LevelMetadataInner {
@@ -34,9 +221,91 @@ pub struct LevelMetadataInner {
 	pub desc: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelTimer {
+	pub secs: u64,
+}
+impl LevelTimer {
+	pub fn duration(&self) -> Duration {
+		Duration::from_secs(self.secs)
+	}
+}
+
+/// the economy a level starts with
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelStart {
+	#[serde(default)]
+	pub inventory: Vec<ResourceAmount>,
+	/// the standard cost table if not given
+	#[serde(default)]
+	pub costs: CostTable,
+}
+impl LevelStart {
+	pub fn inventory(&self) -> Inventory {
+		Inventory::from_amounts(&self.inventory)
+	}
+}
+
 impl LevelMetadata {
+	fn v1() -> u32 {
+		1
+	}
+
+	/// a level that's free to build in, with every tool allowed
+	pub fn new(name: impl Into<String>, desc: impl Into<String>) -> Self {
+		Self {
+			version: LEVEL_TOML_VERSION,
+			sinks: Vec::new(),
+			tools: None,
+			level: LevelMetadataInner {
+				name: name.into(),
+				desc: desc.into(),
+			},
+			start: None,
+			timer: None,
+			goal: None,
+			buildings: Vec::new(),
+		}
+	}
+
+	/// errors for level.toml files written by a newer version of the game, which might mean
+	/// something this one can't read
+	pub fn check_version(&self) -> anyhow::Result<()> {
+		if self.version > LEVEL_TOML_VERSION {
+			return Err(mklogger::anyhow!(
+				"level.toml version {} is newer than the newest supported version {LEVEL_TOML_VERSION}",
+				self.version
+			));
+		}
+		Ok(())
+	}
+
+	/// the level's `[goal]`. only levels run by a script that sets its own goal, like the tutorial,
+	/// go without one
+	pub fn goal(&self) -> anyhow::Result<&Goal> {
+		self.goal.as_ref().ok_or_else(|| {
+			mklogger::anyhow!("the level has no [goal], so it could never be completed")
+		})
+	}
+
+	/// 3 stars for finishing in the first third of the timer, 2 for the second, 1 after that. \
+	/// levels without a timer always give 3
+	pub fn stars(&self, time: Duration) -> u8 {
+		let Some(timer) = &self.timer else {
+			return 3;
+		};
+		let third = timer.duration() / 3;
+		if time <= third {
+			3
+		} else if time <= third * 2 {
+			2
+		} else {
+			1
+		}
+	}
+
 	pub async fn load<A: Assets>(assets: &A, id: &str) -> anyhow::Result<Self> {
-		let metadata_path = format!("levels/{id}/level.toml");
+		let metadata_path = format!("{}/level.toml", level_dir_key(id));
 		let asset = assets
 			.asset(&metadata_path)
 			.await
@@ -44,6 +313,94 @@ impl LevelMetadata {
 
 		let metadata: LevelMetadata = toml::from_slice(asset.as_slice())
 			.with_context(|| format!("while parsing level metadata at {metadata_path}"))?;
+		metadata
+			.check_version()
+			.with_context(|| format!("while loading level metadata at {metadata_path}"))?;
 		Ok(metadata)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const LEVELS: &str = "
+[campaign]
+tutorial = \"tutorial\"
+
+# the first level
+[[campaign.levels]]
+id = \"coal_run\"
+requires = [\"tutorial\"]
+";
+
+	fn level(id: &str, requires: &[&str]) -> CampaignLevel {
+		let requires = requires.iter().map(|req| req.to_string()).collect();
+		CampaignLevel {
+			id: id.to_string(),
+			requires,
+		}
+	}
+
+	#[test]
+	fn registers_levels() {
+		let toml = Levels::register(LEVELS, &level("smelt", &["coal_run"]))
+			.unwrap()
+			.unwrap();
+		assert!(toml.contains("# the first level"));
+
+		let levels: Levels = toml::from_str(&toml).unwrap();
+		let ids = levels.campaign.levels.iter().map(|level| level.id.as_str());
+		assert_eq!(ids.collect::<Vec<_>>(), ["coal_run", "smelt"]);
+		assert_eq!(levels.campaign.levels[1].requires, ["coal_run"]);
+
+		// already there
+		let again = Levels::register(&toml, &level("smelt", &["coal_run"])).unwrap();
+		assert!(again.is_none());
+		assert!(
+			Levels::register(&toml, &level("tutorial", &[]))
+				.unwrap()
+				.is_none()
+		);
+
+		// already there with other requirements, which are edited in place
+		let toml = Levels::register(&toml, &level("coal_run", &[]))
+			.unwrap()
+			.unwrap();
+		assert!(toml.contains(
+			"# the first level\n[[campaign.levels]]\nid = \"coal_run\"\nrequires = []\n"
+		));
+		let levels: Levels = toml::from_str(&toml).unwrap();
+		assert!(levels.campaign.levels[0].requires.is_empty());
+		assert_eq!(levels.campaign.levels[1].requires, ["coal_run"]);
+
+		assert!(Levels::register(LEVELS, &level("smelt", &["nowhere"])).is_err());
+		assert!(Levels::register(LEVELS, &level("Smelt!", &[])).is_err());
+	}
+
+	#[test]
+	fn levels_without_a_goal_cant_be_played() {
+		let metadata = "version = 2\n[level]\nname = \"Smelt\"\ndesc = \"\"\n";
+		let metadata: LevelMetadata = toml::from_str(metadata).unwrap();
+		assert!(metadata.goal().is_err());
+
+		let goal = "[goal]\nrequirement = { deliver = { resource = \"iron\", amount = 5 } }\n";
+		let metadata = format!("version = 2\n[level]\nname = \"Smelt\"\ndesc = \"\"\n{goal}");
+		let metadata: LevelMetadata = toml::from_str(&metadata).unwrap();
+		assert!(metadata.goal().is_ok());
+	}
+
+	#[test]
+	fn newer_level_tomls_are_refused() {
+		let metadata = "version = 1\n[level]\nname = \"Smelt\"\ndesc = \"\"\n";
+		let metadata: LevelMetadata = toml::from_str(metadata).unwrap();
+		assert!(metadata.check_version().is_ok());
+
+		let metadata = format!(
+			"version = {}\n[level]\nname = \"Smelt\"\ndesc = \"\"\n",
+			LEVEL_TOML_VERSION + 1
+		);
+		let metadata: LevelMetadata = toml::from_str(&metadata).unwrap();
+		assert!(metadata.check_version().is_err());
+	}
+}
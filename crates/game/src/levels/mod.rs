@@ -2,3 +2,7 @@ mod levels;
 pub use levels::*;
 mod game_state;
 pub use game_state::*;
+mod research;
+pub use research::*;
+mod level;
+pub use level::*;
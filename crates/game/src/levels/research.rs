@@ -0,0 +1,134 @@
+use std::{
+	collections::{BTreeSet, HashSet},
+	sync::Arc,
+};
+
+use anyhow::Context;
+use asset_provider::Assets;
+use game_core::{ResourceAmount, buildings::BuildingKind, tool::Tool};
+use serde::{Deserialize, Serialize};
+
+/// the campaign key free play research progress is saved under in [super::GameState]
+pub const FREE_PLAY_CAMPAIGN: &str = "free_play";
+
+/// the research tree, as defined in `research.toml`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResearchTree {
+	#[serde(rename = "node")]
+	pub nodes: Vec<ResearchNode>,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResearchNode {
+	pub id: String,
+	pub name: String,
+	pub desc: String,
+	/// ids of the nodes that have to be researched before this one
+	#[serde(default)]
+	pub requires: Vec<String>,
+	/// paid out of the player's inventory. nodes without cost and requirements are researched from the start
+	#[serde(default)]
+	pub cost: Vec<ResourceAmount>,
+	#[serde(default)]
+	pub unlocks: Vec<BuildingKind>,
+}
+impl ResearchNode {
+	pub fn is_root(&self) -> bool {
+		self.requires.is_empty() && self.cost.is_empty()
+	}
+}
+
+/// the ids of the nodes a player has researched in a campaign
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResearchProgress {
+	pub researched: BTreeSet<String>,
+}
+
+impl ResearchTree {
+	pub async fn load<A: Assets>(assets: &A) -> anyhow::Result<Self> {
+		let asset = assets
+			.asset("research.toml")
+			.await
+			.with_context(|| format!("while reading research.toml from assets"))?;
+
+		let tree: ResearchTree = toml::from_slice(asset.as_slice())
+			.with_context(|| format!("while parsing research.toml from assets"))?;
+		tree.validate()
+			.with_context(|| format!("while validating research.toml"))?;
+		Ok(tree)
+	}
+
+	/// errors on duplicate ids and requirements that don't exist
+	pub fn validate(&self) -> anyhow::Result<()> {
+		let mut ids = HashSet::new();
+		for node in &self.nodes {
+			if !ids.insert(node.id.as_str()) {
+				return Err(mklogger::anyhow!(
+					"duplicate research node id {:?}",
+					node.id
+				));
+			}
+		}
+		for node in &self.nodes {
+			if let Some(missing) = node.requires.iter().find(|req| !ids.contains(req.as_str())) {
+				return Err(mklogger::anyhow!(
+					"research node {:?} requires {missing:?}, which doesn't exist",
+					node.id
+				));
+			}
+		}
+		Ok(())
+	}
+
+	pub fn node(&self, id: &str) -> Option<&ResearchNode> {
+		self.nodes.iter().find(|node| node.id == id)
+	}
+
+	pub fn is_researched(&self, progress: &ResearchProgress, id: &str) -> bool {
+		let root = self.node(id).map(ResearchNode::is_root).unwrap_or(false);
+		root || progress.researched.contains(id)
+	}
+	/// true if every requirement is met, but the node itself isn't researched yet
+	pub fn is_available(&self, progress: &ResearchProgress, id: &str) -> bool {
+		let Some(node) = self.node(id) else {
+			return false;
+		};
+		!self.is_researched(progress, id)
+			&& node
+				.requires
+				.iter()
+				.all(|req| self.is_researched(progress, req))
+	}
+
+	/// the eraser is always unlocked
+	pub fn is_unlocked(&self, progress: &ResearchProgress, building: BuildingKind) -> bool {
+		if building == BuildingKind::Nothing {
+			return true;
+		}
+		self.nodes
+			.iter()
+			.filter(|node| node.unlocks.contains(&building))
+			.any(|node| self.is_researched(progress, &node.id))
+	}
+	pub fn is_tool_unlocked(&self, progress: &ResearchProgress, tool: &Tool) -> bool {
+		self.is_unlocked(progress, tool.building().kind())
+	}
+}
+
+/// a research tree together with the player's progress in it
+#[derive(Clone, Debug)]
+pub struct Research {
+	pub tree: Arc<ResearchTree>,
+	pub progress: ResearchProgress,
+}
+impl Research {
+	pub fn new(tree: ResearchTree, progress: ResearchProgress) -> Self {
+		Self {
+			tree: Arc::new(tree),
+			progress,
+		}
+	}
+
+	pub fn is_tool_unlocked(&self, tool: &Tool) -> bool {
+		self.tree.is_tool_unlocked(&self.progress, tool)
+	}
+}
@@ -4,6 +4,7 @@ pub mod assets;
 pub mod comp;
 pub mod game;
 pub mod levels;
+pub mod saves;
 pub mod scripts;
 pub mod utils;
 
@@ -74,8 +75,15 @@ pub async fn start() {
 	let stage = stage_manager::Stage::from_dyn_layable(scripts::main::main().await);
 	let mut ctx = sui_runner::Context::new(stage, rl, thread);
 
+	let started = std::time::Instant::now();
 	ctx.start();
 
+	let playtime = started.elapsed();
+	let saved = levels::GameState::modify(|game_state| game_state.playtime += playtime);
+	if let Err(err) = saved.await {
+		mklogger::eprintln!("failed to save playtime: {err:?}");
+	}
+
 	if let Some(textures) = textures::clear_cache().await {
 		std::mem::drop(textures);
 	};
@@ -0,0 +1,478 @@
+// the managed saves directory: free play saves, autosaves and the level in progress the player left
+
+use std::{
+	fs,
+	io::Cursor,
+	path::{Path, PathBuf},
+	sync::{Mutex, MutexGuard},
+	time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use utils::persist;
+
+use crate::{
+	game::{GameData, GameDataSave, GameSnapshot, Goal, Timer, Tool, goal::Deliveries},
+	world::ticks_duration,
+};
+
+/// the first bytes of every session save
+const SESSION_MAGIC: &[u8; 4] = b"CGSS";
+/// the version written by this version of the game. bump it whenever [SessionState] changes
+pub const SESSION_VERSION: u16 = 1;
+
+/// how many autosaves are kept. a new one replaces the oldest
+pub const AUTOSAVE_SLOTS: usize = 3;
+
+/// where the game's own saves go. in the user's data directory for release builds
+pub fn saves_dir() -> anyhow::Result<PathBuf> {
+	if !cfg!(debug_assertions) {
+		let data =
+			dirs::data_dir().with_context(|| format!("failed to get user's data directory"))?;
+
+		Ok(data.join("conveyor-game").join("saves"))
+	} else {
+		Ok(std::env::current_dir()?.join("conveyor-game-saves"))
+	}
+}
+/// the level the player left last, continued from the main menu
+pub fn session_path() -> anyhow::Result<PathBuf> {
+	Ok(saves_dir()?.join("session.cgss"))
+}
+/// the slot F5 saves into and F9 loads from
+pub fn quicksave_path() -> anyhow::Result<PathBuf> {
+	Ok(saves_dir()?.join("quicksave.cgs"))
+}
+
+/// saves are written one at a time, so two autosaves never pick the same slot
+static WRITING: Mutex<()> = Mutex::new(());
+fn lock_writing() -> MutexGuard<'static, ()> {
+	WRITING
+		.lock()
+		.unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// serializes `data` into a .cgs at `path`. blocks, so it should run off the render thread
+pub fn write_save(path: &Path, data: &GameData) -> anyhow::Result<()> {
+	let _writing = lock_writing();
+	write_save_unlocked(path, data)
+}
+fn write_save_unlocked(path: &Path, data: &GameData) -> anyhow::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)
+			.with_context(|| format!("while creating {}", parent.display()))?;
+	}
+
+	let mut bytes = Vec::new();
+	GameDataSave::new(data)?.save(&mut bytes)?;
+	persist::write_atomic(path, &bytes)
+}
+/// loads any .cgs, falling back to its backup if it can't be read
+pub async fn load_save(path: PathBuf) -> anyhow::Result<GameData> {
+	let decode = |bytes: &[u8]| GameDataSave::load_as_either(&mut Cursor::new(bytes))?.take();
+	let display = path.display().to_string();
+	tokio::task::spawn_blocking(move || persist::read_with_backup(&path, decode))
+		.await
+		.with_context(|| format!("save loading task panicked"))?
+		.with_context(|| format!("while loading save {display}"))
+}
+/// removes the file along with the backup [persist::write_atomic] keeps of it
+pub async fn remove_with_backup(path: &Path) -> anyhow::Result<()> {
+	for path in [path.to_path_buf(), persist::backup_path(path)] {
+		match tokio::fs::remove_file(&path).await {
+			Ok(()) => {}
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+			Err(err) => {
+				return Err(err).with_context(|| format!("while removing {}", path.display()));
+			}
+		}
+	}
+	Ok(())
+}
+
+// --- the save browser
+
+/// a save in the saves directory, as listed by the save browser
+#[derive(Clone, Debug)]
+pub struct SaveInfo {
+	pub path: PathBuf,
+	/// the file name without the extension
+	pub name: String,
+	pub modified: SystemTime,
+	/// None if the save couldn't be read
+	pub summary: Option<SaveSummary>,
+}
+#[derive(Clone, Debug)]
+pub struct SaveSummary {
+	/// the simulation time the world has been running for
+	pub playtime: Duration,
+	pub world_size: (usize, usize),
+}
+
+fn summarize(path: &Path) -> anyhow::Result<SaveSummary> {
+	let mut file = fs::File::open(path)?;
+	let save = GameDataSave::load_as_either(&mut file)?;
+	Ok(SaveSummary {
+		playtime: ticks_duration(save.ticks),
+		world_size: save.world_size(),
+	})
+}
+fn list_saves_in(dir: &Path) -> anyhow::Result<Vec<SaveInfo>> {
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(err) => return Err(err).with_context(|| format!("while listing {}", dir.display())),
+	};
+
+	let mut saves = Vec::new();
+	for entry in entries {
+		let path = entry?.path();
+		if path.extension().is_none_or(|ext| ext != "cgs") {
+			continue;
+		}
+		let Some(name) = path
+			.file_stem()
+			.map(|name| name.to_string_lossy().into_owned())
+		else {
+			continue;
+		};
+		let modified = fs::metadata(&path)?.modified()?;
+
+		let summary = match summarize(&path) {
+			Ok(summary) => Some(summary),
+			Err(err) => {
+				mklogger::eprintln!("failed to read save {}:\n{err:?}", path.display());
+				None
+			}
+		};
+		saves.push(SaveInfo {
+			path,
+			name,
+			modified,
+			summary,
+		});
+	}
+	saves.sort_by(|a, b| b.modified.cmp(&a.modified));
+	Ok(saves)
+}
+/// every .cgs in the saves directory, newest first
+pub async fn list_saves() -> anyhow::Result<Vec<SaveInfo>> {
+	let dir = saves_dir()?;
+	tokio::task::spawn_blocking(move || list_saves_in(&dir))
+		.await
+		.with_context(|| format!("save listing task panicked"))?
+}
+
+/// the path of the save called `name` in the saves directory. names can't leave the directory
+pub fn named_save_path(name: &str) -> anyhow::Result<PathBuf> {
+	let name = name.trim();
+	if name.is_empty() {
+		return Err(mklogger::anyhow!("save names can't be empty"));
+	}
+	if name.starts_with('.') || name.contains(['/', '\\', ':']) {
+		return Err(mklogger::anyhow!("{name:?} can't be used as a save name"));
+	}
+	Ok(saves_dir()?.join(format!("{name}.cgs")))
+}
+/// the first `save-{n}` that isn't taken yet
+pub fn unused_save_name() -> anyhow::Result<String> {
+	let dir = saves_dir()?;
+	let name = (1..)
+		.map(|n| format!("save-{n}"))
+		.find(|name| !dir.join(format!("{name}.cgs")).exists())
+		.expect("ran out of save names");
+	Ok(name)
+}
+/// renames the save at `from` to `name`, along with its backup. fails if `name` is taken
+pub async fn rename_save(from: &Path, name: &str) -> anyhow::Result<PathBuf> {
+	let to = named_save_path(name)?;
+	if tokio::fs::try_exists(&to).await? {
+		return Err(mklogger::anyhow!("there's already a save called {name}"));
+	}
+
+	tokio::fs::rename(from, &to)
+		.await
+		.with_context(|| format!("while renaming {} to {}", from.display(), to.display()))?;
+	let backup = persist::backup_path(from);
+	if tokio::fs::try_exists(&backup).await? {
+		tokio::fs::rename(&backup, persist::backup_path(&to))
+			.await
+			.with_context(|| format!("while renaming the backup of {}", from.display()))?;
+	}
+	Ok(to)
+}
+
+/// formats the time as a UTC date, like `2025-03-14 09:26`
+pub fn format_date(time: SystemTime) -> String {
+	let secs = time
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.map(|since| since.as_secs())
+		.unwrap_or(0);
+	let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+	// days since the epoch into a civil date, from http://howardhinnant.github.io/date_algorithms.html
+	let z = days as i64 + 719468;
+	let era = z.div_euclid(146097);
+	let day_of_era = z.rem_euclid(146097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	let (hour, minute) = (secs_of_day / 3600, secs_of_day % 3600 / 60);
+	format!("{year}-{month:02}-{day:02} {hour:02}:{minute:02}")
+}
+
+// --- autosaves
+
+fn autosave_path_in(dir: &Path, slot: usize) -> PathBuf {
+	dir.join(format!("autosave-{slot}.cgs"))
+}
+/// every autosave in `dir` with when it was written, newest first
+fn autosaves_in(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+	let mut autosaves = (0..AUTOSAVE_SLOTS)
+		.map(|slot| autosave_path_in(dir, slot))
+		.filter_map(|path| {
+			let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+			Some((path, modified))
+		})
+		.collect::<Vec<_>>();
+	autosaves.sort_by(|(_, a), (_, b)| b.cmp(a));
+	autosaves
+}
+/// the first empty slot, or the one with the oldest autosave if they're all taken
+fn next_autosave_path_in(dir: &Path) -> PathBuf {
+	let empty = (0..AUTOSAVE_SLOTS)
+		.map(|slot| autosave_path_in(dir, slot))
+		.find(|path| !path.exists());
+	match empty {
+		Some(path) => path,
+		None => match autosaves_in(dir).pop() {
+			Some((oldest, _)) => oldest,
+			None => autosave_path_in(dir, 0),
+		},
+	}
+}
+
+/// the autosave written last, if there's any
+pub fn newest_autosave() -> anyhow::Result<Option<PathBuf>> {
+	let dir = saves_dir()?;
+	let newest = autosaves_in(&dir).into_iter().next();
+	Ok(newest.map(|(path, _)| path))
+}
+/// serializes `data` into the next autosave slot. blocks, so it should run off the render thread
+pub fn write_autosave(data: &GameData) -> anyhow::Result<PathBuf> {
+	let dir = saves_dir()?;
+	let _writing = lock_writing();
+
+	let path = next_autosave_path_in(&dir);
+	write_save_unlocked(&path, data)?;
+	Ok(path)
+}
+
+/// everything about a level in progress that isn't the world itself
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionState {
+	/// the id of the level being played
+	pub level: String,
+	pub timer: Option<Timer>,
+	pub goal: Goal,
+	pub deliveries: Deliveries,
+	pub camera_at: (f32, f32),
+	pub scale: f32,
+	pub tool: Tool,
+}
+
+/// a level in progress. written as [SESSION_MAGIC], the version as a little endian u16, the
+/// bincode of the [SessionState], then the world as a whole .cgs so it goes through the save
+/// migrations like any other save
+#[derive(Clone, Debug)]
+pub struct SessionSave {
+	pub state: SessionState,
+	pub data: GameDataSave,
+}
+impl SessionSave {
+	pub fn new(
+		level: String,
+		snapshot: GameSnapshot,
+		goal: Goal,
+		deliveries: Deliveries,
+	) -> anyhow::Result<Self> {
+		let data = GameDataSave::new(&snapshot.data)?;
+		let state = SessionState {
+			level,
+			timer: snapshot.timer,
+			goal,
+			deliveries,
+			camera_at: snapshot.camera_at,
+			scale: snapshot.scale,
+			tool: snapshot.tool,
+		};
+		Ok(Self { state, data })
+	}
+
+	pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(SESSION_MAGIC);
+		bytes.extend_from_slice(&SESSION_VERSION.to_le_bytes());
+		bincode::serde::encode_into_std_write(&self.state, &mut bytes, bincode::config::standard())
+			.with_context(|| format!("while encoding SessionState"))?;
+		self.data.save(&mut bytes)?;
+		Ok(bytes)
+	}
+	pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+		let rest = bytes
+			.strip_prefix(SESSION_MAGIC)
+			.ok_or_else(|| mklogger::anyhow!("not a session save"))?;
+		let (version, payload) = match rest {
+			[a, b, payload @ ..] => (u16::from_le_bytes([*a, *b]), payload),
+			_ => return Err(mklogger::anyhow!("session save header is cut short")),
+		};
+		if version != SESSION_VERSION {
+			return Err(mklogger::anyhow!(
+				"session save version {version} isn't supported, the newest known version is {SESSION_VERSION}"
+			));
+		}
+
+		let (state, read) = bincode::serde::decode_from_slice(payload, bincode::config::standard())
+			.with_context(|| format!("while decoding SessionState"))?;
+		let data = GameDataSave::load(&mut Cursor::new(&payload[read..]))
+			.with_context(|| format!("while loading the world of the session"))?;
+		Ok(Self { state, data })
+	}
+
+	/// falls back to the backup of the previous save if the file can't be read
+	pub async fn load(path: PathBuf) -> anyhow::Result<Self> {
+		tokio::task::spawn_blocking(move || persist::read_with_backup(&path, Self::decode))
+			.await
+			.with_context(|| format!("session loading task panicked"))?
+			.with_context(|| format!("while loading session"))
+	}
+	/// blocks until the save is on disk
+	pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)
+				.with_context(|| format!("while creating {}", parent.display()))?;
+		}
+		let bytes = self.encode()?;
+		persist::write_atomic(path, &bytes)
+	}
+
+	pub async fn exists(path: &Path) -> bool {
+		tokio::fs::try_exists(path).await.unwrap_or(false)
+	}
+	/// removes the save along with its backup, so it can't be loaded again
+	pub async fn remove(path: &Path) -> anyhow::Result<()> {
+		remove_with_backup(path).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use game_core::{
+		GAME_TICK_FREQUENCY, GameData, ResourceKind,
+		maps::{BuildingsMap, Tilemap, TilemapExt},
+	};
+
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		let data = GameData::new(Tilemap::stone(3, 2), BuildingsMap::new_default(3, 2));
+		let mut timer = Timer::new(Duration::from_secs(60));
+		timer.advance(Duration::from_secs(12));
+		let snapshot = GameSnapshot {
+			data,
+			timer: Some(timer),
+			camera_at: (1.5, 0.5),
+			scale: 3.0,
+			tool: Tool::default(),
+		};
+		let mut deliveries = Deliveries::new(0);
+		deliveries.record(ResourceKind::Coal, 40);
+		let goal = Goal::deliver_all([(ResourceKind::Coal, 20)]);
+
+		let save =
+			SessionSave::new("coal_run".to_string(), snapshot, goal.clone(), deliveries).unwrap();
+		let loaded = SessionSave::decode(&save.encode().unwrap()).unwrap();
+
+		assert_eq!(loaded.state.level, "coal_run");
+		assert_eq!(loaded.state.goal, goal);
+		assert_eq!(loaded.state.deliveries.total(ResourceKind::Coal), 1);
+		assert_eq!(loaded.state.deliveries.now(), 40);
+		assert_eq!(
+			loaded.state.timer.map(|timer| timer.elapsed()),
+			Some(Duration::from_secs(12))
+		);
+		assert_eq!(loaded.state.camera_at, (1.5, 0.5));
+		assert_eq!(loaded.data.take().unwrap().world_size(), (3, 2));
+	}
+
+	#[test]
+	fn autosaves_rotate() {
+		let dir = std::env::temp_dir().join(format!("game-autosaves-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+
+		let start = SystemTime::now();
+		for i in 0..AUTOSAVE_SLOTS + 2 {
+			let path = next_autosave_path_in(&dir);
+			let file = fs::File::create(&path).unwrap();
+			// mtimes can be too coarse to tell saves written right after each other apart
+			file.set_modified(start + Duration::from_secs(i as u64))
+				.unwrap();
+		}
+
+		let autosaves = autosaves_in(&dir);
+		assert_eq!(autosaves.len(), AUTOSAVE_SLOTS);
+		// the fourth and fifth saves went into the first two slots
+		assert_eq!(autosaves[0].0, autosave_path_in(&dir, 1));
+		assert_eq!(autosaves[1].0, autosave_path_in(&dir, 0));
+		assert_eq!(next_autosave_path_in(&dir), autosave_path_in(&dir, 2));
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn lists_saves() {
+		let dir = std::env::temp_dir().join(format!("game-saves-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+
+		let mut data = GameData::new(Tilemap::stone(4, 3), BuildingsMap::new_default(4, 3));
+		for _ in 0..10 {
+			data.tick();
+		}
+		write_save(&dir.join("factory.cgs"), &data).unwrap();
+		fs::write(dir.join("broken.cgs"), b"not a save").unwrap();
+		fs::write(dir.join("notes.txt"), b"not a save either").unwrap();
+
+		let mut saves = list_saves_in(&dir).unwrap();
+		saves.sort_by(|a, b| a.name.cmp(&b.name));
+		assert_eq!(saves.len(), 2);
+		assert_eq!(saves[0].name, "broken");
+		assert!(saves[0].summary.is_none());
+
+		let summary = saves[1].summary.as_ref().unwrap();
+		assert_eq!(summary.world_size, (4, 3));
+		assert_eq!(summary.playtime, GAME_TICK_FREQUENCY * 10);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn formats_dates() {
+		let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+		assert_eq!(format_date(at(0)), "1970-01-01 00:00");
+		assert_eq!(format_date(at(951_827_696)), "2000-02-29 12:34");
+		assert_eq!(format_date(at(1_741_944_360)), "2025-03-14 09:26");
+	}
+
+	#[test]
+	fn rejects_other_files() {
+		assert!(SessionSave::decode(b"CGSV\x02\x00").is_err());
+		assert!(SessionSave::decode(b"CGSS").is_err());
+	}
+}
@@ -0,0 +1,340 @@
+use std::{borrow::Cow, fmt::Debug, time::Duration};
+
+use anyhow::Context;
+use game_core::{GameData, ticks_duration};
+use rust_i18n::t;
+use stage_manager::StageChange;
+use stage_manager_loaders::Loader;
+use stage_manager_remote::{RemoteStageChange, StageSyncWrap};
+use sui::{DynamicLayable, Layable, LayableExt};
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+	assets::GameAssets,
+	comp::handle_result,
+	game::{
+		Game, GameCommand, GameRunner, GameSnapshot, Goal, GoalStatus,
+		goal::{Deliveries, ResourceCounter},
+	},
+	levels::{GameState, Level},
+	saves::{self, SessionSave},
+	scripts::{
+		level_select::{format_time, level_select},
+		tips::{action, text_with_actions, text_with_actions_fullscreen},
+	},
+	textures,
+};
+
+/// how often the controller looks at the timer when nothing gets delivered
+const TIMER_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Debug)]
+pub enum LevelPage {
+	Start,
+	Retry,
+	Menu,
+}
+
+/// where a level starts from
+#[derive(Debug)]
+enum LevelStart {
+	New(String),
+	Session(Box<SessionSave>),
+}
+
+/// loads and plays any level in `levels/{id}/`, driven only by its `level.toml`
+pub fn level(id: impl Into<String>) -> DynamicLayable<'static> {
+	load_level(LevelStart::New(id.into()))
+}
+/// continues a level the player left, from its session save
+pub fn resume_level(save: SessionSave) -> DynamicLayable<'static> {
+	load_level(LevelStart::Session(Box::new(save)))
+}
+
+fn load_level(start: LevelStart) -> DynamicLayable<'static> {
+	let loader = textures::load_as_layable(GameAssets::default(), move |textures| {
+		let f = move || {
+			let textures = textures?;
+
+			let loader = Loader::new(
+				sui::text(t!("level.loading"), 16).centered(),
+				async move { assemble_level(textures, start).await },
+				|level| StageChange::Simple(handle_result(level)),
+			);
+			anyhow::Ok(DynamicLayable::new_only_debug(loader))
+		};
+
+		handle_result(f())
+	});
+	DynamicLayable::new_only_debug(loader)
+}
+
+async fn assemble_level(
+	textures: textures::Textures,
+	start: LevelStart,
+) -> anyhow::Result<impl Layable + Debug> {
+	let assets = GameAssets::default();
+	let resumed = matches!(start, LevelStart::Session(_));
+
+	let (level, mut game, mut goal) = match start {
+		LevelStart::New(id) => {
+			let level = Level::load(&assets, &id).await?;
+			let (game, deliveries_rx) = level.game(textures.clone())?;
+			let goal = level.metadata.goal()?.clone();
+			let goal = ResourceCounter::new(goal, deliveries_rx);
+			(level, game, goal)
+		}
+		LevelStart::Session(save) => {
+			let SessionSave { state, data } = *save;
+			let level = Level::load(&assets, &state.level).await?;
+			let data = data
+				.take()
+				.with_context(|| format!("while loading the session of level {}", level.id))?;
+
+			let (mut game, deliveries_rx) = level.resume(textures.clone(), data);
+			game.restore_snapshot_view(state.timer, state.camera_at, state.scale, state.tool);
+			let goal =
+				ResourceCounter::new(state.goal, deliveries_rx).with_deliveries(state.deliveries);
+			(level, game, goal)
+		}
+	};
+	let display_tx = game.enable_goal_display::<()>().0;
+	goal.enable_display_tx(textures, display_tx);
+
+	let (progress_tx, progress_rx) = watch::channel(Some(GoalProgress::of(&goal)));
+	game.enable_exit_handler(session_saver(level.id.clone(), progress_rx));
+
+	let (master_tx, master_rx) = mpsc::channel(5);
+	let (mut game, game_tx) = GameRunner::new(game);
+
+	game.enable_tips_spawn(|stage_tx, stage_rx| {
+		let channels = LevelChannels {
+			level,
+			resumed,
+			goal,
+			progress_tx,
+			master_tx,
+			stage_tx,
+			stage_rx,
+			game_tx,
+		};
+		controller(channels)
+	});
+
+	let game = StageSyncWrap::assemble(game, master_rx);
+	Ok(game)
+}
+
+/// what the controller knows about the goal, kept up to date for the session save
+#[derive(Clone, Debug)]
+struct GoalProgress {
+	goal: Goal,
+	deliveries: Deliveries,
+}
+impl GoalProgress {
+	fn of(counter: &ResourceCounter) -> Self {
+		Self {
+			goal: counter.goal().clone(),
+			deliveries: counter.deliveries().clone(),
+		}
+	}
+}
+
+/// saves the level into the session slot when the player leaves it. written before the main menu
+/// loads, so its continue button already sees it
+fn session_saver(
+	level: String,
+	progress_rx: watch::Receiver<Option<GoalProgress>>,
+) -> impl FnMut(GameSnapshot) + Send + 'static {
+	move |snapshot| {
+		// None once the level is over, there's nothing to continue then
+		let Some(progress) = progress_rx.borrow().clone() else {
+			return;
+		};
+
+		let f = || {
+			let save =
+				SessionSave::new(level.clone(), snapshot, progress.goal, progress.deliveries)?;
+			save.write(&saves::session_path()?)
+		};
+		match f() {
+			Ok(()) => mklogger::println!("saved the session of level {level}"),
+			Err(err) => {
+				mklogger::eprintln!("failed to save the session of level {level}:\n{err:?}")
+			}
+		}
+	}
+}
+
+/// removes the session save if it's of this level, so it can't be continued after being finished
+async fn remove_session_of(id: &str) -> anyhow::Result<()> {
+	let path = saves::session_path()?;
+	if !SessionSave::exists(&path).await {
+		return Ok(());
+	}
+	let save = SessionSave::load(path.clone()).await?;
+	if save.state.level == id {
+		SessionSave::remove(&path).await?;
+	}
+	Ok(())
+}
+
+#[derive(Debug)]
+struct LevelChannels {
+	level: Level,
+	/// continued from a session save instead of started fresh
+	resumed: bool,
+	goal: ResourceCounter,
+	progress_tx: watch::Sender<Option<GoalProgress>>,
+
+	master_tx: mpsc::Sender<RemoteStageChange>,
+	stage_tx: mpsc::Sender<RemoteStageChange>,
+	stage_rx: mpsc::Receiver<LevelPage>,
+	game_tx: mpsc::Sender<GameCommand<GameData>>,
+}
+impl LevelChannels {
+	async fn page(
+		&mut self,
+		text: impl Into<Cow<'static, str>>,
+		actions: impl IntoIterator<Item = (Cow<'static, str>, LevelPage)>,
+	) -> anyhow::Result<LevelPage> {
+		let actions = actions.into_iter().map(|(name, page)| action(name, page));
+		self.stage_tx
+			.send(text_with_actions_fullscreen(text, actions))
+			.await
+			.map_err(|err| mklogger::anyhow!("error while sending stage change:\n{err}"))?;
+
+		self.stage_rx
+			.recv()
+			.await
+			.ok_or_else(|| mklogger::anyhow!("expected to receive LevelPage from stage_rx"))
+	}
+	/// a page without actions, that doesn't cover the level
+	async fn small_page(&mut self, text: impl Into<Cow<'static, str>>) -> anyhow::Result<()> {
+		self.stage_tx
+			.send(text_with_actions::<LevelPage>(text, []))
+			.await
+			.map_err(|err| mklogger::anyhow!("error while sending stage change:\n{err}"))
+	}
+
+	async fn game_with_return<
+		R: Debug + Send + 'static,
+		F: FnOnce(&mut Game<GameData>) -> R + Send + 'static,
+	>(
+		&mut self,
+		f: F,
+	) -> anyhow::Result<R> {
+		let (command, rx) = GameCommand::new_return(f);
+		self.game_tx
+			.send(command)
+			.await
+			.map_err(|err| mklogger::anyhow!("channel.game_with_return failed:\n{err}"))?;
+
+		let ret = rx.await?;
+		Ok(ret)
+	}
+
+	async fn change_master<L: Layable + Debug + 'static>(
+		&mut self,
+		stage: L,
+	) -> anyhow::Result<()> {
+		self.master_tx
+			.send(RemoteStageChange::simple_only_debug(stage))
+			.await
+			.map_err(|err| mklogger::anyhow!("{err}"))
+	}
+}
+
+async fn controller(mut channels: LevelChannels) {
+	if let Err(err) = play(&mut channels).await {
+		mklogger::eprintln!("level controller caught an error:\n{err}")
+	}
+}
+
+async fn play(channels: &mut LevelChannels) -> anyhow::Result<()> {
+	let metadata = channels.level.metadata.level.clone();
+	let intro = format!("{}\n{}", metadata.name, metadata.desc);
+	let start = match channels.resumed {
+		true => t!("level.continue"),
+		false => t!("level.start"),
+	};
+	channels.page(intro, [(start, LevelPage::Start)]).await?;
+	channels.small_page(metadata.name).await?;
+	channels.game_with_return(|game| game.resume_time()).await?;
+
+	let status = loop {
+		tokio::select! {
+			res = channels.goal.tick_next() => res?,
+			_ = tokio::time::sleep(TIMER_POLL) => {},
+		};
+		channels.goal.tick()?;
+
+		let (timer, ticks) = channels
+			.game_with_return(|game| (game.timer().cloned(), game.data().ticks()))
+			.await?;
+		channels.goal.check_ticks(ticks);
+		channels.goal.render_tick().await?;
+		channels
+			.progress_tx
+			.send_replace(Some(GoalProgress::of(&channels.goal)));
+
+		let out_of_time = timer.map(|timer| timer.is_finished()).unwrap_or(false);
+		match channels.goal.status() {
+			GoalStatus::Pending if out_of_time => break GoalStatus::Failed,
+			GoalStatus::Pending => continue,
+			status => break status,
+		}
+	};
+	let ticks = channels
+		.game_with_return(|game| {
+			game.pause_time();
+			game.data().ticks()
+		})
+		.await?;
+
+	// the level is over, leaving it shouldn't save it to be continued
+	channels.progress_tx.send_replace(None);
+	if let Err(err) = remove_session_of(&channels.level.id).await {
+		mklogger::eprintln!("failed to remove the finished session:\n{err:?}");
+	}
+
+	let event = match status {
+		GoalStatus::Reached => {
+			let time = ticks_duration(ticks);
+			let stars = channels.level.metadata.stars(time);
+			let id = &channels.level.id;
+			GameState::modify(|game_state| game_state.complete_level(id, time, stars))
+				.await
+				.with_context(|| format!("while saving completion of level {id}"))?;
+
+			channels
+				.page(
+					t!("level.complete", time = format_time(time), stars = stars),
+					[(t!("level.back-to-levels"), LevelPage::Menu)],
+				)
+				.await?
+		}
+		_ => {
+			channels
+				.page(
+					t!("level.failed"),
+					[
+						(t!("level.retry"), LevelPage::Retry),
+						(t!("level.back-to-levels"), LevelPage::Menu),
+					],
+				)
+				.await?
+		}
+	};
+
+	match event {
+		LevelPage::Retry => {
+			let retry = level(channels.level.id.clone());
+			channels.change_master(retry).await
+		}
+		_ => {
+			let levels = level_select().await;
+			channels.change_master(levels).await
+		}
+	}
+}
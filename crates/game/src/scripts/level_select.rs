@@ -0,0 +1,78 @@
+use std::{fmt::Debug, time::Duration};
+
+use rust_i18n::t;
+use stage_manager::StageChange;
+use sui::{DynamicLayable, Layable, LayableExt, core::ReturnEvent};
+
+use crate::{
+	assets::GameAssets,
+	comp::handle_result,
+	levels::{GameState, LevelMetadata, Levels},
+	scripts::{level::level, main::main_menu},
+};
+
+/// lists the levels of the campaign, with their completion state and best time
+pub async fn level_select() -> DynamicLayable<'static> {
+	handle_result(level_select_page().await)
+}
+
+async fn level_select_page() -> anyhow::Result<impl Layable + Debug> {
+	let assets = GameAssets::default();
+	let levels = Levels::load(&assets).await?;
+	let campaign = levels.campaign;
+	let game_state = GameState::load().await;
+
+	let mut rows = Vec::new();
+	for campaign_level in &campaign.levels {
+		let id = campaign_level.id.clone();
+		let metadata = LevelMetadata::load(&assets, &id).await?;
+
+		let unlocked = campaign.is_unlocked(&game_state, &id);
+		let progress = game_state.level(&id);
+		let status = match (unlocked, progress.best_time) {
+			(false, _) => t!("level-select.locked"),
+			(true, Some(best_time)) if progress.completed => t!(
+				"level-select.completed",
+				time = format_time(best_time),
+				stars = progress.stars
+			),
+			(true, _) => t!("level-select.not-completed"),
+		};
+
+		let play = comp_extra::button_explicit(t!("level-select.play"), !unlocked, move || {
+			ReturnEvent::new(StageChange::Simple(level(id.clone())))
+		});
+
+		let text = sui::div([
+			sui::Text::new(metadata.level.name, 24).into_comp(),
+			sui::Text::new(metadata.level.desc, 16).into_comp(),
+			sui::Text::new(status, 16).into_comp(),
+		]);
+		let row = sui::div_h([
+			sui::custom_only_debug(text.margin(4)),
+			sui::custom_only_debug(play.center_y()),
+		]);
+		rows.push(sui::custom_only_debug(row.margin(4)));
+	}
+
+	let back = comp_extra::button_explicit(t!("level-select.back"), false, || {
+		let loader = stage_manager_loaders::Loader::new_invisible(main_menu(), |menu| {
+			StageChange::simple_only_debug(menu)
+		});
+		ReturnEvent::new(loader)
+	});
+
+	let title = sui::Text::new(t!("level-select.title"), 32).margin(32);
+	let page = sui::div([
+		sui::custom_only_debug(title.center_x()),
+		sui::custom_only_debug(sui::div(rows).restrict_to_size().center_x()),
+		sui::custom_only_debug(back.margin(8).center_x()),
+	]);
+	Ok(page)
+}
+
+/// formats the time as minutes:seconds
+pub fn format_time(time: Duration) -> String {
+	let secs = time.as_secs();
+	format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -1,21 +1,35 @@
-use std::{fmt::Debug, io::Seek, ops::Deref, path::PathBuf, sync::Arc};
+use std::{
+	fmt::Debug,
+	io::Cursor,
+	ops::Deref,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
 
 use anyhow::Context;
 use asset_provider::Assets;
 use stage_manager::StageChange;
 use sui::{DynamicLayable, Layable, LayableExt, core::ReturnEvent};
 use tokio::sync::Mutex;
+use utils::persist;
 
 use crate::{
 	assets::GameAssets,
 	comp::{err_page, handle_err, handle_result_dyn},
-	game::{Game, GameData, GameDataSave},
-	levels::GameState,
-	scripts::tutorial,
+	game::{Game, GameData, GameDataSave, SaveHotkey},
+	levels::{FREE_PLAY_CAMPAIGN, GameState, Research, ResearchProgress, ResearchTree},
+	saves::{self, SessionSave},
+	scripts::{
+		level::resume_level, level_select::level_select, save_browser::save_browser, tutorial,
+	},
 	textures,
 	world::maps::BuildingsMap,
 };
 
+/// how often free play gets autosaved
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
 pub async fn main() -> DynamicLayable<'static> {
 	let main_menu = main_menu().await;
 
@@ -27,6 +41,11 @@ pub async fn main() -> DynamicLayable<'static> {
 pub async fn main_menu() -> impl Layable + Debug {
 	let game_state = GameState::load().await;
 	let only_allow_tutorial = !game_state.tutorial_completed;
+	let has_session = match saves::session_path() {
+		Ok(path) => SessionSave::exists(&path).await,
+		Err(_) => false,
+	};
+	let has_autosave = matches!(saves::newest_autosave(), Ok(Some(_)));
 
 	let title = sui::Text::new("conveyor-game", 32);
 	let title = title.margin(32);
@@ -37,14 +56,53 @@ pub async fn main_menu() -> impl Layable + Debug {
 		ReturnEvent::new(StageChange::Simple(tutorial))
 	});
 	let start_freeplay = comp_extra::button_explicit("free play", only_allow_tutorial, || {
-		ReturnEvent::new(free_play(None))
+		let loader = stage_manager_loaders::Loader::new_invisible(
+			load_free_play_research(),
+			|res| match res {
+				Ok(research) => free_play(None, research),
+				Err(err) => StageChange::simple_only_debug(err_page(err)),
+			},
+		);
+		ReturnEvent::new(loader)
+	});
+	let browse_saves = comp_extra::button_explicit("saves", only_allow_tutorial, || {
+		let loader =
+			stage_manager_loaders::Loader::new_invisible(save_browser(None, None), |page| {
+				StageChange::simple_only_debug(page)
+			});
+		ReturnEvent::new(loader)
 	});
 	let load_freeplay =
-		comp_extra::button_explicit("load save file", only_allow_tutorial, freeplay_loader);
+		comp_extra::button_explicit("import save file", only_allow_tutorial, freeplay_loader);
+	let continue_freeplay = comp_extra::button_explicit(
+		"continue",
+		only_allow_tutorial || !has_autosave,
+		autosave_loader,
+	);
+	let continue_level = comp_extra::button_explicit("continue level", !has_session, || {
+		let loader = stage_manager_loaders::Loader::new_invisible(
+			async { SessionSave::load(saves::session_path()?).await },
+			|res| match res {
+				Ok(save) => StageChange::Simple(resume_level(save)),
+				Err(err) => StageChange::simple_only_debug(err_page(err)),
+			},
+		);
+		ReturnEvent::new(loader)
+	});
+	let levels = comp_extra::button_explicit("levels", only_allow_tutorial, || {
+		let loader = stage_manager_loaders::Loader::new_invisible(level_select(), |page| {
+			StageChange::simple_only_debug(page)
+		});
+		ReturnEvent::new(loader)
+	});
 
 	let buttons = sui::div([
+		sui::custom_only_debug(continue_freeplay),
+		sui::custom_only_debug(continue_level),
 		sui::custom_only_debug(start_tutorial),
+		sui::custom_only_debug(levels),
 		sui::custom_only_debug(start_freeplay),
+		sui::custom_only_debug(browse_saves),
 		sui::custom_only_debug(load_freeplay),
 	]);
 	let buttons = buttons.restrict_to_size().center_x();
@@ -54,13 +112,22 @@ pub async fn main_menu() -> impl Layable + Debug {
 	page
 }
 
-pub fn free_play(game_data: Option<GameData>) -> StageChange<'static> {
+/// loads the research tree along with the player's free play progress in it
+pub async fn load_free_play_research() -> anyhow::Result<Research> {
+	let assets = GameAssets::default();
+	let tree = ResearchTree::load(&assets).await?;
+	let game_state = GameState::load().await;
+
+	Ok(Research::new(tree, game_state.research(FREE_PLAY_CAMPAIGN)))
+}
+
+pub fn free_play(game_data: Option<GameData>, research: Research) -> StageChange<'static> {
 	let (tx, mut rx) = tokio::sync::oneshot::channel();
-	let _ = tx.send(game_data);
+	let _ = tx.send((game_data, research));
 	textures::load_as_scene(GameAssets::default(), move |tex| {
 		let tex = tex.expect("fuck");
 
-		let recv_msg = "failed to receive Option<GameData> from oneshot channel";
+		let recv_msg = "failed to receive Option<GameData> and Research from oneshot channel";
 		let game = rx.try_recv().with_context(|| recv_msg);
 		let game = match game {
 			Ok(a) => a,
@@ -70,6 +137,7 @@ pub fn free_play(game_data: Option<GameData>) -> StageChange<'static> {
 				return sui::custom_only_debug(err_page);
 			}
 		};
+		let (game, research) = game;
 		let mut game = match game {
 			Some(data) => Game::new_multithread(tex, data),
 			None => match Game::new_multithread_worldgen(tex) {
@@ -82,6 +150,10 @@ pub fn free_play(game_data: Option<GameData>) -> StageChange<'static> {
 			},
 		};
 		game.enable_save_handler(save_handler());
+		game.enable_autosave(AUTOSAVE_INTERVAL, autosave_handler());
+		game.enable_save_hotkeys(save_hotkeys_handler());
+		game.enable_research(research);
+		game.enable_research_handler(research_handler());
 
 		sui::custom_only_debug(game)
 	})
@@ -98,13 +170,7 @@ fn save_handler() -> impl FnMut(GameData) + Send + 'static {
 		// the save location
 		let (tx, rx) = tokio::sync::oneshot::channel();
 		tokio::task::spawn(async move {
-			let f = || {
-				let save = GameDataSave::new(&game_data)?;
-				let mut buf = Vec::new();
-				save.save(&mut buf)?;
-				anyhow::Ok(buf)
-			};
-			let save = f();
+			let save = GameDataSave::new(&game_data);
 			let res = tx.send(save);
 			match res {
 				Ok(_) => {}
@@ -118,6 +184,7 @@ fn save_handler() -> impl FnMut(GameData) + Send + 'static {
 
 		let files = AsyncFileDialog::new()
 			.add_filter("save file", &["cgs"])
+			.add_filter("text save file", &["ron"])
 			.set_directory(std::env::current_dir()?)
 			.set_title("saving")
 			.set_file_name("new-game.cgs")
@@ -136,8 +203,17 @@ fn save_handler() -> impl FnMut(GameData) + Send + 'static {
 				format!("failed to receive from oneshot channel (something's broken)")
 			});
 		let save = save??;
+		let save = match is_text_save(&path) {
+			true => save.to_text()?.into_bytes(),
+			false => {
+				let mut buf = Vec::new();
+				save.save(&mut buf)?;
+				buf
+			}
+		};
 
-		tokio::fs::write(&path, &save).await?;
+		let save_path = path.clone();
+		tokio::task::spawn_blocking(move || persist::write_atomic(&save_path, &save)).await??;
 		anyhow::Ok(path)
 	};
 
@@ -173,12 +249,103 @@ fn save_handler() -> impl FnMut(GameData) + Send + 'static {
 	executor
 }
 
+/// writes autosaves on a blocking thread
+fn autosave_handler() -> impl FnMut(GameData) + Send + 'static {
+	|game_data: GameData| {
+		tokio::task::spawn_blocking(move || match saves::write_autosave(&game_data) {
+			Ok(path) => mklogger::println!("autosaved to {}", path.display()),
+			Err(err) => mklogger::eprintln!("failed to autosave: {err:?}"),
+		});
+	}
+}
+
+/// F5 quick saves on a blocking thread, F9 loads the quick save and F6 opens the save browser
+fn save_hotkeys_handler() -> impl FnMut(SaveHotkey, GameData) -> Option<ReturnEvent> + Send + 'static
+{
+	|hotkey, game_data| match hotkey {
+		SaveHotkey::QuickSave => {
+			tokio::task::spawn_blocking(move || {
+				let path = saves::quicksave_path();
+				match path.and_then(|path| saves::write_save(&path, &game_data)) {
+					Ok(()) => mklogger::println!("quick saved"),
+					Err(err) => mklogger::eprintln!("failed to quick save: {err:?}"),
+				}
+			});
+			None
+		}
+		SaveHotkey::QuickLoad => {
+			let future = async {
+				let game_data = saves::load_save(saves::quicksave_path()?).await?;
+				let research = load_free_play_research().await?;
+
+				anyhow::Ok((game_data, research))
+			};
+			let loader = stage_manager_loaders::Loader::new_invisible(future, free_play_or_err);
+			Some(ReturnEvent::new(loader))
+		}
+		SaveHotkey::Browse => {
+			let browser = save_browser(Some(game_data), None);
+			let loader = stage_manager_loaders::Loader::new_invisible(browser, |page| {
+				StageChange::simple_only_debug(page)
+			});
+			Some(ReturnEvent::new(loader))
+		}
+	}
+}
+
+/// saves free play research progress into the GameState
+fn research_handler() -> impl FnMut(&ResearchProgress) + Send + 'static {
+	|progress: &ResearchProgress| {
+		let progress = progress.clone();
+		tokio::task::spawn(async move {
+			let saved = GameState::modify(|game_state| {
+				let campaign = FREE_PLAY_CAMPAIGN.to_string();
+				game_state.research.insert(campaign, progress);
+			});
+			if let Err(err) = saved.await {
+				mklogger::eprintln!("failed to save research progress: {err:?}")
+			}
+		});
+	}
+}
+
+/// continues free play from the newest autosave
+fn autosave_loader() -> ReturnEvent {
+	let future = async {
+		let path = saves::newest_autosave()?
+			.ok_or_else(|| mklogger::anyhow!("there are no autosaves to continue from"))?;
+		let game_data = saves::load_save(path).await?;
+		let research = load_free_play_research().await?;
+
+		anyhow::Ok((game_data, research))
+	};
+
+	let loader = stage_manager_loaders::Loader::new_invisible(future, free_play_or_err);
+	ReturnEvent::new(loader)
+}
+
+/// starts free play with a loaded save, or shows why it couldn't be loaded
+fn free_play_or_err(res: anyhow::Result<(GameData, Research)>) -> StageChange<'static> {
+	match res {
+		Ok((game_data, research)) => free_play(Some(game_data), research),
+		Err(err) => {
+			let err_page = err_page(err);
+			StageChange::simple_only_debug(err_page)
+		}
+	}
+}
+
+/// saves exported as `.ron` are RON text, see [crate::world::text]
+fn is_text_save(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext == "ron")
+}
+
 fn freeplay_loader() -> ReturnEvent {
 	let future = async {
 		use rfd::AsyncFileDialog;
 
 		let files = AsyncFileDialog::new()
-			.add_filter("save file", &["cgs"])
+			.add_filter("save file", &["cgs", "ron"])
 			.set_directory(std::env::current_dir()?)
 			.set_title("saving")
 			.set_file_name("new-game.cgs")
@@ -188,20 +355,20 @@ fn freeplay_loader() -> ReturnEvent {
 		let path = files.path();
 		let path = PathBuf::from(path);
 
-		let file = tokio::fs::OpenOptions::new()
-			.read(true)
-			.open(&path)
+		let file = tokio::fs::read(&path)
 			.await
-			.with_context(|| format!("while opening save file at {}", path.display()))?;
-		let mut file = file.into_std().await;
-
-		let decoded = GameDataSave::load_as_either(&mut file)?;
+			.with_context(|| format!("while reading save file at {}", path.display()))?;
+		let decoded = match is_text_save(&path) {
+			true => GameDataSave::from_text(&String::from_utf8(file)?)?,
+			false => GameDataSave::load_as_either(&mut Cursor::new(file))?,
+		};
 		let game_data = decoded.take()?;
+		let research = load_free_play_research().await?;
 
-		anyhow::Ok(game_data)
+		anyhow::Ok((game_data, research))
 	};
 	let post_process = move |res| match res {
-		Ok(game_data) => free_play(Some(game_data)),
+		Ok((game_data, research)) => free_play(Some(game_data), research),
 		Err(err) => {
 			let err_page = err_page(err);
 			StageChange::simple_only_debug(err_page)
@@ -1,3 +1,6 @@
+pub mod level;
+pub mod level_select;
 pub mod main;
+pub mod save_browser;
 pub mod tips;
 pub mod tutorial;
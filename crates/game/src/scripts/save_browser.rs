@@ -0,0 +1,239 @@
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use rust_i18n::t;
+use stage_manager::StageChange;
+use sui::{DynamicLayable, Layable, LayableExt, core::ReturnEvent};
+
+use crate::{
+	comp::{TextInput, err_page, handle_result},
+	game::GameData,
+	saves::{self, SaveInfo},
+	scripts::{
+		level_select::format_time,
+		main::{free_play, load_free_play_research, main_menu},
+	},
+};
+
+/// the longest name a save can be renamed to
+const MAX_NAME_LEN: usize = 32;
+
+/// lists the saves in the saves directory. `current` is the free play game the browser was opened
+/// from: saves can be overwritten with it, and going back returns to it. `message` is shown on top,
+/// like the result of the last action
+pub async fn save_browser(
+	current: Option<GameData>,
+	message: Option<String>,
+) -> DynamicLayable<'static> {
+	handle_result(save_browser_page(current.map(Arc::new), message).await)
+}
+
+/// a ReturnEvent that goes to the stage `future` resolves into
+fn change_to<F: Future<Output = StageChange<'static>> + Send + 'static>(future: F) -> ReturnEvent {
+	let loader = stage_manager_loaders::Loader::new_invisible(future, |change| change);
+	ReturnEvent::new(loader)
+}
+/// reopens the browser with the result of an action
+async fn browser_after(
+	current: Option<Arc<GameData>>,
+	res: anyhow::Result<String>,
+) -> StageChange<'static> {
+	let message = match res {
+		Ok(message) => message,
+		Err(err) => {
+			mklogger::eprintln!("save browser action failed:\n{err:?}");
+			t!("saves.error", err = format!("{err:#}")).into_owned()
+		}
+	};
+	let page = save_browser_page(current, Some(message)).await;
+	StageChange::simple_only_debug(handle_result(page))
+}
+async fn free_play_with(data: GameData) -> StageChange<'static> {
+	match load_free_play_research().await {
+		Ok(research) => free_play(Some(data), research),
+		Err(err) => StageChange::simple_only_debug(err_page(err)),
+	}
+}
+
+async fn write_save(path: PathBuf, data: Arc<GameData>) -> anyhow::Result<String> {
+	let name = path
+		.file_stem()
+		.map(|name| name.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	tokio::task::spawn_blocking(move || saves::write_save(&path, &data))
+		.await
+		.with_context(|| format!("saving task panicked"))??;
+	Ok(t!("saves.saved", name = name).into_owned())
+}
+
+async fn save_browser_page(
+	current: Option<Arc<GameData>>,
+	message: Option<String>,
+) -> anyhow::Result<impl Layable + Debug> {
+	let saves = saves::list_saves().await?;
+
+	let mut rows = Vec::new();
+	for info in saves {
+		rows.push(sui::custom_only_debug(save_row(info, &current).margin(4)));
+	}
+	if rows.is_empty() {
+		let empty = sui::Text::new(t!("saves.empty"), 16).margin(4);
+		rows.push(sui::custom_only_debug(empty));
+	}
+
+	let mut buttons = Vec::new();
+	if let Some(data) = &current {
+		let data = data.clone();
+		let save_new = comp_extra::button_explicit(t!("saves.save-new"), false, move || {
+			let data = data.clone();
+			change_to(async move {
+				let res = async {
+					let path = saves::named_save_path(&saves::unused_save_name()?)?;
+					write_save(path, data.clone()).await
+				};
+				let res = res.await;
+				browser_after(Some(data), res).await
+			})
+		});
+		buttons.push(sui::custom_only_debug(save_new));
+	}
+	let back_to = current.clone();
+	let back = comp_extra::button_explicit(t!("saves.back"), false, move || {
+		let back_to = back_to.clone();
+		change_to(async move {
+			match back_to {
+				Some(data) => free_play_with(Arc::unwrap_or_clone(data)).await,
+				None => StageChange::simple_only_debug(main_menu().await),
+			}
+		})
+	});
+	buttons.push(sui::custom_only_debug(back));
+
+	let title = sui::Text::new(t!("saves.title"), 32).margin(32);
+	let message = sui::Text::new(message.unwrap_or_default(), 16).margin(4);
+	let page = sui::div([
+		sui::custom_only_debug(title.center_x()),
+		sui::custom_only_debug(message.center_x()),
+		sui::custom_only_debug(sui::div(rows).restrict_to_size().center_x()),
+		sui::custom_only_debug(sui::div_h(buttons).restrict_to_size().center_x()),
+	]);
+	Ok(page)
+}
+
+fn save_row(info: SaveInfo, current: &Option<Arc<GameData>>) -> impl Layable + Debug + 'static {
+	let date = saves::format_date(info.modified);
+	let details = match &info.summary {
+		Some(summary) => t!(
+			"saves.details",
+			date = date,
+			playtime = format_time(summary.playtime),
+			width = summary.world_size.0,
+			height = summary.world_size.1
+		),
+		None => t!("saves.unreadable", date = date),
+	};
+
+	let path = info.path.clone();
+	let load = comp_extra::button_explicit(t!("saves.load"), info.summary.is_none(), move || {
+		let path = path.clone();
+		change_to(async move {
+			match saves::load_save(path).await {
+				Ok(data) => free_play_with(data).await,
+				Err(err) => StageChange::simple_only_debug(err_page(err)),
+			}
+		})
+	});
+
+	let mut buttons = vec![sui::custom_only_debug(load)];
+	if let Some(data) = current {
+		let (path, data) = (info.path.clone(), data.clone());
+		let overwrite = comp_extra::button_explicit(t!("saves.overwrite"), false, move || {
+			let (path, data) = (path.clone(), data.clone());
+			change_to(async move {
+				let res = write_save(path, data.clone()).await;
+				browser_after(Some(data), res).await
+			})
+		});
+		buttons.push(sui::custom_only_debug(overwrite));
+	}
+
+	let (rename_info, rename_current) = (info.clone(), current.clone());
+	let rename = comp_extra::button_explicit(t!("saves.rename"), false, move || {
+		let page = rename_page(rename_info.clone(), rename_current.clone());
+		ReturnEvent::new(StageChange::simple_only_debug(page))
+	});
+	buttons.push(sui::custom_only_debug(rename));
+
+	let (delete_info, delete_current) = (info.clone(), current.clone());
+	let delete = comp_extra::button_explicit(t!("saves.delete"), false, move || {
+		let page = delete_page(delete_info.clone(), delete_current.clone());
+		ReturnEvent::new(StageChange::simple_only_debug(page))
+	});
+	buttons.push(sui::custom_only_debug(delete));
+
+	let text = sui::div([
+		sui::Text::new(info.name, 24).into_comp(),
+		sui::Text::new(details, 16).into_comp(),
+	]);
+	sui::div_h([
+		sui::custom_only_debug(text.margin(4)),
+		sui::custom_only_debug(sui::div_h(buttons).center_y()),
+	])
+}
+
+fn cancel_button(current: Option<Arc<GameData>>) -> impl Layable + Debug {
+	comp_extra::button_explicit(t!("saves.cancel"), false, move || {
+		change_to(browser_after(current.clone(), Ok(String::new())))
+	})
+}
+
+fn rename_page(info: SaveInfo, current: Option<Arc<GameData>>) -> impl Layable + Debug + 'static {
+	let title = sui::Text::new(t!("saves.rename-title", name = &info.name), 24).margin(8);
+	let hint = sui::Text::new(t!("saves.rename-hint"), 16).margin(4);
+
+	let (path, submit_current) = (info.path.clone(), current.clone());
+	let input = TextInput::new(info.name.clone(), MAX_NAME_LEN, move |name| {
+		let (path, current) = (path.clone(), submit_current.clone());
+		change_to(async move {
+			let res = async {
+				let renamed = saves::rename_save(&path, &name).await?;
+				let name = renamed.file_stem().unwrap_or_default().to_string_lossy();
+				anyhow::Ok(t!("saves.renamed", name = name).into_owned())
+			};
+			browser_after(current, res.await).await
+		})
+	});
+
+	let page = sui::div([
+		sui::custom_only_debug(title.center_x()),
+		sui::custom_only_debug(input.margin(4).center_x()),
+		sui::custom_only_debug(hint.center_x()),
+		sui::custom_only_debug(cancel_button(current).center_x()),
+	]);
+	page.center_y()
+}
+
+fn delete_page(info: SaveInfo, current: Option<Arc<GameData>>) -> impl Layable + Debug + 'static {
+	let text = sui::Text::new(t!("saves.delete-confirm", name = &info.name), 24).margin(8);
+
+	let (path, name, delete_current) = (info.path.clone(), info.name.clone(), current.clone());
+	let delete = comp_extra::button_explicit(t!("saves.delete"), false, move || {
+		let (path, name, current) = (path.clone(), name.clone(), delete_current.clone());
+		change_to(async move {
+			let res = saves::remove_with_backup(&path)
+				.await
+				.map(|()| t!("saves.deleted", name = name).into_owned());
+			browser_after(current, res).await
+		})
+	});
+
+	let buttons = sui::div_h([
+		sui::custom_only_debug(delete),
+		sui::custom_only_debug(cancel_button(current)),
+	]);
+	let page = sui::div([
+		sui::custom_only_debug(text.center_x()),
+		sui::custom_only_debug(buttons.restrict_to_size().center_x()),
+	]);
+	page.center_y()
+}
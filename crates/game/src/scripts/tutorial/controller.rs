@@ -20,7 +20,6 @@ use crate::{
 	world::{
 		EResource, Resource,
 		buildings::{Building, EBuilding},
-		maps::BuildingsMap,
 	},
 };
 
@@ -47,7 +46,9 @@ pub struct Channels {
 	pub tool_use_rx: broadcast::Receiver<(Tool, (i32, i32))>,
 	pub game_tx: mpsc::Sender<crate::game::GameCommand<GameData>>,
 
-	pub stage_size: (usize, usize),
+	/// where the resources have to be delivered to, from the level's metadata
+	pub sinks: Vec<(i32, i32)>,
+	pub timer: Duration,
 }
 impl Channels {
 	pub async fn send_stage<L: Layable + Debug + 'static>(
@@ -301,10 +302,11 @@ pub async fn get_started(channels: &mut Channels) -> anyhow::Result<()> {
 		_ => return Err(mklogger::anyhow!("incorrect tooltippage received")),
 	}
 
+	let timer = channels.timer;
 	channels
-		.game(|game| {
+		.game(move |game| {
 			// sets a timer that the user can't touch (yet)
-			game.enable_timer(Duration::from_secs(60 * 5));
+			game.enable_timer(timer);
 			game.pause_time();
 			game.set_can_toggle_time(false);
 		})
@@ -452,7 +454,7 @@ async fn mined(channels: &mut Channels, pos: (i32, i32)) -> anyhow::Result<bool>
 	// set the goal to some of the resource we placed an extractor over
 	channels
 		.goal
-		.set_goal(Goal::new([(tile_resource.clone(), 10)]));
+		.set_goal(Goal::deliver_all([(tile_resource.kind(), 10)]));
 
 	// enable the goal ui
 	let display_tx = channels
@@ -498,18 +500,7 @@ async fn mined(channels: &mut Channels, pos: (i32, i32)) -> anyhow::Result<bool>
 	{
 		// this block contains the code used to check if the miner's connected to the center building or nah
 
-		let mut test_buildmap =
-			BuildingsMap::new_default(channels.stage_size.0, channels.stage_size.1);
-		super::place_at_center(&mut test_buildmap, EBuilding::debug_consumer());
-
-		let targets = test_buildmap
-			.iter()
-			.filter_map(|(c, b)| match b {
-				EBuilding::DebugConsumer(_) => Some(c),
-				_ => None,
-			})
-			.collect::<Vec<_>>();
-		std::mem::drop(test_buildmap);
+		let targets = channels.sinks.clone();
 
 		loop {
 			tokio::time::sleep(Duration::from_millis(750)).await;
@@ -709,10 +700,7 @@ async fn smelting_start(
 }
 
 async fn won(channels: &mut Channels) -> anyhow::Result<()> {
-	let mut game_state = GameState::load().await;
-	game_state.tutorial_completed = true;
-	game_state
-		.save()
+	GameState::modify(|game_state| game_state.tutorial_completed = true)
 		.await
 		.with_context(|| format!("while saving tutorial completion"))?;
 
@@ -0,0 +1,354 @@
+// compact encodings shared by saves (.cgs) and level files (.cglf)
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ETile,
+	buildings::PlacedBuilding,
+	maps::{BuildingsMap, Tilemap},
+};
+
+/// how the body of a file is compressed. picked when writing, recorded in the file's header
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+	#[default]
+	None,
+	Zstd,
+}
+impl Compression {
+	const ZSTD_LEVEL: i32 = 3;
+
+	pub fn compress(self, bytes: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+		match self {
+			Self::None => Ok(bytes),
+			Self::Zstd => zstd::encode_all(bytes.as_slice(), Self::ZSTD_LEVEL)
+				.with_context(|| format!("while compressing with zstd")),
+		}
+	}
+	pub fn decompress(self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+		match self {
+			Self::None => Ok(bytes.to_vec()),
+			Self::Zstd => {
+				zstd::decode_all(bytes).with_context(|| format!("while decompressing with zstd"))
+			}
+		}
+	}
+
+	fn to_byte(self) -> u8 {
+		match self {
+			Self::None => 0,
+			Self::Zstd => 1,
+		}
+	}
+	fn from_byte(byte: u8) -> anyhow::Result<Self> {
+		match byte {
+			0 => Ok(Self::None),
+			1 => Ok(Self::Zstd),
+			_ => Err(mklogger::anyhow!("unknown compression {byte}")),
+		}
+	}
+}
+
+/// a grid stored as the distinct cells it has and runs of them, going down each column from the
+/// left. worlds are mostly the same few cells, so this is a lot smaller than every cell one by one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaletteGrid<T> {
+	width: usize,
+	height: usize,
+	palette: Vec<T>,
+	/// an index into the palette and how many cells in a row it's repeated for
+	runs: Vec<(u32, u32)>,
+}
+impl<T: Serialize + Clone> PaletteGrid<T> {
+	/// cells are told apart by their CBOR, so they don't need to be comparable
+	pub fn encode(columns: &[Vec<T>]) -> anyhow::Result<Self> {
+		let width = columns.len();
+		let height = columns.first().map(Vec::len).unwrap_or_default();
+
+		let mut palette = Vec::new();
+		let mut indices = HashMap::new();
+		let mut runs: Vec<(u32, u32)> = Vec::new();
+		for (x, column) in columns.iter().enumerate() {
+			if column.len() != height {
+				return Err(mklogger::anyhow!(
+					"column {x} is {} tall instead of {height}",
+					column.len()
+				));
+			}
+
+			for cell in column {
+				let key = serde_cbor::to_vec(cell)
+					.with_context(|| format!("while encoding a cell of column {x}"))?;
+				let index = *indices.entry(key).or_insert_with(|| {
+					palette.push(cell.clone());
+					palette.len() as u32 - 1
+				});
+
+				match runs.last_mut() {
+					Some((last, len)) if *last == index && *len < u32::MAX => *len += 1,
+					_ => runs.push((index, 1)),
+				}
+			}
+		}
+
+		Ok(Self {
+			width,
+			height,
+			palette,
+			runs,
+		})
+	}
+
+	pub fn decode(self) -> anyhow::Result<Vec<Vec<T>>> {
+		let cells = self
+			.runs
+			.iter()
+			.map(|(_, len)| *len as usize)
+			.sum::<usize>();
+		if Some(cells) != self.width.checked_mul(self.height) {
+			return Err(mklogger::anyhow!(
+				"the runs cover {cells} cells, a {}x{} grid has {}",
+				self.width,
+				self.height,
+				self.width.saturating_mul(self.height)
+			));
+		}
+
+		let mut cells = Vec::with_capacity(cells);
+		for (index, len) in self.runs {
+			let cell = self.palette.get(index as usize).with_context(|| {
+				format!(
+					"run refers to cell {index}, the palette only has {}",
+					self.palette.len()
+				)
+			})?;
+			cells.extend(std::iter::repeat_n(cell, len as usize).cloned());
+		}
+
+		let mut cells = cells.into_iter();
+		let columns = (0..self.width)
+			.map(|_| cells.by_ref().take(self.height).collect())
+			.collect();
+		Ok(columns)
+	}
+}
+
+/// for `#[serde(with = "...")]`: writes a column-major grid as a [PaletteGrid]
+pub mod palette_columns {
+	use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser};
+
+	use super::PaletteGrid;
+
+	pub fn serialize<T, S>(columns: &[Vec<T>], serializer: S) -> Result<S::Ok, S::Error>
+	where
+		T: Serialize + Clone,
+		S: Serializer,
+	{
+		let grid =
+			PaletteGrid::encode(columns).map_err(|err| ser::Error::custom(format!("{err:#}")))?;
+		grid.serialize(serializer)
+	}
+	pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<Vec<T>>, D::Error>
+	where
+		T: Serialize + Deserialize<'de> + Clone,
+		D: Deserializer<'de>,
+	{
+		let grid = PaletteGrid::<T>::deserialize(deserializer)?;
+		grid.decode()
+			.map_err(|err| de::Error::custom(format!("{err:#}")))
+	}
+}
+
+// -
+
+/// the first bytes of every .cglf with a header. level files without it are a bincode [Tilemap]
+pub const LEVEL_MAGIC: &[u8; 4] = b"CGLF";
+/// the level file version written by this version of the game
+pub const LEVEL_FILE_VERSION: u16 = 2;
+
+/// everything in a .cglf: the tiles and the buildings the level starts with
+#[derive(Clone, Debug)]
+pub struct LevelFile {
+	pub tilemap: Tilemap,
+	pub buildings: Vec<PlacedBuilding>,
+}
+impl LevelFile {
+	pub fn new(tilemap: Tilemap) -> Self {
+		Self {
+			tilemap,
+			buildings: Vec::new(),
+		}
+	}
+
+	/// the buildings put down on an empty map the size of the tilemap, sinks unconnected
+	pub fn buildings_map(&self) -> anyhow::Result<BuildingsMap> {
+		let (w, h) = self.tilemap.size();
+		let mut buildings = BuildingsMap::new_default(w, h);
+		for (i, placed) in self.buildings.iter().enumerate() {
+			placed
+				.place(&mut buildings)
+				.with_context(|| format!("while placing building number {i}"))?;
+		}
+		Ok(buildings)
+	}
+}
+
+/// the bincode body of a version 2 level file
+#[derive(Serialize, Deserialize)]
+struct LevelBody {
+	tiles: PaletteGrid<ETile>,
+	buildings: Vec<PlacedBuilding>,
+}
+
+/// a .cglf is [LEVEL_MAGIC], the version as a little endian u16, a [Compression] byte, then the
+/// bincode of the tiles as a [PaletteGrid] and the buildings, compressed
+pub fn encode_level(level: &LevelFile, compression: Compression) -> anyhow::Result<Vec<u8>> {
+	let columns = level.tilemap.iter_inner().cloned().collect::<Vec<_>>();
+	let body = LevelBody {
+		tiles: PaletteGrid::encode(&columns)?,
+		buildings: level.buildings.clone(),
+	};
+	let body = bincode::serde::encode_to_vec(&body, bincode::config::standard())
+		.with_context(|| format!("while encoding level"))?;
+
+	let mut bytes = Vec::new();
+	bytes.extend_from_slice(LEVEL_MAGIC);
+	bytes.extend_from_slice(&LEVEL_FILE_VERSION.to_le_bytes());
+	bytes.push(compression.to_byte());
+	bytes.extend(compression.compress(body)?);
+	Ok(bytes)
+}
+
+/// reads level files of any version, with or without a header. the ones before version 2 have
+/// no buildings
+pub fn decode_level(bytes: &[u8]) -> anyhow::Result<LevelFile> {
+	let config = bincode::config::standard();
+	let Some(rest) = bytes.strip_prefix(LEVEL_MAGIC) else {
+		let (tilemap, _) = bincode::serde::decode_from_slice(bytes, config)
+			.with_context(|| format!("while decoding a level file without a header"))?;
+		return Ok(LevelFile::new(tilemap));
+	};
+
+	let (version, compression, body) = match rest {
+		[a, b, compression, body @ ..] => (u16::from_le_bytes([*a, *b]), *compression, body),
+		_ => return Err(mklogger::anyhow!("level file header is cut short")),
+	};
+	if version > LEVEL_FILE_VERSION {
+		return Err(mklogger::anyhow!(
+			"level file version {version} is newer than the newest supported version {LEVEL_FILE_VERSION}"
+		));
+	}
+
+	let body = Compression::from_byte(compression)?.decompress(body)?;
+	let body = match version {
+		0 | 1 => bincode::serde::decode_from_slice(&body, config).map(|(tiles, _)| LevelBody {
+			tiles,
+			buildings: Vec::new(),
+		}),
+		_ => bincode::serde::decode_from_slice(&body, config).map(|(body, _)| body),
+	};
+	let body = body.with_context(|| format!("while decoding a version {version} level file"))?;
+	Ok(LevelFile {
+		tilemap: Tilemap::from_vec(body.tiles.decode()?)?,
+		buildings: body.buildings,
+	})
+}
+/// [decode_level] for when only the tiles matter, like for worldgen segments
+pub fn decode_tilemap(bytes: &[u8]) -> anyhow::Result<Tilemap> {
+	Ok(decode_level(bytes)?.tilemap)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		buildings::{Building, BuildingKind},
+		maps::TilemapExt,
+	};
+
+	fn level() -> Tilemap {
+		let mut tilemap = Tilemap::stone(64, 48);
+		for x in 10..20 {
+			*tilemap.at_mut_usize((x, 5)).unwrap() = ETile::iron_ore();
+			*tilemap.at_mut_usize((x, 6)).unwrap() = ETile::coal_ore();
+		}
+		tilemap
+	}
+	/// tiles can't be compared, their CBOR can
+	fn assert_same(a: &Tilemap, b: &Tilemap) {
+		assert_eq!(
+			serde_cbor::to_vec(a).unwrap(),
+			serde_cbor::to_vec(b).unwrap()
+		);
+	}
+
+	#[test]
+	fn palette_grid_round_trip() {
+		let columns = vec![vec![1, 1, 2], vec![2, 2, 2], vec![3, 1, 1]];
+		let grid = PaletteGrid::encode(&columns).unwrap();
+		assert_eq!(grid.palette, [1, 2, 3]);
+		assert_eq!(grid.runs, [(0, 2), (1, 4), (2, 1), (0, 2)]);
+		assert_eq!(grid.decode().unwrap(), columns);
+
+		assert!(PaletteGrid::encode(&[vec![1], vec![1, 2]]).is_err());
+	}
+
+	#[test]
+	fn rejects_broken_palette_grids() {
+		let mut grid = PaletteGrid::encode(&[vec![1, 2], vec![2, 2]]).unwrap();
+		grid.runs.push((0, 1));
+		assert!(grid.clone().decode().is_err());
+		grid.runs = vec![(5, 4)];
+		assert!(grid.decode().is_err());
+	}
+
+	#[test]
+	fn level_round_trip() {
+		let level = LevelFile {
+			tilemap: level(),
+			buildings: vec![PlacedBuilding {
+				pos: (3, 4),
+				building: BuildingKind::SmallExtractor,
+				dir: None,
+				protected: true,
+			}],
+		};
+		for compression in [Compression::None, Compression::Zstd] {
+			let bytes = encode_level(&level, compression).unwrap();
+			assert!(bytes.starts_with(LEVEL_MAGIC));
+			let decoded = decode_level(&bytes).unwrap();
+			assert_same(&decoded.tilemap, &level.tilemap);
+			assert_eq!(decoded.buildings, level.buildings);
+		}
+		let buildings = level.buildings_map().unwrap();
+		assert!(buildings.at((4, 5)).unwrap().is_protected());
+	}
+
+	#[test]
+	fn loads_older_levels() {
+		let level = level();
+		let bytes = bincode::serde::encode_to_vec(&level, bincode::config::standard()).unwrap();
+		let decoded = decode_level(&bytes).unwrap();
+		assert_same(&decoded.tilemap, &level);
+		assert!(decoded.buildings.is_empty());
+
+		// version 1 had the header, but only the tiles in the body
+		let columns = level.iter_inner().cloned().collect::<Vec<_>>();
+		let body = bincode::serde::encode_to_vec(
+			PaletteGrid::encode(&columns).unwrap(),
+			bincode::config::standard(),
+		)
+		.unwrap();
+		let mut v1 = LEVEL_MAGIC.to_vec();
+		v1.extend_from_slice(&1u16.to_le_bytes());
+		v1.push(Compression::None.to_byte());
+		v1.extend(body);
+		assert_same(&decode_tilemap(&v1).unwrap(), &level);
+
+		let encoded = encode_level(&LevelFile::new(level), Compression::None).unwrap();
+		assert!(encoded.len() * 10 < bytes.len());
+	}
+}
@@ -3,5 +3,8 @@ pub use world::*;
 
 pub mod tool;
 
+pub mod codec;
+pub mod text;
+
 pub mod prov;
 pub use prov::*;
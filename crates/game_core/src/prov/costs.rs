@@ -0,0 +1,57 @@
+use crate::{ResourceAmount, ResourceKind, buildings::BuildingKind};
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BuildingCost {
+	pub building: BuildingKind,
+	pub cost: Vec<ResourceAmount>,
+}
+
+/// what every building costs to place, and how much of it is given back on demolition \
+/// buildings missing from the table are free
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CostTable {
+	#[serde(default)]
+	pub buildings: Vec<BuildingCost>,
+	/// the part of the cost refunded when a building gets replaced or removed, rounded down
+	#[serde(default = "CostTable::default_refund_ratio")]
+	pub refund_ratio: f32,
+}
+impl Default for CostTable {
+	/// the standard table used for free play
+	fn default() -> Self {
+		let iron = |amount| vec![ResourceAmount::new(ResourceKind::Iron, amount)];
+		let cost = |building, cost| BuildingCost { building, cost };
+
+		Self {
+			buildings: vec![
+				cost(BuildingKind::Conveyor, iron(1)),
+				cost(BuildingKind::Junction, iron(2)),
+				cost(BuildingKind::Router, iron(2)),
+				cost(BuildingKind::SmallExtractor, iron(4)),
+				cost(BuildingKind::Smelter, iron(6)),
+				cost(BuildingKind::Core, iron(10)),
+			],
+			refund_ratio: Self::default_refund_ratio(),
+		}
+	}
+}
+impl CostTable {
+	/// everything costs nothing, like before costs existed
+	pub fn free() -> Self {
+		Self {
+			buildings: Vec::new(),
+			refund_ratio: 0.0,
+		}
+	}
+	fn default_refund_ratio() -> f32 {
+		0.5
+	}
+
+	pub fn cost_of(&self, building: BuildingKind) -> &[ResourceAmount] {
+		self.buildings
+			.iter()
+			.find(|entry| entry.building == building)
+			.map(|entry| entry.cost.as_slice())
+			.unwrap_or_default()
+	}
+}
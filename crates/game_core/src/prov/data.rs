@@ -5,20 +5,43 @@ use std::{
 
 use anyhow::{Context, anyhow};
 
+use super::save;
 use crate::{
-	EResource, ETile, Tile,
-	buildings::EBuilding,
-	maps::{BuildingsMap, OrIndexed, Tilemap, TilemapExt},
+	CostTable, EResource, ETile, Inventory, Tile,
+	buildings::{EBuilding, LegacyEBuilding},
+	codec::Compression,
+	maps::{BuildingsMap, MovesQueue, OrIndexed, Tilemap, TilemapExt},
 };
 
+const DEBUG: bool = false;
+
 #[derive(Clone, Debug)]
 pub struct GameData {
 	pub tilemap: Tilemap,
 	pub buildings: BuildingsMap,
+
+	/// resources the player collected, spent on placing buildings
+	pub inventory: Inventory,
+	pub costs: CostTable,
+
+	/// the number of simulation ticks that have passed
+	ticks: u64,
 }
 impl GameData {
 	pub fn new(tilemap: Tilemap, buildings: BuildingsMap) -> Self {
-		Self { tilemap, buildings }
+		Self {
+			tilemap,
+			buildings,
+			inventory: Inventory::new(),
+			costs: CostTable::free(),
+			ticks: 0,
+		}
+	}
+	/// like [Self::new], but building costs something and the player starts with `inventory`
+	pub fn with_economy(mut self, inventory: Inventory, costs: CostTable) -> Self {
+		self.inventory = inventory;
+		self.costs = costs;
+		self
 	}
 
 	pub fn tile_resource_at(&self, pos: (i32, i32)) -> Option<EResource> {
@@ -31,13 +54,23 @@ impl GameData {
 		self.tilemap.size()
 	}
 
+	pub fn ticks(&self) -> u64 {
+		self.ticks
+	}
+	/// the simulation time that has passed, in [crate::GAME_TICK_FREQUENCY] steps
+	pub fn sim_time(&self) -> std::time::Duration {
+		crate::ticks_duration(self.ticks)
+	}
+
 	pub fn tick(&mut self) {
 		let tile_resource_at = |pos| {
 			let tile = self.tilemap.at(pos)?;
 			let resource = tile.generate_resource();
 			resource
 		};
-		self.buildings.tick(tile_resource_at);
+		self.buildings.tick(self.ticks + 1, tile_resource_at);
+		self.inventory.deposit(self.buildings.take_deposits());
+		self.ticks += 1;
 	}
 }
 
@@ -45,11 +78,51 @@ impl GameData {
 
 // we have big buildings they just can't be saved yet
 
+/// the body of a .cgs. older saves are brought up to this layout by the migrations in [super::save]
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct GameDataSave {
+	#[serde(with = "crate::codec::palette_columns")]
 	pub grid: Vec<Vec<(ETile, OrIndexed<EBuilding>)>>,
 	pub external_buildings: Vec<EBuilding>,
+	pub inventory: Inventory,
+	pub costs: CostTable,
+	/// the moves the buildings queued up for the next tick
+	pub moves_queue: MovesQueue,
+	pub ticks: u64,
+}
+
+/// the layout of bincode saves from before the inventory was added. bincode isn't self-describing,
+/// so missing fields can't just be defaulted, down to the buildings' own fields
+#[derive(Clone, Debug, serde::Deserialize)]
+struct LegacyGameDataSave {
+	grid: Vec<Vec<(ETile, OrIndexed<LegacyEBuilding>)>>,
+	external_buildings: Vec<LegacyEBuilding>,
 }
+impl From<LegacyGameDataSave> for GameDataSave {
+	fn from(legacy: LegacyGameDataSave) -> Self {
+		let grid = legacy.grid.into_iter().map(|col| {
+			let col = col.into_iter().map(|(tile, building)| {
+				let building = match building {
+					OrIndexed::Item(building) => OrIndexed::Item(building.into()),
+					OrIndexed::Indexed { root, index } => OrIndexed::Indexed { root, index },
+				};
+				(tile, building)
+			});
+			col.collect()
+		});
+		let external_buildings = legacy.external_buildings.into_iter().map(EBuilding::from);
+
+		Self {
+			grid: grid.collect(),
+			external_buildings: external_buildings.collect(),
+			inventory: Inventory::new(),
+			costs: CostTable::free(),
+			moves_queue: MovesQueue::new(),
+			ticks: 0,
+		}
+	}
+}
+
 impl GameDataSave {
 	pub fn new(game_data: &GameData) -> anyhow::Result<Self> {
 		let (w, h) = game_data.tilemap.size();
@@ -65,21 +138,10 @@ impl GameDataSave {
 							format!("tilemap is reportedly bigger than it actually is: {x}, {y}")
 						})?;
 
-						let building = game_data.buildings.at((x as _, y as _)).cloned();
+						let building = game_data.buildings.grid_at((x as _, y as _)).cloned();
 						let building = building.with_context(|| {
 							format!("buildingsmap is smaller than it reported: {x}, {y}")
 						})?;
-						let building = match building {
-							// filter out building types that can't be serialized
-							EBuilding::ChannelConsumer(_) => OrIndexed::Item(EBuilding::nothing()),
-							_ => {
-								let grid_entry = game_data
-									.buildings
-									.grid_at((x as _, y as _))
-									.with_context(|| format!("impossible"))?;
-								grid_entry.clone()
-							}
-						};
 
 						anyhow::Ok((tile, building))
 					})
@@ -93,9 +155,19 @@ impl GameDataSave {
 		Ok(Self {
 			grid,
 			external_buildings,
+			inventory: game_data.inventory.clone(),
+			costs: game_data.costs.clone(),
+			moves_queue: game_data.buildings.moves_queue().clone(),
+			ticks: game_data.ticks,
 		})
 	}
 
+	/// the width and height of the saved world
+	pub fn world_size(&self) -> (usize, usize) {
+		let height = self.grid.first().map(Vec::len).unwrap_or(0);
+		(self.grid.len(), height)
+	}
+
 	pub fn assert_uniform_size(&self) -> anyhow::Result<()> {
 		let w = self.grid.len();
 		let hs = self
@@ -105,11 +177,7 @@ impl GameDataSave {
 			.map(|a| format!("{a}"))
 			.collect::<Vec<_>>()
 			.join(" ");
-
-		std::fs::write(
-			"./fuck.txt",
-			format!("{:#?}\n\n---\n\n{w}; {hs}", self.grid),
-		)?;
+		mklogger::debug!("save is {w} columns wide, with column heights {hs}");
 
 		let h = self.grid.iter().next().map(Vec::len).unwrap_or_default();
 
@@ -154,25 +222,44 @@ impl GameDataSave {
 			}
 		}
 
-		Ok(GameData::new(tilemap, buildings))
+		buildings.set_moves_queue(self.moves_queue);
+
+		let mut data = GameData::new(tilemap, buildings).with_economy(self.inventory, self.costs);
+		data.ticks = self.ticks;
+		Ok(data)
 	}
 
+	/// writes the save with a [super::save::SaveHeader] in front, compressed with zstd
 	pub fn save<W: Write>(&self, write: &mut W) -> anyhow::Result<()> {
-		serde_cbor::to_writer(write, self)
-			.with_context(|| format!("while serializing save file"))?;
-		Ok(())
+		self.save_with(write, Compression::Zstd)
+	}
+	pub fn save_with<W: Write>(
+		&self,
+		write: &mut W,
+		compression: Compression,
+	) -> anyhow::Result<()> {
+		let body =
+			serde_cbor::to_vec(self).with_context(|| format!("while serializing save file"))?;
+		save::write_save(write, body, compression)
 	}
 
+	/// loads CBOR saves of any version, with or without a header
 	pub fn load<R: Read>(read: &mut R) -> anyhow::Result<Self> {
-		let deser = serde_cbor::from_reader(read)
+		let mut bytes = Vec::new();
+		read.read_to_end(&mut bytes)
+			.with_context(|| format!("while reading save file"))?;
+
+		let (_, body) =
+			save::read_body(&bytes)?.with_context(|| format!("save file isn't CBOR"))?;
+		let deser = serde_cbor::value::from_value(body)
 			.with_context(|| format!("while deserializing cbor save file"))?;
 		Ok(deser)
 	}
 	pub fn load_bincode<R: Read>(read: &mut R) -> anyhow::Result<Self> {
-		let decoded: GameDataSave =
+		let decoded: LegacyGameDataSave =
 			bincode::serde::decode_from_std_read(read, bincode::config::standard())
 				.with_context(|| format!("while deserializing bincode save file"))?;
-		Ok(decoded)
+		Ok(decoded.into())
 	}
 	pub fn load_as_either<R: Read + Seek>(read: &mut R) -> anyhow::Result<Self> {
 		struct BothFailedError {
@@ -209,3 +296,107 @@ impl GameDataSave {
 		Ok(decoded)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use tokio::sync::mpsc;
+	use utils::Direction;
+
+	use super::*;
+	use crate::{
+		ResourceKind,
+		buildings::{ChannelConsumer, Delivery},
+	};
+
+	/// an extractor on iron feeding a sink and one on coal feeding a smelter, both through conveyors
+	fn factory() -> (GameData, mpsc::Receiver<Delivery>) {
+		let mut tilemap = Tilemap::stone(8, 3);
+		*tilemap.at_mut_usize((0, 0)).unwrap() = ETile::iron_ore();
+		*tilemap.at_mut_usize((0, 2)).unwrap() = ETile::coal_ore();
+
+		let mut buildings = BuildingsMap::new_default(8, 3);
+		let mut place = |pos, building| *buildings.at_mut(pos).unwrap() = building;
+		for y in [0, 2] {
+			place((0, y), EBuilding::small_extractor());
+			for x in 1..5 {
+				place((x, y), EBuilding::conveyor(Direction::Right));
+			}
+		}
+		let (sink, deliveries) = ChannelConsumer::new();
+		place((5, 0), EBuilding::ChannelConsumer(sink));
+		place((5, 2), EBuilding::smelter());
+
+		(GameData::new(tilemap, buildings), deliveries)
+	}
+	fn snapshot(data: &GameData) -> Vec<u8> {
+		serde_cbor::to_vec(&GameDataSave::new(data).unwrap()).unwrap()
+	}
+
+	#[test]
+	fn save_round_trip_resumes_exactly() {
+		// drained every tick, so the connected sink takes as much as the reloaded one
+		let (mut original, mut deliveries) = factory();
+		let mut tick = |data: &mut GameData| {
+			data.tick();
+			while deliveries.try_recv().is_ok() {}
+		};
+		for _ in 0..37 {
+			tick(&mut original);
+		}
+
+		let mut bytes = Vec::new();
+		GameDataSave::new(&original)
+			.unwrap()
+			.save(&mut bytes)
+			.unwrap();
+		let mut loaded = GameDataSave::load_as_either(&mut Cursor::new(bytes))
+			.unwrap()
+			.take()
+			.unwrap();
+		assert_eq!(loaded.ticks(), 37);
+
+		for n in 0..200 {
+			tick(&mut original);
+			loaded.tick();
+			assert_eq!(
+				snapshot(&original),
+				snapshot(&loaded),
+				"diverged {n} ticks after loading"
+			);
+			assert_eq!(original.inventory, loaded.inventory);
+		}
+		assert!(loaded.inventory.count(ResourceKind::RawIron) > 0);
+	}
+
+	#[test]
+	fn large_worlds_save_small() {
+		let mut data = GameData::new(
+			Tilemap::stone(512, 512),
+			BuildingsMap::new_default(512, 512),
+		);
+		for x in 100..200 {
+			*data.tilemap.at_mut_usize((x, 300)).unwrap() = ETile::iron_ore();
+			*data.buildings.at_mut((x as _, 301)).unwrap() = EBuilding::conveyor(Direction::Left);
+		}
+		let save = GameDataSave::new(&data).unwrap();
+
+		for compression in [Compression::None, Compression::Zstd] {
+			let mut bytes = Vec::new();
+			save.save_with(&mut bytes, compression).unwrap();
+			assert!(
+				bytes.len() < 16 * 1024,
+				"{compression:?} save is {} bytes",
+				bytes.len()
+			);
+
+			let loaded = GameDataSave::load_as_either(&mut Cursor::new(bytes)).unwrap();
+			assert_eq!(loaded.world_size(), (512, 512));
+			assert_eq!(
+				serde_cbor::to_vec(&loaded).unwrap(),
+				serde_cbor::to_vec(&save).unwrap()
+			);
+		}
+	}
+}
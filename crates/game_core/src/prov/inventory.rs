@@ -0,0 +1,139 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{EResource, ResourceKind};
+
+/// some amount of a single resource, as written in cost tables and level files
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ResourceAmount {
+	pub resource: ResourceKind,
+	pub amount: u32,
+}
+impl ResourceAmount {
+	pub fn new(resource: ResourceKind, amount: u32) -> Self {
+		Self { resource, amount }
+	}
+}
+impl Display for ResourceAmount {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} {}", self.amount, self.resource.name())
+	}
+}
+
+/// the resources the player has collected through cores and sinks
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Inventory {
+	resources: BTreeMap<ResourceKind, u32>,
+}
+impl Inventory {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	pub fn from_amounts<'a>(amounts: impl IntoIterator<Item = &'a ResourceAmount>) -> Self {
+		let mut inventory = Self::new();
+		inventory.add_all(amounts);
+		inventory
+	}
+
+	pub fn count(&self, resource: ResourceKind) -> u32 {
+		self.resources.get(&resource).copied().unwrap_or_default()
+	}
+	pub fn add(&mut self, resource: ResourceKind, amount: u32) {
+		let count = self.resources.entry(resource).or_default();
+		*count = count.saturating_add(amount);
+	}
+	pub fn add_all<'a>(&mut self, amounts: impl IntoIterator<Item = &'a ResourceAmount>) {
+		for amount in amounts {
+			self.add(amount.resource, amount.amount);
+		}
+	}
+	pub fn deposit(&mut self, resources: impl IntoIterator<Item = EResource>) {
+		for resource in resources {
+			self.add(resource.kind(), 1);
+		}
+	}
+
+	/// everything that's lacking to pay for `cost`. empty if it's affordable
+	pub fn missing_for(&self, cost: &[ResourceAmount]) -> Vec<ResourceAmount> {
+		let mut needed = Inventory::new();
+		needed.add_all(cost);
+
+		needed
+			.iter()
+			.filter_map(|(resource, amount)| {
+				let missing = amount.saturating_sub(self.count(resource));
+				(missing > 0).then(|| ResourceAmount::new(resource, missing))
+			})
+			.collect()
+	}
+	pub fn can_afford(&self, cost: &[ResourceAmount]) -> bool {
+		self.missing_for(cost).is_empty()
+	}
+	/// takes `cost` out of the inventory, or leaves it untouched and returns what's missing
+	pub fn try_spend(&mut self, cost: &[ResourceAmount]) -> Result<(), Vec<ResourceAmount>> {
+		let missing = self.missing_for(cost);
+		if !missing.is_empty() {
+			return Err(missing);
+		}
+
+		for amount in cost {
+			if let Some(count) = self.resources.get_mut(&amount.resource) {
+				*count -= amount.amount;
+			}
+		}
+		Ok(())
+	}
+	/// gives back `ratio` of `cost`, rounded down
+	pub fn refund(&mut self, cost: &[ResourceAmount], ratio: f32) {
+		let ratio = ratio.clamp(0.0, 1.0);
+		for amount in cost {
+			let refunded = (amount.amount as f32 * ratio).floor() as u32;
+			if refunded > 0 {
+				self.add(amount.resource, refunded);
+			}
+		}
+	}
+
+	/// every resource the player has at least one of
+	pub fn iter(&self) -> impl Iterator<Item = (ResourceKind, u32)> + '_ {
+		self.resources
+			.iter()
+			.map(|(resource, amount)| (*resource, *amount))
+			.filter(|(_, amount)| *amount > 0)
+	}
+	pub fn is_empty(&self) -> bool {
+		self.iter().next().is_none()
+	}
+}
+impl Display for Inventory {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.is_empty() {
+			return write!(f, "nothing");
+		}
+		let amounts = self
+			.iter()
+			.map(|(resource, amount)| ResourceAmount::new(resource, amount).to_string())
+			.collect::<Vec<_>>();
+		write!(f, "{}", amounts.join(", "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn spend_and_refund() {
+		let cost = [ResourceAmount::new(ResourceKind::Iron, 3)];
+		let mut inventory = Inventory::from_amounts(&[ResourceAmount::new(ResourceKind::Iron, 4)]);
+
+		assert!(inventory.try_spend(&cost).is_ok());
+		assert_eq!(inventory.count(ResourceKind::Iron), 1);
+
+		let missing = inventory.try_spend(&cost).unwrap_err();
+		assert_eq!(missing, vec![ResourceAmount::new(ResourceKind::Iron, 2)]);
+		assert_eq!(inventory.count(ResourceKind::Iron), 1);
+
+		inventory.refund(&cost, 0.5);
+		assert_eq!(inventory.count(ResourceKind::Iron), 2);
+	}
+}
@@ -6,7 +6,26 @@ pub const GAME_TICK_FREQUENCY: Duration = const {
 	Duration::from_millis(millis as u64)
 };
 
+/// how many simulation ticks it takes for `duration` to pass, rounded up
+pub const fn ticks_in(duration: Duration) -> u32 {
+	let tick = GAME_TICK_FREQUENCY.as_millis();
+	let millis = duration.as_millis();
+	((millis + tick - 1) / tick) as u32
+}
+/// the simulation time `ticks` ticks take. saturates instead of wrapping for absurd tick counts
+pub const fn ticks_duration(ticks: u64) -> Duration {
+	let tick = GAME_TICK_FREQUENCY.as_nanos() as u64;
+	Duration::from_nanos(tick.saturating_mul(ticks))
+}
+
 mod data;
 pub use data::*;
 mod provider;
 pub use provider::*;
+mod speed;
+pub use speed::*;
+mod inventory;
+pub use inventory::*;
+mod costs;
+pub use costs::*;
+pub mod save;
@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-use crate::{GameData, tool::Tool};
+use crate::{GameData, TimeControl, tool::Tool};
 
 /// provides the game \
 /// this is so `Game` can have a blanket implementation over `GameProvider`s so \
@@ -10,8 +10,16 @@ pub trait GameProvider {
 
 	/// called on every component tick by Game
 	fn standard_tick(&mut self);
+	/// advances the simulation by exactly one tick, even if time is paused
+	fn step(&mut self);
+	/// called by Game whenever the pause state or the simulation speed changes \
+	/// providers that tick on their own (like off-thread ones) should follow it
+	fn set_time_control(&mut self, _control: TimeControl) {}
 
 	fn tool_use(&mut self, tool: &Tool, pos: (i32, i32));
+
+	/// applies a change to the game data that doesn't come from a tool, like spending from the inventory
+	fn modify<R>(&mut self, f: impl FnOnce(&mut GameData) -> R) -> R;
 }
 
 impl GameProvider for GameData {
@@ -21,8 +29,17 @@ impl GameProvider for GameData {
 	fn standard_tick(&mut self) {
 		self.tick();
 	}
+	fn step(&mut self) {
+		self.tick();
+	}
 
 	fn tool_use(&mut self, tool: &Tool, pos: (i32, i32)) {
-		tool.r#use(self, pos);
+		if let Err(err) = tool.r#use(self, pos) {
+			mklogger::eprintln!("{err}");
+		}
+	}
+
+	fn modify<R>(&mut self, f: impl FnOnce(&mut GameData) -> R) -> R {
+		f(self)
 	}
 }
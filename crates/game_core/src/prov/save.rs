@@ -0,0 +1,287 @@
+// the .cgs container: a versioned header in front of the CBOR GameDataSave
+
+use std::io::Write;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_cbor::Value;
+
+use crate::{
+	CostTable, Inventory,
+	codec::{Compression, PaletteGrid},
+};
+
+/// the first bytes of every .cgs with a header. saves without it are version 0
+pub const SAVE_MAGIC: &[u8; 4] = b"CGSV";
+/// the save format version written by this version of the game
+pub const SAVE_VERSION: u16 = 3;
+
+/// written as CBOR right after the magic and the version
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveHeader {
+	/// the version of the game that wrote the save, for error messages
+	pub game_version: String,
+	/// how the body after the header is compressed. headers from before compression don't have it
+	#[serde(default)]
+	pub compression: Compression,
+}
+impl SaveHeader {
+	pub fn current(compression: Compression) -> Self {
+		Self {
+			game_version: env!("CARGO_PKG_VERSION").to_string(),
+			compression,
+		}
+	}
+}
+
+type Migration = fn(Value) -> anyhow::Result<Value>;
+/// `MIGRATIONS[n]` turns the body of a version n save into a version n + 1 one. \
+/// when the layout of GameDataSave changes, bump [SAVE_VERSION] and add a migration here
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// version 0 saves were written before inventories and costs existed
+fn v0_to_v1(mut body: Value) -> anyhow::Result<Value> {
+	let Value::Map(map) = &mut body else {
+		return Err(mklogger::anyhow!("expected the save to be a map"));
+	};
+	let key = |key: &str| Value::Text(key.to_string());
+
+	if !map.contains_key(&key("inventory")) {
+		map.insert(
+			key("inventory"),
+			serde_cbor::value::to_value(Inventory::new())?,
+		);
+	}
+	if !map.contains_key(&key("costs")) {
+		map.insert(
+			key("costs"),
+			serde_cbor::value::to_value(CostTable::free())?,
+		);
+	}
+	Ok(body)
+}
+
+/// version 1 saves didn't keep the queued moves and the tick count
+fn v1_to_v2(mut body: Value) -> anyhow::Result<Value> {
+	let Value::Map(map) = &mut body else {
+		return Err(mklogger::anyhow!("expected the save to be a map"));
+	};
+	let key = |key: &str| Value::Text(key.to_string());
+
+	map.entry(key("moves_queue"))
+		.or_insert_with(|| Value::Map(Default::default()));
+	map.entry(key("ticks")).or_insert(Value::Integer(0));
+	Ok(body)
+}
+
+/// version 2 saves had the grid as every cell one by one, instead of a [PaletteGrid]
+fn v2_to_v3(mut body: Value) -> anyhow::Result<Value> {
+	let Value::Map(map) = &mut body else {
+		return Err(mklogger::anyhow!("expected the save to be a map"));
+	};
+
+	let grid = map
+		.get_mut(&Value::Text("grid".to_string()))
+		.with_context(|| format!("save has no grid"))?;
+	let columns: Vec<Vec<Value>> = serde_cbor::value::from_value(grid.clone())
+		.with_context(|| format!("expected the grid to be a list of columns"))?;
+	*grid = serde_cbor::value::to_value(PaletteGrid::encode(&columns)?)?;
+	Ok(body)
+}
+
+/// runs every migration from `version` up to [SAVE_VERSION]
+pub fn migrate(mut body: Value, version: u16) -> anyhow::Result<Value> {
+	if version > SAVE_VERSION {
+		return Err(mklogger::anyhow!(
+			"save version {version} is newer than the newest supported version {SAVE_VERSION}"
+		));
+	}
+	for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+		body = migration(body)
+			.with_context(|| format!("while migrating save from version {from} to {}", from + 1))?;
+	}
+	Ok(body)
+}
+
+/// writes the header, then the body compressed the way the header says
+pub fn write_save<W: Write>(
+	write: &mut W,
+	body: Vec<u8>,
+	compression: Compression,
+) -> anyhow::Result<()> {
+	write.write_all(SAVE_MAGIC)?;
+	write.write_all(&SAVE_VERSION.to_le_bytes())?;
+	serde_cbor::to_writer(&mut *write, &SaveHeader::current(compression))
+		.with_context(|| format!("while writing save header"))?;
+	write.write_all(&compression.compress(body)?)?;
+	Ok(())
+}
+
+/// splits a save into its header and body, migrated to the current version. `None` if the save
+/// isn't CBOR at all, like the bincode saves from before CBOR
+pub fn read_body(bytes: &[u8]) -> anyhow::Result<Option<(Option<SaveHeader>, Value)>> {
+	let Some(rest) = bytes.strip_prefix(SAVE_MAGIC) else {
+		// version 0, no header
+		let body = match serde_cbor::from_slice::<Value>(bytes) {
+			Ok(body @ Value::Map(_)) => body,
+			_ => return Ok(None),
+		};
+		return Ok(Some((None, migrate(body, 0)?)));
+	};
+
+	let (version, rest) = match rest {
+		[a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]), rest),
+		_ => return Err(mklogger::anyhow!("save header is cut short")),
+	};
+	let mut deserializer = serde_cbor::Deserializer::from_slice(rest);
+	let header = SaveHeader::deserialize(&mut deserializer)
+		.with_context(|| format!("while reading the header of a version {version} save"))?;
+	let body = header
+		.compression
+		.decompress(&rest[deserializer.byte_offset()..])
+		.with_context(|| format!("while decompressing the body of a version {version} save"))?;
+	let body = serde_cbor::from_slice::<Value>(&body)
+		.with_context(|| format!("while reading the body of a version {version} save"))?;
+
+	let body = migrate(body, version)
+		.with_context(|| format!("save was written by conveyor-game {}", header.game_version))?;
+	Ok(Some((Some(header), body)))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use super::*;
+	use crate::{
+		EResource, ETile, GameDataSave, ResourceAmount, ResourceKind,
+		buildings::{Building, BuildingKind, EBuilding},
+		maps::OrIndexed,
+	};
+
+	fn load(bytes: &[u8]) -> GameDataSave {
+		GameDataSave::load_as_either(&mut Cursor::new(bytes)).unwrap()
+	}
+	/// every fixture has the same 2x2 grid with a single iron ore tile
+	fn assert_grid(save: &GameDataSave) {
+		assert_eq!(save.grid.len(), 2);
+		assert!(save.grid.iter().all(|col| col.len() == 2));
+		assert!(matches!(save.grid[0][1].0, ETile::IronOre(_)));
+		assert!(matches!(
+			save.grid[1][1],
+			(ETile::Stone(_), OrIndexed::Item(EBuilding::Nothing(_)))
+		));
+	}
+	fn assert_economy(save: &GameDataSave) {
+		assert_eq!(save.inventory.count(ResourceKind::Iron), 12);
+		assert_eq!(
+			save.costs.cost_of(BuildingKind::Conveyor),
+			[ResourceAmount::new(ResourceKind::Iron, 1)]
+		);
+	}
+
+	/// the version 2 and 3 fixtures also have an extractor about to extract in the other corner and
+	/// a move queued up
+	fn assert_state(save: &GameDataSave) {
+		let OrIndexed::Item(EBuilding::SmallExtractor(extractor)) = &save.grid[0][0].1 else {
+			panic!("expected an extractor, found {:?}", save.grid[0][0].1);
+		};
+		assert!(extractor.needs_poll());
+		assert_eq!(save.moves_queue.get(&(1, 0)), Some(&vec![(0, 0)]));
+		assert_eq!(save.ticks, 37);
+	}
+
+	#[test]
+	fn loads_bincode() {
+		let save = load(include_bytes!("fixtures/bincode.cgs"));
+		assert_grid(&save);
+		assert!(save.inventory.is_empty());
+	}
+
+	#[test]
+	fn loads_bincode_buildings() {
+		// a protected extractor, a smelter with fuel and raw iron queued, a protected sink holding iron
+		let save = load(include_bytes!("fixtures/bincode_buildings.cgs"));
+		let building = |x: usize, y: usize| match &save.grid[x][y].1 {
+			OrIndexed::Item(building) => building.clone(),
+			other => panic!("expected a building at {x}, {y}, found {other:?}"),
+		};
+
+		assert!(matches!(building(0, 0), EBuilding::SmallExtractor(_)));
+		assert!(building(0, 0).is_protected());
+		assert!(matches!(building(1, 0), EBuilding::Smelter(_)));
+		// fuel for one more to smelt and one more spot in the queue
+		assert_eq!(building(1, 0).capacity_for(&EResource::raw_iron(), None), 2);
+		assert!(matches!(building(1, 1), EBuilding::ChannelConsumer(_)));
+		assert!(building(1, 1).is_protected());
+		assert_eq!(building(1, 1).take_deposits(), [EResource::iron()]);
+	}
+
+	#[test]
+	fn loads_v0() {
+		let save = load(include_bytes!("fixtures/v0.cgs"));
+		assert_grid(&save);
+		assert!(save.inventory.is_empty());
+		assert_eq!(save.costs, CostTable::free());
+	}
+
+	#[test]
+	fn loads_v0_with_economy() {
+		let save = load(include_bytes!("fixtures/v0_economy.cgs"));
+		assert_grid(&save);
+		assert_economy(&save);
+	}
+
+	#[test]
+	fn loads_v1() {
+		let bytes = include_bytes!("fixtures/v1.cgs");
+		let (header, _) = read_body(bytes).unwrap().unwrap();
+		assert_eq!(header.unwrap().game_version, "0.1.0");
+
+		let save = load(bytes);
+		assert_grid(&save);
+		assert_economy(&save);
+	}
+
+	#[test]
+	fn loads_v2() {
+		let save = load(include_bytes!("fixtures/v2.cgs"));
+		assert_grid(&save);
+		assert_economy(&save);
+		assert_state(&save);
+	}
+
+	#[test]
+	fn loads_v3() {
+		let bytes = include_bytes!("fixtures/v3.cgs");
+		let (header, _) = read_body(bytes).unwrap().unwrap();
+		assert_eq!(header.unwrap().compression, Compression::None);
+
+		let save = load(bytes);
+		assert_grid(&save);
+		assert_economy(&save);
+		assert_state(&save);
+	}
+
+	#[test]
+	fn saves_current_version() {
+		let save = load(include_bytes!("fixtures/v0_economy.cgs"));
+		let mut bytes = Vec::new();
+		save.save(&mut bytes).unwrap();
+
+		assert!(bytes.starts_with(SAVE_MAGIC));
+		assert_eq!(bytes[4..6], SAVE_VERSION.to_le_bytes());
+		let (header, _) = read_body(&bytes).unwrap().unwrap();
+		assert_eq!(header, Some(SaveHeader::current(Compression::Zstd)));
+
+		let loaded = load(&bytes);
+		assert_grid(&loaded);
+		assert_economy(&loaded);
+	}
+
+	#[test]
+	fn rejects_newer_versions() {
+		let body = Value::Map(Default::default());
+		assert!(migrate(body, SAVE_VERSION + 1).is_err());
+	}
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use super::GAME_TICK_FREQUENCY;
+
+/// how fast the simulation runs compared to [GAME_TICK_FREQUENCY]
+#[derive(
+	Copy, Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum SimSpeed {
+	Half,
+	#[default]
+	Normal,
+	Double,
+	Quadruple,
+}
+impl SimSpeed {
+	pub const fn multiplier(self) -> f32 {
+		match self {
+			Self::Half => 0.5,
+			Self::Normal => 1.0,
+			Self::Double => 2.0,
+			Self::Quadruple => 4.0,
+		}
+	}
+	pub const fn name(self) -> &'static str {
+		match self {
+			Self::Half => "0.5x",
+			Self::Normal => "1x",
+			Self::Double => "2x",
+			Self::Quadruple => "4x",
+		}
+	}
+
+	/// the wall-clock time between two simulation ticks at this speed
+	pub fn tick_duration(self) -> Duration {
+		GAME_TICK_FREQUENCY.div_f32(self.multiplier())
+	}
+
+	pub fn all() -> impl Iterator<Item = Self> {
+		[Self::Half, Self::Normal, Self::Double, Self::Quadruple].into_iter()
+	}
+}
+
+/// everything a [crate::GameProvider] needs to know about the passage of time
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimeControl {
+	pub paused: bool,
+	pub speed: SimSpeed,
+}
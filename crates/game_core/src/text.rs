@@ -0,0 +1,257 @@
+// human readable saves and level files, as RON. meant for diffing, fixing by hand and fixtures
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	CostTable, ETile, GameDataSave, Inventory,
+	buildings::{EBuilding, PlacedBuilding},
+	codec::LevelFile,
+	maps::{MovesQueue, OrIndexed, Tilemap},
+};
+
+/// the text form of a [GameDataSave]
+#[derive(Debug, Serialize, Deserialize)]
+struct TextSave {
+	/// top to bottom, one [ETile::symbol] per tile
+	tiles: Vec<String>,
+	/// every cell that has a building on it, by x, y
+	#[serde(default)]
+	buildings: BTreeMap<(usize, usize), OrIndexed<EBuilding>>,
+	#[serde(default)]
+	external_buildings: Vec<EBuilding>,
+	#[serde(default)]
+	inventory: Inventory,
+	#[serde(default = "CostTable::free")]
+	costs: CostTable,
+	#[serde(default)]
+	moves_queue: MovesQueue,
+	#[serde(default)]
+	ticks: u64,
+}
+
+/// the text form of a [LevelFile]
+#[derive(Debug, Serialize, Deserialize)]
+struct TextLevel {
+	/// top to bottom, one [ETile::symbol] per tile
+	tiles: Vec<String>,
+	#[serde(default)]
+	buildings: Vec<PlacedBuilding>,
+}
+
+/// anything deeper than the fields and the map entries stays on one line, so every building gets a
+/// line of its own
+fn pretty() -> PrettyConfig {
+	PrettyConfig::new().depth_limit(2)
+}
+
+fn to_rows<T>(columns: &[Vec<T>], tile: impl Fn(&T) -> &ETile) -> Vec<String> {
+	let height = columns.first().map(Vec::len).unwrap_or_default();
+	let row = |y| {
+		let row = columns.iter().filter_map(|column| column.get(y));
+		row.map(|cell| tile(cell).symbol()).collect()
+	};
+	(0..height).map(row).collect()
+}
+/// rows of different lengths give columns of different heights, left for the caller to reject
+fn to_columns(rows: &[String]) -> anyhow::Result<Vec<Vec<ETile>>> {
+	let rows = rows
+		.iter()
+		.enumerate()
+		.map(|(y, row)| {
+			let tiles = row.chars().enumerate().map(|(x, symbol)| {
+				ETile::from_symbol(symbol)
+					.with_context(|| format!("unknown tile symbol {symbol:?} at {x}, {y}"))
+			});
+			tiles.collect::<anyhow::Result<Vec<_>>>()
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+	let columns = (0..width)
+		.map(|x| rows.iter().filter_map(|row| row.get(x).cloned()).collect())
+		.collect();
+	Ok(columns)
+}
+
+impl GameDataSave {
+	pub fn to_text(&self) -> anyhow::Result<String> {
+		let mut buildings = BTreeMap::new();
+		for (x, column) in self.grid.iter().enumerate() {
+			for (y, (_, building)) in column.iter().enumerate() {
+				if !matches!(building, OrIndexed::Item(EBuilding::Nothing(_))) {
+					buildings.insert((x, y), building.clone());
+				}
+			}
+		}
+
+		let text = TextSave {
+			tiles: to_rows(&self.grid, |(tile, _)| tile),
+			buildings,
+			external_buildings: self.external_buildings.clone(),
+			inventory: self.inventory.clone(),
+			costs: self.costs.clone(),
+			moves_queue: self.moves_queue.clone(),
+			ticks: self.ticks,
+		};
+		ron::ser::to_string_pretty(&text, pretty())
+			.with_context(|| format!("while writing save as text"))
+	}
+
+	/// checked the way [Self::take] checks saves, so broken files are rejected here
+	pub fn from_text(text: &str) -> anyhow::Result<Self> {
+		let text: TextSave =
+			ron::from_str(text).with_context(|| format!("while parsing text save"))?;
+
+		let grid = to_columns(&text.tiles)?.into_iter().map(|column| {
+			let column = column.into_iter();
+			column
+				.map(|tile| (tile, OrIndexed::Item(EBuilding::nothing())))
+				.collect()
+		});
+		let mut save = Self {
+			grid: grid.collect(),
+			external_buildings: text.external_buildings,
+			inventory: text.inventory,
+			costs: text.costs,
+			moves_queue: text.moves_queue,
+			ticks: text.ticks,
+		};
+		save.assert_uniform_size()?;
+
+		let (w, h) = save.world_size();
+		let externals = save.external_buildings.len();
+		for ((x, y), building) in text.buildings {
+			if let OrIndexed::Indexed { index, .. } = building {
+				if index >= externals {
+					return Err(mklogger::anyhow!(
+						"building at {x}, {y} refers to external building {index}, there are only {externals}"
+					));
+				}
+			}
+			let cell = save.grid.get_mut(x).and_then(|column| column.get_mut(y));
+			let cell = cell
+				.with_context(|| format!("building at {x}, {y} is outside of the {w}x{h} world"))?;
+			cell.1 = building;
+		}
+		Ok(save)
+	}
+}
+
+pub fn level_to_text(level: &LevelFile) -> anyhow::Result<String> {
+	let columns = level.tilemap.iter_inner().cloned().collect::<Vec<_>>();
+	let text = TextLevel {
+		tiles: to_rows(&columns, |tile| tile),
+		buildings: level.buildings.clone(),
+	};
+	ron::ser::to_string_pretty(&text, pretty())
+		.with_context(|| format!("while writing level as text"))
+}
+pub fn level_from_text(text: &str) -> anyhow::Result<LevelFile> {
+	let text: TextLevel =
+		ron::from_str(text).with_context(|| format!("while parsing text level"))?;
+	Ok(LevelFile {
+		tilemap: Tilemap::from_vec(to_columns(&text.tiles)?)?,
+		buildings: text.buildings,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use utils::Direction;
+
+	use super::*;
+	use crate::{
+		GameData, ResourceKind,
+		buildings::BuildingKind,
+		maps::{BuildingsMap, TilemapExt},
+	};
+
+	fn save() -> GameDataSave {
+		let mut tilemap = Tilemap::stone(4, 2);
+		*tilemap.at_mut_usize((0, 1)).unwrap() = ETile::iron_ore();
+		*tilemap.at_mut_usize((3, 0)).unwrap() = ETile::coal_ore();
+
+		let mut buildings = BuildingsMap::new_default(4, 2);
+		*buildings.at_mut((0, 1)).unwrap() = EBuilding::small_extractor();
+		*buildings.at_mut((1, 1)).unwrap() = EBuilding::conveyor(Direction::Right);
+
+		let mut data = GameData::new(tilemap, buildings);
+		for _ in 0..20 {
+			data.tick();
+		}
+		GameDataSave::new(&data).unwrap()
+	}
+
+	#[test]
+	fn save_round_trip() {
+		let save = save();
+		let text = save.to_text().unwrap();
+		assert!(text.contains("\"...c\""), "{text}");
+		assert!(text.contains("\"i...\""), "{text}");
+
+		let loaded = GameDataSave::from_text(&text).unwrap();
+		assert_eq!(
+			serde_cbor::to_vec(&loaded).unwrap(),
+			serde_cbor::to_vec(&save).unwrap()
+		);
+		loaded.take().unwrap();
+	}
+
+	#[test]
+	fn reads_hand_written_saves() {
+		let text = r#"(
+			tiles: ["ii..", "...."],
+			buildings: {
+				(1, 0): Item(Conveyor((dir: Bottom, holding: []))),
+			},
+		)"#;
+		let save = GameDataSave::from_text(text).unwrap();
+		assert_eq!(save.world_size(), (4, 2));
+		assert!(matches!(
+			save.grid[1][0].1,
+			OrIndexed::Item(EBuilding::Conveyor(_))
+		));
+		assert_eq!(save.inventory.count(ResourceKind::Iron), 0);
+		save.take().unwrap();
+	}
+
+	#[test]
+	fn rejects_broken_saves() {
+		let ragged = r#"(tiles: ["....", "..."])"#;
+		assert!(GameDataSave::from_text(ragged).is_err());
+		let unknown_tile = r#"(tiles: ["..x."])"#;
+		assert!(GameDataSave::from_text(unknown_tile).is_err());
+		let outside =
+			r#"(tiles: ["...."], buildings: { (0, 1): Item(Conveyor((dir: Left, holding: []))) })"#;
+		assert!(GameDataSave::from_text(outside).is_err());
+	}
+
+	#[test]
+	fn level_round_trip() {
+		let save = save();
+		let level = LevelFile {
+			tilemap: save.take().unwrap().tilemap,
+			buildings: vec![PlacedBuilding {
+				pos: (1, 0),
+				building: BuildingKind::Conveyor,
+				dir: Some(Direction::Left),
+				protected: false,
+			}],
+		};
+		let text = level_to_text(&level).unwrap();
+		let loaded = level_from_text(&text).unwrap();
+		assert_eq!(
+			serde_cbor::to_vec(&loaded.tilemap).unwrap(),
+			serde_cbor::to_vec(&level.tilemap).unwrap()
+		);
+		assert_eq!(loaded.buildings, level.buildings);
+
+		let tiles_only = level_from_text(r#"(tiles: ["..", ".."])"#).unwrap();
+		assert!(tiles_only.buildings.is_empty());
+		assert!(level_from_text(r#"(tiles: ["..", "."])"#).is_err());
+	}
+}
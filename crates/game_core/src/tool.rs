@@ -1,13 +1,20 @@
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+	borrow::Cow,
+	fmt::{Debug, Display},
+};
 
+#[cfg(feature = "render")]
+use crate::render::TILE_RENDER_SIZE;
 use crate::{
-	GameData,
-	buildings::{Building, EBuilding, Nothing},
+	GameData, ResourceAmount,
+	buildings::{Building, BuildingKind, EBuilding, Nothing},
 	maps::OrIndexed,
-	render::TILE_RENDER_SIZE,
 };
+#[cfg(feature = "render")]
 use sui::{Details, Layable, LayableExt};
-use textures::{TextureID, Textures};
+use textures::TextureID;
+#[cfg(feature = "render")]
+use textures::Textures;
 use utils::Direction;
 
 pub fn tools() -> impl Iterator<Item = Tool> {
@@ -21,10 +28,42 @@ pub fn tools() -> impl Iterator<Item = Tool> {
 			Tool::PlaceBuilding(EBuilding::junction()),
 			Tool::PlaceBuilding(EBuilding::router()),
 			Tool::PlaceBuilding(EBuilding::smelter()),
+			Tool::PlaceBuilding(EBuilding::core()),
 		])
 }
 
 #[derive(Clone, Debug)]
+pub enum ToolError {
+	CantAfford {
+		building: Cow<'static, str>,
+		missing: Vec<ResourceAmount>,
+	},
+	/// the building couldn't be put down, like when there's a protected building in the way
+	CantPlace {
+		building: Cow<'static, str>,
+		pos: (i32, i32),
+	},
+}
+impl Display for ToolError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::CantAfford { building, missing } => {
+				let missing = missing
+					.iter()
+					.map(ResourceAmount::to_string)
+					.collect::<Vec<_>>()
+					.join(", ");
+				write!(f, "can't afford {building}: missing {missing}")
+			}
+			Self::CantPlace { building, pos } => {
+				write!(f, "can't place {building} at {}, {}", pos.0, pos.1)
+			}
+		}
+	}
+}
+impl std::error::Error for ToolError {}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Tool {
 	PlaceBuilding(EBuilding),
 	/// places 4 buildings and hooks them up to the same building impl
@@ -52,37 +91,115 @@ impl Tool {
 		}
 	}
 
-	pub fn r#use(&self, game: &mut GameData, pos: (i32, i32)) {
-		let mut f = || match self {
+	pub fn building(&self) -> &EBuilding {
+		match self {
+			Tool::PlaceBuilding(building) => building,
+			Tool::Place2x2(building) => building,
+		}
+	}
+	/// the grid positions this tool covers when used at `pos`
+	fn footprint(&self, pos: (i32, i32)) -> heapless::Vec<(i32, i32), 4> {
+		match self {
+			Tool::PlaceBuilding(_) => [pos].into_iter().collect(),
+			Tool::Place2x2(_) => [(0, 0), (1, 0), (0, 1), (1, 1)]
+				.into_iter()
+				.map(|(rx, ry)| (pos.0 + rx, pos.1 + ry))
+				.collect(),
+		}
+	}
+
+	/// uses the tool, paying for the building out of the player's inventory \
+	/// every building that gets replaced refunds part of its cost
+	pub fn r#use(&self, game: &mut GameData, pos: (i32, i32)) -> Result<(), ToolError> {
+		let building = self.building();
+		let kind = building.kind();
+
+		// every cell is checked before anything is written, so a 2x2 building that doesn't fit
+		// leaves the buildings it would've replaced alone
+		for place_pos in self.footprint(pos) {
+			let protected = game.buildings.at(place_pos).map(Building::is_protected);
+			if protected.unwrap_or(true) {
+				return Err(ToolError::CantPlace {
+					building: building.name(),
+					pos: place_pos,
+				});
+			}
+		}
+
+		// the buildings that will be replaced, each indexed building only once
+		let mut replaced = Vec::new();
+		let mut replaced_indices = heapless::Vec::<usize, 4>::new();
+		for place_pos in self.footprint(pos) {
+			match game.buildings.grid_at(place_pos) {
+				Some(OrIndexed::Item(existing)) => replaced.push(existing.kind()),
+				Some(OrIndexed::Indexed { index, .. }) if !replaced_indices.contains(index) => {
+					let _ = replaced_indices.push(*index);
+					if let Some(existing) = game.buildings.indexed(*index) {
+						replaced.push(existing.kind());
+					}
+				}
+				_ => {}
+			}
+		}
+		replaced.retain(|existing| *existing != BuildingKind::Nothing);
+
+		// turning or re-placing a building over one of the same kind is free
+		let reorient = matches!(replaced.as_slice(), [existing] if *existing == kind);
+		let cost = if reorient {
+			Vec::new()
+		} else {
+			game.costs.cost_of(kind).to_vec()
+		};
+
+		let missing = game.inventory.missing_for(&cost);
+		if !missing.is_empty() {
+			return Err(ToolError::CantAfford {
+				building: building.name(),
+				missing,
+			});
+		}
+
+		let placed = match self {
 			Self::PlaceBuilding(building) => game
 				.buildings
-				.try_place(pos, OrIndexed::Item(building.clone())),
+				.try_place(pos, OrIndexed::Item(building.clone()))
+				.map_err(|_| pos),
 
 			Self::Place2x2(building) => {
 				let index = game.buildings.insert_indexed(building.clone());
 				let indexed = OrIndexed::Indexed { index, root: pos };
 
-				let rels = [(0, 0), (1, 0), (0, 1), (1, 1)];
-				let rels = rels.into_iter().map(|(rx, ry)| (pos.0 + rx, pos.1 + ry));
-
-				for place_pos in rels {
-					game.buildings.try_place(place_pos, indexed.clone())?;
-				}
-				Ok(())
+				self.footprint(pos).into_iter().try_for_each(|place_pos| {
+					game.buildings
+						.try_place(place_pos, indexed.clone())
+						.map(|_| ())
+						.map_err(|_| place_pos)
+				})
 			}
 		};
+		if let Err(at) = placed {
+			return Err(ToolError::CantPlace {
+				building: building.name(),
+				pos: at,
+			});
+		}
 
-		match f() {
-			Ok(_) => {}
-			Err(err) => {
-				mklogger::eprintln!("failed to place {err:?}")
+		// checked above, can't fail
+		let _ = game.inventory.try_spend(&cost);
+		if !reorient {
+			let ratio = game.costs.refund_ratio;
+			for existing in replaced {
+				let refund = game.costs.cost_of(existing).to_vec();
+				game.inventory.refund(&refund, ratio);
 			}
 		}
+		Ok(())
 	}
 	// pub fn held(&self, game: &mut GameData, pos: (i32, i32)) {}
 	// pub fn release(&mut self, game: &mut GameData, pos: (i32, i32)) {}
 }
 
+#[cfg(feature = "render")]
 impl Tool {
 	pub fn render_preview<'a>(
 		&'a self,
@@ -148,3 +265,43 @@ impl Tool {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		CostTable, Inventory, ResourceKind,
+		maps::{BuildingsMap, Tilemap, TilemapExt},
+	};
+
+	fn game() -> GameData {
+		let inventory = Inventory::from_amounts(&[ResourceAmount::new(ResourceKind::Iron, 20)]);
+		let data = GameData::new(Tilemap::stone(4, 4), BuildingsMap::new_default(4, 4));
+		data.with_economy(inventory, CostTable::default())
+	}
+
+	#[test]
+	fn failed_2x2_placements_change_nothing() {
+		let mut game = game();
+		let conveyor = Tool::PlaceBuilding(EBuilding::conveyor(Direction::Right));
+		conveyor.r#use(&mut game, (1, 1)).unwrap();
+		let mut core = EBuilding::core();
+		core.set_protected(true).unwrap();
+		*game.buildings.at_mut((2, 2)).unwrap() = core;
+		let iron = game.inventory.count(ResourceKind::Iron);
+
+		let extractor = Tool::Place2x2(EBuilding::small_extractor());
+		// over the conveyor, but the protected core is in the way
+		assert!(extractor.r#use(&mut game, (1, 1)).is_err());
+		// half outside of the world
+		assert!(extractor.r#use(&mut game, (3, 0)).is_err());
+
+		assert_eq!(game.inventory.count(ResourceKind::Iron), iron);
+		let at = game.buildings.at((1, 1)).map(EBuilding::kind);
+		assert_eq!(at, Some(BuildingKind::Conveyor));
+		assert!(game.buildings.indexed(0).is_none());
+
+		extractor.r#use(&mut game, (0, 2)).unwrap();
+		assert_eq!(game.inventory.count(ResourceKind::Iron), iron - 4);
+	}
+}
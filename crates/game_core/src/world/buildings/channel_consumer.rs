@@ -4,25 +4,82 @@ use crate::{EResource, buildings::Building};
 use textures::TextureID;
 use utils::Direction;
 
+/// a resource received by a [ChannelConsumer]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+	pub resource: EResource,
+	/// the tick of the world it arrived on, what [crate::prov::GameData::ticks] is once that tick
+	/// is done
+	pub tick: u64,
+}
+
+/// how much a consumer without a channel takes in a single move
+const SINK_CAPACITY: usize = 20;
+
+/// sends everything it receives down a channel, and deposits it for the player. \
+/// without a channel, like after being loaded from a save, it's a plain sink until
+/// [Building::connect_sink] is called
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ChannelConsumer {
 	#[serde(skip, default)]
-	tx: Option<mpsc::Sender<EResource>>,
+	tx: Option<mpsc::Sender<Delivery>>,
+	/// the tick of the world deliveries are stamped with, kept up by [Building::tick]
+	#[serde(default)]
+	ticks: u64,
+	/// everything successfully sent, waiting to be collected into the player's inventory
+	#[serde(default)]
+	deposits: Vec<EResource>,
 	pub protected: bool,
 }
+
+/// a [ChannelConsumer] as bincode saves laid it out, from before its tick was saved
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct LegacyChannelConsumer {
+	deposits: Vec<EResource>,
+	protected: bool,
+}
+impl From<LegacyChannelConsumer> for ChannelConsumer {
+	fn from(legacy: LegacyChannelConsumer) -> Self {
+		Self {
+			tx: None,
+			ticks: 0,
+			deposits: legacy.deposits,
+			protected: legacy.protected,
+		}
+	}
+}
+
 impl ChannelConsumer {
-	pub fn new() -> (Self, mpsc::Receiver<EResource>) {
+	pub fn new() -> (Self, mpsc::Receiver<Delivery>) {
 		let (tx, rx) = mpsc::channel(20);
 		let consumer = Self {
 			tx: Some(tx),
+			ticks: 0,
+			deposits: Vec::new(),
 			protected: false,
 		};
 
 		(consumer, rx)
 	}
+	/// a consumer without a channel, a plain sink until [Building::connect_sink] is called
+	pub fn unconnected() -> Self {
+		Self {
+			tx: None,
+			ticks: 0,
+			deposits: Vec::new(),
+			protected: false,
+		}
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.tx.is_some()
+	}
 
 	fn capacity_unwrapped(&self) -> usize {
-		self.tx.as_ref().map(|tx| tx.capacity()).unwrap_or_default()
+		match &self.tx {
+			Some(tx) => tx.capacity(),
+			None => SINK_CAPACITY,
+		}
 	}
 }
 
@@ -34,6 +91,10 @@ impl Building for ChannelConsumer {
 		TextureID::ChannelConsumer
 	}
 
+	fn tick(&mut self, tick: u64) {
+		self.ticks = tick;
+	}
+
 	fn can_receive(&self, _from: Option<Direction>) -> bool {
 		self.capacity_unwrapped() > 0
 	}
@@ -42,12 +103,19 @@ impl Building for ChannelConsumer {
 	}
 	fn receive(&mut self, resource: EResource, _from: Option<Direction>) {
 		let mut drop_tx = false;
-		if let Some(tx) = &mut self.tx {
-			match tx.try_send(resource) {
-				Ok(a) => a,
-				Err(mpsc::error::TrySendError::Closed(_)) => drop_tx = true,
-				Err(_) => {}
+		match &mut self.tx {
+			Some(tx) => {
+				let delivery = Delivery {
+					resource: resource.clone(),
+					tick: self.ticks,
+				};
+				match tx.try_send(delivery) {
+					Ok(()) => self.deposits.push(resource),
+					Err(mpsc::error::TrySendError::Closed(_)) => drop_tx = true,
+					Err(_) => {}
+				}
 			}
+			None => self.deposits.push(resource),
 		}
 		if drop_tx {
 			self.tx = None;
@@ -55,6 +123,13 @@ impl Building for ChannelConsumer {
 		}
 	}
 
+	fn take_deposits(&mut self) -> Vec<EResource> {
+		std::mem::take(&mut self.deposits)
+	}
+	fn connect_sink(&mut self, tx: &mpsc::Sender<Delivery>) {
+		self.tx = Some(tx.clone());
+	}
+
 	fn is_protected(&self) -> bool {
 		self.protected
 	}
@@ -1,9 +1,14 @@
 // use std::time::{Duration, Instant};
 
+#[cfg(feature = "render")]
 use sui::{Layable, LayableExt, raylib::prelude::RaylibDraw, tex::Texture};
 
-use crate::{EResource, Resource, buildings::Building, render::TILE_RENDER_SIZE};
-use textures::{TextureID, Textures};
+use crate::{EResource, buildings::Building};
+#[cfg(feature = "render")]
+use crate::{Resource, render::TILE_RENDER_SIZE};
+use textures::TextureID;
+#[cfg(feature = "render")]
+use textures::Textures;
 use utils::Direction;
 
 pub const CONVEYOR_CAPACITY: usize = 3;
@@ -34,6 +39,7 @@ impl Building for Conveyor {
 		TextureID::ConveyorTop
 	}
 
+	#[cfg(feature = "render")]
 	fn render<'a>(
 		&'a self,
 		textures: &'a Textures,
@@ -114,6 +120,7 @@ impl Building for Conveyor {
 			holding: &self.holding,
 		}
 	}
+	#[cfg(feature = "render")]
 	fn tool_icon_render(
 		&self,
 		textures: &Textures,
@@ -0,0 +1,46 @@
+use crate::{EResource, buildings::Building};
+use textures::TextureID;
+use utils::Direction;
+
+/// how many resources a core can take in a single tick
+pub const CORE_CAPACITY: usize = 16;
+
+/// accepts every resource and hands it over to the player's inventory
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Core {
+	/// resources received since the last time they were collected into the inventory
+	#[serde(default)]
+	deposits: Vec<EResource>,
+	#[serde(default)]
+	protected: bool,
+}
+impl Building for Core {
+	fn name(&self) -> std::borrow::Cow<'static, str> {
+		"core".into()
+	}
+	fn texture_id(&self) -> TextureID {
+		TextureID::ChannelConsumer
+	}
+
+	fn can_receive(&self, _from: Option<Direction>) -> bool {
+		self.deposits.len() < CORE_CAPACITY
+	}
+	fn capacity_for(&self, _resource: &EResource, _from: Option<Direction>) -> i32 {
+		CORE_CAPACITY as i32 - self.deposits.len() as i32
+	}
+	fn receive(&mut self, resource: EResource, _from: Option<Direction>) {
+		self.deposits.push(resource);
+	}
+
+	fn take_deposits(&mut self) -> Vec<EResource> {
+		std::mem::take(&mut self.deposits)
+	}
+
+	fn is_protected(&self) -> bool {
+		self.protected
+	}
+	fn set_protected(&mut self, protected: bool) -> Result<(), ()> {
+		self.protected = protected;
+		Ok(())
+	}
+}
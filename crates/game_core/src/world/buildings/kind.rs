@@ -0,0 +1,142 @@
+use anyhow::Context;
+use utils::Direction;
+
+use super::{Building, ChannelConsumer, EBuilding};
+use crate::maps::{BuildingsMap, OrIndexed};
+
+/// data-less counterpart of [EBuilding], used where buildings are referred to by hand
+/// (cost tables, level files)
+#[derive(
+	Copy,
+	Clone,
+	Debug,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	strum::EnumIter,
+	serde::Serialize,
+	serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildingKind {
+	Nothing,
+	SmallExtractor,
+	DebugConsumer,
+	Conveyor,
+	Junction,
+	Router,
+	ChannelConsumer,
+	Smelter,
+	Core,
+}
+
+impl EBuilding {
+	pub fn kind(&self) -> BuildingKind {
+		match self {
+			Self::Nothing(_) => BuildingKind::Nothing,
+			Self::SmallExtractor(_) => BuildingKind::SmallExtractor,
+			Self::DebugConsumer(_) => BuildingKind::DebugConsumer,
+			Self::Conveyor(_) => BuildingKind::Conveyor,
+			Self::Junction(_) => BuildingKind::Junction,
+			Self::Router(_) => BuildingKind::Router,
+			Self::ChannelConsumer(_) => BuildingKind::ChannelConsumer,
+			Self::Smelter(_) => BuildingKind::Smelter,
+			Self::Core(_) => BuildingKind::Core,
+		}
+	}
+}
+impl BuildingKind {
+	/// the grid cells the building takes up
+	pub fn size(self) -> (i32, i32) {
+		match self {
+			Self::SmallExtractor => (2, 2),
+			_ => (1, 1),
+		}
+	}
+
+	/// a new building of this kind, facing `dir` if it cares about that \
+	/// `None` for buildings that can't be made on their own, like channel consumers
+	pub fn building(self, dir: Direction) -> Option<EBuilding> {
+		let building = match self {
+			Self::Nothing => EBuilding::nothing(),
+			Self::SmallExtractor => EBuilding::small_extractor(),
+			Self::DebugConsumer => EBuilding::debug_consumer(),
+			Self::Conveyor => EBuilding::conveyor(dir),
+			Self::Junction => EBuilding::junction(),
+			Self::Router => EBuilding::router(),
+			Self::ChannelConsumer => return None,
+			Self::Smelter => EBuilding::smelter(),
+			Self::Core => EBuilding::core(),
+		};
+		Some(building)
+	}
+}
+
+/// a building put down by a level file before the player gets to do anything
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct PlacedBuilding {
+	/// the top left corner for buildings bigger than a single cell
+	pub pos: (i32, i32),
+	pub building: BuildingKind,
+	#[serde(default)]
+	pub dir: Option<Direction>,
+	/// only extractors, cores and sinks can be protected
+	#[serde(default)]
+	pub protected: bool,
+}
+impl PlacedBuilding {
+	/// the building this puts down. sinks come out unconnected, [BuildingsMap::connect_sinks]
+	/// hooks them up
+	pub fn building(&self) -> anyhow::Result<EBuilding> {
+		let mut building = match self.building {
+			BuildingKind::ChannelConsumer => {
+				EBuilding::ChannelConsumer(ChannelConsumer::unconnected())
+			}
+			kind => kind
+				.building(self.dir.unwrap_or_default())
+				.with_context(|| format!("{:?} can't be placed by a level", self.building))?,
+		};
+		if self.protected {
+			building
+				.set_protected(true)
+				.map_err(|_| mklogger::anyhow!("{} can't be protected", building.name()))?;
+		}
+		Ok(building)
+	}
+
+	/// every grid cell the building takes up
+	pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> {
+		let (x, y) = self.pos;
+		let (w, h) = self.building.size();
+		(0..w).flat_map(move |rx| (0..h).map(move |ry| (x + rx, y + ry)))
+	}
+
+	pub fn place(&self, buildings: &mut BuildingsMap) -> anyhow::Result<()> {
+		let building = self.building()?;
+
+		let (w, h) = self.building.size();
+		let entry = if (w, h) == (1, 1) {
+			OrIndexed::Item(building)
+		} else {
+			let index = buildings.insert_indexed(building);
+			OrIndexed::Indexed {
+				index,
+				root: self.pos,
+			}
+		};
+
+		for pos in self.cells() {
+			buildings.try_place(pos, entry.clone()).map_err(|_| {
+				mklogger::anyhow!(
+					"couldn't place {:?} at {}, {}: out of bounds or protected",
+					self.building,
+					pos.0,
+					pos.1
+				)
+			})?;
+		}
+		Ok(())
+	}
+}
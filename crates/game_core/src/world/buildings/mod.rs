@@ -1,14 +1,22 @@
 // pub mod small_extractor;
 // pub use small_extractor::*;
 
-use std::{borrow::Cow, fmt::Debug};
+use std::borrow::Cow;
+#[cfg(feature = "render")]
+use std::fmt::Debug;
 
+#[cfg(feature = "render")]
 use sui::{Layable, LayableExt};
 
-use textures::{TextureID, Textures};
+use textures::TextureID;
+#[cfg(feature = "render")]
+use textures::Textures;
+use tokio::sync::mpsc;
 use utils::Direction;
 
-use crate::{EResource, render::TILE_RENDER_SIZE};
+use crate::EResource;
+#[cfg(feature = "render")]
+use crate::render::TILE_RENDER_SIZE;
 
 mod conveyor;
 pub use conveyor::*;
@@ -21,9 +29,14 @@ pub use small_extractor::*;
 mod debug_consumer;
 pub use debug_consumer::*;
 pub mod channel_consumer;
-pub use channel_consumer::ChannelConsumer;
+pub(crate) use channel_consumer::LegacyChannelConsumer;
+pub use channel_consumer::{ChannelConsumer, Delivery};
 mod smelter;
 pub use smelter::*;
+mod core;
+pub use core::*;
+mod kind;
+pub use kind::*;
 
 pub use super::maps::BuildingsMap;
 
@@ -33,6 +46,7 @@ pub trait Building {
 	fn texture_id(&self) -> TextureID;
 
 	/// returns the render component
+	#[cfg(feature = "render")]
 	fn render<'a>(&'a self, textures: &'a Textures) -> impl Layable + Clone + Debug + 'a {
 		#[derive(Clone, Debug)]
 		struct DefaultBuildingRender<'a> {
@@ -57,6 +71,7 @@ pub trait Building {
 
 	/// used to render an image of the building statically \
 	/// the returned layable can't depend on any lifetime
+	#[cfg(feature = "render")]
 	fn tool_icon_render(&self, textures: &Textures) -> impl Layable + Clone + Debug + 'static {
 		textures
 			.texture_for(self.texture_id())
@@ -65,6 +80,12 @@ pub trait Building {
 			.fix_wh_square(64)
 	}
 
+	/// called once every simulation tick, before any resources are moved. `tick` is the world's
+	/// tick being simulated \
+	/// buildings that do things over time should count time in ticks using this, so they follow
+	/// the simulation speed
+	fn tick(&mut self, tick: u64) {}
+
 	fn can_receive(&self, from: Option<Direction>) -> bool {
 		false
 	}
@@ -107,6 +128,14 @@ pub trait Building {
 		available_directions.last().into_iter().collect()
 	}
 
+	/// resources this building collected for the player since the last call \
+	/// drained into the inventory by GameData after every tick
+	fn take_deposits(&mut self) -> Vec<EResource> {
+		Vec::new()
+	}
+	/// sinks send what they receive into `tx` from now on. other buildings ignore it
+	fn connect_sink(&mut self, tx: &mpsc::Sender<Delivery>) {}
+
 	/// if true, this building can't be removed by the standard eraser tool
 	fn is_protected(&self) -> bool {
 		false
@@ -128,6 +157,7 @@ pub enum EBuilding {
 	ChannelConsumer(ChannelConsumer),
 
 	Smelter(Smelter),
+	Core(Core),
 }
 impl EBuilding {
 	pub const fn nothing() -> Self {
@@ -151,12 +181,85 @@ impl EBuilding {
 	pub fn smelter() -> Self {
 		Self::Smelter(Smelter::default())
 	}
+	pub fn core() -> Self {
+		Self::Core(Core::default())
+	}
+
+	/// the character standing for the building in [crate::maps::ascii] grids
+	pub fn symbol(&self) -> char {
+		match self {
+			Self::Nothing(_) => '.',
+			Self::SmallExtractor(_) => 'E',
+			Self::DebugConsumer(_) => 'D',
+			Self::Conveyor(conveyor) => match conveyor.dir {
+				Direction::Top => '^',
+				Direction::Bottom => 'v',
+				Direction::Left => '<',
+				Direction::Right => '>',
+			},
+			Self::Junction(_) => 'J',
+			Self::Router(_) => 'R',
+			Self::ChannelConsumer(_) => 'O',
+			Self::Smelter(_) => 'S',
+			Self::Core(_) => 'C',
+		}
+	}
+	/// sinks come out unconnected, see [ChannelConsumer::unconnected]
+	pub fn from_symbol(symbol: char) -> Option<Self> {
+		let building = match symbol {
+			'.' => Self::nothing(),
+			'E' => Self::small_extractor(),
+			'D' => Self::debug_consumer(),
+			'^' => Self::conveyor(Direction::Top),
+			'v' => Self::conveyor(Direction::Bottom),
+			'<' => Self::conveyor(Direction::Left),
+			'>' => Self::conveyor(Direction::Right),
+			'J' => Self::junction(),
+			'R' => Self::router(),
+			'O' => Self::ChannelConsumer(ChannelConsumer::unconnected()),
+			'S' => Self::smelter(),
+			'C' => Self::core(),
+			_ => return None,
+		};
+		Some(building)
+	}
 }
 impl Default for EBuilding {
 	fn default() -> Self {
 		Self::Nothing(Default::default())
 	}
 }
+
+/// an [EBuilding] as bincode saves laid it out. bincode isn't self-describing, so the buildings
+/// that save more state now are read with their old fields and converted
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) enum LegacyEBuilding {
+	Nothing(Nothing),
+	SmallExtractor(LegacySmallExtractor),
+	DebugConsumer(DebugConsumer),
+	Conveyor(Conveyor),
+	Junction(Junction),
+	Router(Router),
+	ChannelConsumer(LegacyChannelConsumer),
+
+	Smelter(LegacySmelter),
+	Core(Core),
+}
+impl From<LegacyEBuilding> for EBuilding {
+	fn from(legacy: LegacyEBuilding) -> Self {
+		match legacy {
+			LegacyEBuilding::Nothing(a) => Self::Nothing(a),
+			LegacyEBuilding::SmallExtractor(a) => Self::SmallExtractor(a.into()),
+			LegacyEBuilding::DebugConsumer(a) => Self::DebugConsumer(a),
+			LegacyEBuilding::Conveyor(a) => Self::Conveyor(a),
+			LegacyEBuilding::Junction(a) => Self::Junction(a),
+			LegacyEBuilding::Router(a) => Self::Router(a),
+			LegacyEBuilding::ChannelConsumer(a) => Self::ChannelConsumer(a.into()),
+			LegacyEBuilding::Smelter(a) => Self::Smelter(a.into()),
+			LegacyEBuilding::Core(a) => Self::Core(a),
+		}
+	}
+}
 impl Building for EBuilding {
 	fn name(&self) -> Cow<'static, str> {
 		match self {
@@ -168,6 +271,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.name(),
 			Self::Router(a) => a.name(),
 			Self::Smelter(a) => a.name(),
+			Self::Core(a) => a.name(),
 		}
 	}
 	fn texture_id(&self) -> TextureID {
@@ -180,9 +284,11 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.texture_id(),
 			Self::Router(a) => a.texture_id(),
 			Self::Smelter(a) => a.texture_id(),
+			Self::Core(a) => a.texture_id(),
 		}
 	}
 
+	#[cfg(feature = "render")]
 	fn render<'a>(&'a self, textures: &'a Textures) -> impl Layable + Clone + Debug + 'a {
 		match self {
 			Self::Nothing(a) => sui::custom(a.render(textures)),
@@ -193,8 +299,10 @@ impl Building for EBuilding {
 			Self::Junction(a) => sui::custom(a.render(textures)),
 			Self::Router(a) => sui::custom(a.render(textures)),
 			Self::Smelter(a) => sui::custom(a.render(textures)),
+			Self::Core(a) => sui::custom(a.render(textures)),
 		}
 	}
+	#[cfg(feature = "render")]
 	fn tool_icon_render(&self, textures: &Textures) -> impl Layable + Clone + Debug + 'static {
 		match self {
 			Self::Nothing(a) => sui::custom(a.tool_icon_render(textures)),
@@ -205,6 +313,21 @@ impl Building for EBuilding {
 			Self::Junction(a) => sui::custom(a.tool_icon_render(textures)),
 			Self::Router(a) => sui::custom(a.tool_icon_render(textures)),
 			Self::Smelter(a) => sui::custom(a.tool_icon_render(textures)),
+			Self::Core(a) => sui::custom(a.tool_icon_render(textures)),
+		}
+	}
+
+	fn tick(&mut self, tick: u64) {
+		match self {
+			Self::Nothing(a) => a.tick(tick),
+			Self::SmallExtractor(a) => a.tick(tick),
+			Self::DebugConsumer(a) => a.tick(tick),
+			Self::ChannelConsumer(a) => a.tick(tick),
+			Self::Conveyor(a) => a.tick(tick),
+			Self::Junction(a) => a.tick(tick),
+			Self::Router(a) => a.tick(tick),
+			Self::Smelter(a) => a.tick(tick),
+			Self::Core(a) => a.tick(tick),
 		}
 	}
 
@@ -218,6 +341,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.can_receive(from),
 			Self::Router(a) => a.can_receive(from),
 			Self::Smelter(a) => a.can_receive(from),
+			Self::Core(a) => a.can_receive(from),
 		}
 	}
 	fn capacity_for(&self, resource: &EResource, from: Option<Direction>) -> i32 {
@@ -230,6 +354,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.capacity_for(resource, from),
 			Self::Router(a) => a.capacity_for(resource, from),
 			Self::Smelter(a) => a.capacity_for(resource, from),
+			Self::Core(a) => a.capacity_for(resource, from),
 		}
 	}
 	fn receive(&mut self, resource: EResource, from: Option<Direction>) {
@@ -242,6 +367,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.receive(resource, from),
 			Self::Router(a) => a.receive(resource, from),
 			Self::Smelter(a) => a.receive(resource, from),
+			Self::Core(a) => a.receive(resource, from),
 		}
 	}
 
@@ -255,6 +381,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.needs_poll(),
 			Self::Router(a) => a.needs_poll(),
 			Self::Smelter(a) => a.needs_poll(),
+			Self::Core(a) => a.needs_poll(),
 		}
 	}
 	fn resource_sample(
@@ -271,6 +398,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.resource_sample(tile_resource, to),
 			Self::Router(a) => a.resource_sample(tile_resource, to),
 			Self::Smelter(a) => a.resource_sample(tile_resource, to),
+			Self::Core(a) => a.resource_sample(tile_resource, to),
 		}
 	}
 	fn poll_resource(
@@ -287,6 +415,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.poll_resource(tile_resource, to),
 			Self::Router(a) => a.poll_resource(tile_resource, to),
 			Self::Smelter(a) => a.poll_resource(tile_resource, to),
+			Self::Core(a) => a.poll_resource(tile_resource, to),
 		}
 	}
 
@@ -300,6 +429,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.pass_directions(),
 			Self::Router(a) => a.pass_directions(),
 			Self::Smelter(a) => a.pass_directions(),
+			Self::Core(a) => a.pass_directions(),
 		}
 	}
 	fn confirm_pass_directions(
@@ -315,6 +445,34 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.confirm_pass_directions(available_directions),
 			Self::Router(a) => a.confirm_pass_directions(available_directions),
 			Self::Smelter(a) => a.confirm_pass_directions(available_directions),
+			Self::Core(a) => a.confirm_pass_directions(available_directions),
+		}
+	}
+
+	fn take_deposits(&mut self) -> Vec<EResource> {
+		match self {
+			Self::Nothing(a) => a.take_deposits(),
+			Self::SmallExtractor(a) => a.take_deposits(),
+			Self::DebugConsumer(a) => a.take_deposits(),
+			Self::ChannelConsumer(a) => a.take_deposits(),
+			Self::Conveyor(a) => a.take_deposits(),
+			Self::Junction(a) => a.take_deposits(),
+			Self::Router(a) => a.take_deposits(),
+			Self::Smelter(a) => a.take_deposits(),
+			Self::Core(a) => a.take_deposits(),
+		}
+	}
+	fn connect_sink(&mut self, tx: &mpsc::Sender<Delivery>) {
+		match self {
+			Self::Nothing(a) => a.connect_sink(tx),
+			Self::SmallExtractor(a) => a.connect_sink(tx),
+			Self::DebugConsumer(a) => a.connect_sink(tx),
+			Self::ChannelConsumer(a) => a.connect_sink(tx),
+			Self::Conveyor(a) => a.connect_sink(tx),
+			Self::Junction(a) => a.connect_sink(tx),
+			Self::Router(a) => a.connect_sink(tx),
+			Self::Smelter(a) => a.connect_sink(tx),
+			Self::Core(a) => a.connect_sink(tx),
 		}
 	}
 
@@ -328,6 +486,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.is_protected(),
 			Self::Router(a) => a.is_protected(),
 			Self::Smelter(a) => a.is_protected(),
+			Self::Core(a) => a.is_protected(),
 		}
 	}
 	fn set_protected(&mut self, protected: bool) -> Result<(), ()> {
@@ -340,6 +499,7 @@ impl Building for EBuilding {
 			Self::Junction(a) => a.set_protected(protected),
 			Self::Router(a) => a.set_protected(protected),
 			Self::Smelter(a) => a.set_protected(protected),
+			Self::Core(a) => a.set_protected(protected),
 		}
 	}
 }
@@ -354,6 +514,7 @@ impl Building for Nothing {
 	fn texture_id(&self) -> TextureID {
 		TextureID::Transparent
 	}
+	#[cfg(feature = "render")]
 	fn render<'a>(&'a self, _textures: &'a Textures) -> impl Layable + Clone + Debug + 'a {
 		sui::comp::Space::new(0, 0)
 	}
@@ -365,6 +526,7 @@ impl Building for Nothing {
 		false
 	}
 
+	#[cfg(feature = "render")]
 	fn tool_icon_render(&self, textures: &Textures) -> impl Layable + Clone + Debug + 'static {
 		textures.texture_for(TextureID::Eraser).cloned()
 	}
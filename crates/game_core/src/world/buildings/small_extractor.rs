@@ -1,24 +1,42 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::{EResource, buildings::Building};
+use crate::{EResource, buildings::Building, ticks_in};
 use textures::TextureID;
 use utils::Direction;
 
+pub const EXTRACT_TICKS: u32 = ticks_in(Duration::from_millis(750));
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SmallExtractor {
-	#[serde(skip, default = "Instant::now")]
-	last_extract: Instant,
+	/// ticks since the last extraction
+	#[serde(default)]
+	since_extract: u32,
 
 	protected: bool,
 }
 impl SmallExtractor {
 	pub fn new() -> Self {
 		Self {
-			last_extract: Instant::now(),
+			since_extract: 0,
 			protected: false,
 		}
 	}
 }
+
+/// a [SmallExtractor] as bincode saves laid it out, from before the time since the last extraction
+/// was saved
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct LegacySmallExtractor {
+	protected: bool,
+}
+impl From<LegacySmallExtractor> for SmallExtractor {
+	fn from(legacy: LegacySmallExtractor) -> Self {
+		Self {
+			since_extract: 0,
+			protected: legacy.protected,
+		}
+	}
+}
 impl Building for SmallExtractor {
 	fn name(&self) -> std::borrow::Cow<'static, str> {
 		"small extractor".into()
@@ -27,8 +45,12 @@ impl Building for SmallExtractor {
 		TextureID::SmallExtractor
 	}
 
+	fn tick(&mut self, _tick: u64) {
+		self.since_extract = self.since_extract.saturating_add(1);
+	}
+
 	fn needs_poll(&self) -> bool {
-		self.last_extract.elapsed() > Duration::from_millis(750)
+		self.since_extract > EXTRACT_TICKS
 	}
 	fn resource_sample(
 		&self,
@@ -43,7 +65,7 @@ impl Building for SmallExtractor {
 		_to: Option<Direction>,
 	) -> Option<EResource> {
 		if self.needs_poll() {
-			self.last_extract = Instant::now();
+			self.since_extract = 0;
 			self.resource_sample(tile_resource, _to)
 		} else {
 			None
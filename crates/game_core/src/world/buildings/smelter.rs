@@ -1,6 +1,6 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::{EResource, buildings::Building};
+use crate::{EResource, buildings::Building, ticks_in};
 use textures::TextureID;
 use utils::Direction;
 
@@ -35,9 +35,25 @@ pub struct Smelter {
 
 	#[serde(default)]
 	resource_queue: heapless::Deque<EResource, 2>,
-	#[serde(skip, default)]
+	#[serde(default)]
 	smelting: Option<SmeltData>,
 }
+
+/// a [Smelter] as bincode saves laid it out, from before what's being smelted was saved
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct LegacySmelter {
+	fuel: Duration,
+	resource_queue: heapless::Deque<EResource, 2>,
+}
+impl From<LegacySmelter> for Smelter {
+	fn from(legacy: LegacySmelter) -> Self {
+		Self {
+			fuel: legacy.fuel,
+			resource_queue: legacy.resource_queue,
+			smelting: None,
+		}
+	}
+}
 impl Smelter {
 	fn resources_free(&self, resource: &EResource) -> i32 {
 		let duration = match smelt(resource) {
@@ -69,7 +85,7 @@ impl Smelter {
 		}
 
 		let smelt_data = SmeltData {
-			end_time: Instant::now() + smelt_duration,
+			remaining_ticks: ticks_in(smelt_duration),
 			output_resource: out_resource,
 		};
 
@@ -99,12 +115,13 @@ impl Building for Smelter {
 		TextureID::Furnace
 	}
 
+	#[cfg(feature = "render")]
 	fn render<'a>(
 		&'a self,
 		textures: &'a textures::Textures,
 	) -> impl sui::Layable + Clone + std::fmt::Debug + 'a {
 		let actively_smelting = match &self.smelting {
-			Some(smelt_data) => smelt_data.end_time > Instant::now(),
+			Some(smelt_data) => !smelt_data.is_done(),
 			None => false,
 		};
 		let tid = if actively_smelting {
@@ -116,6 +133,12 @@ impl Building for Smelter {
 		textures.texture_for(tid).cloned()
 	}
 
+	fn tick(&mut self, _tick: u64) {
+		if let Some(smelt_data) = &mut self.smelting {
+			smelt_data.remaining_ticks = smelt_data.remaining_ticks.saturating_sub(1);
+		}
+	}
+
 	fn can_receive(&self, _from: Option<Direction>) -> bool {
 		self.smelting.is_none() || self.fuel < MAX_FUEL
 	}
@@ -163,7 +186,7 @@ impl Building for Smelter {
 	) -> Option<EResource> {
 		match &self.smelting {
 			Some(smelt_data) => {
-				if Instant::now() >= smelt_data.end_time {
+				if smelt_data.is_done() {
 					Some(smelt_data.output_resource.clone())
 				} else {
 					None
@@ -174,7 +197,7 @@ impl Building for Smelter {
 	}
 	fn needs_poll(&self) -> bool {
 		match &self.smelting {
-			Some(smelt_data) => Instant::now() >= smelt_data.end_time,
+			Some(smelt_data) => smelt_data.is_done(),
 			None => false,
 		}
 	}
@@ -185,7 +208,7 @@ impl Building for Smelter {
 	) -> Option<EResource> {
 		let res = match &self.smelting {
 			Some(smelt_data) => {
-				if Instant::now() >= smelt_data.end_time {
+				if smelt_data.is_done() {
 					Some(smelt_data.output_resource.clone())
 				} else {
 					None
@@ -198,8 +221,13 @@ impl Building for Smelter {
 	}
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SmeltData {
-	end_time: Instant,
+	remaining_ticks: u32,
 	output_resource: EResource,
 }
+impl SmeltData {
+	fn is_done(&self) -> bool {
+		self.remaining_ticks == 0
+	}
+}
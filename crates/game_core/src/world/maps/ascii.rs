@@ -0,0 +1,208 @@
+// grids written as one character per cell, one line per row. tiles use [ETile::symbol] and
+// buildings [EBuilding::symbol], like
+//
+// EE>R>O
+// EE.v..
+// ...O..
+//
+// bigger buildings are a full block of their symbol, like the 2x2 extractor above. lines are
+// trimmed and blank lines skipped, so grids can be written indented inside raw strings
+
+use anyhow::Context;
+
+use crate::{
+	ETile,
+	buildings::EBuilding,
+	maps::{BuildingsMap, Map, OrIndexed, Tilemap},
+};
+
+fn parse_grid<T>(text: &str, parse: impl Fn(char) -> Option<T>) -> anyhow::Result<Map<T>> {
+	let rows = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+	let mut columns: Vec<Vec<T>> = Vec::new();
+	for (y, row) in rows.enumerate() {
+		let row = row.chars().enumerate().map(|(x, symbol)| {
+			parse(symbol).with_context(|| format!("unknown symbol {symbol:?} at {x}, {y}"))
+		});
+		let row = row.collect::<anyhow::Result<Vec<_>>>()?;
+
+		if y == 0 {
+			columns = row.iter().map(|_| Vec::new()).collect();
+		}
+		if row.len() != columns.len() {
+			return Err(mklogger::anyhow!(
+				"row {y} is {} wide instead of {}",
+				row.len(),
+				columns.len()
+			));
+		}
+		for (column, cell) in columns.iter_mut().zip(row) {
+			column.push(cell);
+		}
+	}
+	Map::from_vec(columns)
+}
+
+fn print_grid(size: (usize, usize), symbol: impl Fn((i32, i32)) -> char) -> String {
+	let (width, height) = size;
+	let row = |y| {
+		(0..width)
+			.map(|x| symbol((x as i32, y as i32)))
+			.collect::<String>()
+	};
+	(0..height).map(row).collect::<Vec<_>>().join("\n")
+}
+
+pub fn parse_tilemap(text: &str) -> anyhow::Result<Tilemap> {
+	parse_grid(text, ETile::from_symbol).with_context(|| format!("while parsing tilemap"))
+}
+pub fn print_tilemap(tilemap: &Tilemap) -> String {
+	print_grid(tilemap.size(), |pos| {
+		tilemap.at(pos).map(ETile::symbol).unwrap_or(' ')
+	})
+}
+
+/// sinks come out unconnected, [BuildingsMap::connect_sinks] hooks them up to a channel. \
+/// every block of a bigger building becomes a single indexed building, the way
+/// [crate::tool::Tool::Place2x2] puts it down
+pub fn parse_buildings(text: &str) -> anyhow::Result<BuildingsMap> {
+	let grid = parse_grid(text, EBuilding::from_symbol)
+		.with_context(|| format!("while parsing buildings"))?;
+	let mut buildings = BuildingsMap::from_grid(grid);
+	join_blocks(&mut buildings).with_context(|| format!("while parsing buildings"))?;
+	Ok(buildings)
+}
+
+/// goes through the grid row by row, so the top left of each block is found first and the rest of
+/// the block is taken with it. fails on blocks that are cut short
+fn join_blocks(buildings: &mut BuildingsMap) -> anyhow::Result<()> {
+	let (width, height) = buildings.size();
+	for y in 0..height as i32 {
+		for x in 0..width as i32 {
+			let Some(OrIndexed::Item(building)) = buildings.grid_at((x, y)) else {
+				continue;
+			};
+			let kind = building.kind();
+			let (w, h) = kind.size();
+			if (w, h) == (1, 1) {
+				continue;
+			}
+			let building = building.clone();
+
+			let cells = (0..h).flat_map(|ry| (0..w).map(move |rx| (x + rx, y + ry)));
+			let cells = cells.collect::<Vec<_>>();
+			for &(cx, cy) in &cells {
+				let part = match buildings.grid_at((cx, cy)) {
+					Some(OrIndexed::Item(part)) => Some(part.kind()),
+					_ => None,
+				};
+				if part != Some(kind) {
+					return Err(mklogger::anyhow!(
+						"{:?} at {x}, {y} isn't a full {w}x{h} block, {cx}, {cy} is missing",
+						building.symbol()
+					));
+				}
+			}
+
+			let index = buildings.insert_indexed(building);
+			for pos in cells {
+				if let Some(cell) = buildings.grid_at_mut(pos) {
+					*cell = OrIndexed::Indexed {
+						index,
+						root: (x, y),
+					};
+				}
+			}
+		}
+	}
+	Ok(())
+}
+/// parts of bigger buildings show the symbol of the building they're a part of
+pub fn print_buildings(buildings: &BuildingsMap) -> String {
+	print_grid(buildings.size(), |pos| {
+		buildings.at(pos).map(EBuilding::symbol).unwrap_or(' ')
+	})
+}
+
+/// buildings over the tiles they're on, for looking at a whole world in the terminal
+pub fn print_world(tilemap: &Tilemap, buildings: &BuildingsMap) -> String {
+	print_grid(tilemap.size(), |pos| match buildings.at(pos) {
+		Some(EBuilding::Nothing(_)) | None => tilemap.at(pos).map(ETile::symbol).unwrap_or(' '),
+		Some(building) => building.symbol(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{GameData, tool::Tool};
+
+	#[test]
+	fn round_trip() {
+		let tiles = "i...\n.cc.";
+		assert_eq!(print_tilemap(&parse_tilemap(tiles).unwrap()), tiles);
+
+		let buildings = "EE>v.\nEE<RJ\nSCOD.";
+		assert_eq!(
+			print_buildings(&parse_buildings(buildings).unwrap()),
+			buildings
+		);
+	}
+
+	#[test]
+	fn round_trips_placed_extractors() {
+		let tilemap = parse_tilemap("....\n....\n....").unwrap();
+		let mut game = GameData::new(tilemap, BuildingsMap::new_default(4, 3));
+		let extractor = Tool::Place2x2(EBuilding::small_extractor());
+		extractor.r#use(&mut game, (0, 0)).unwrap();
+		extractor.r#use(&mut game, (2, 1)).unwrap();
+
+		let printed = print_buildings(&game.buildings);
+		assert_eq!(printed, "EE..\nEEEE\n..EE");
+
+		let parsed = parse_buildings(&printed).unwrap();
+		assert_eq!(print_buildings(&parsed), printed);
+		assert_eq!(parsed.external_buildings().len(), 2);
+		for pos in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+			assert!(matches!(
+				parsed.grid_at(pos),
+				Some(OrIndexed::Indexed { root: (0, 0), .. })
+			));
+		}
+		assert!(matches!(
+			parsed.grid_at((3, 2)),
+			Some(OrIndexed::Indexed { root: (2, 1), .. })
+		));
+	}
+
+	#[test]
+	fn parses_indented_grids() {
+		let tilemap = parse_tilemap(
+			"
+			i..
+			.c.
+			",
+		)
+		.unwrap();
+		assert_eq!(tilemap.size(), (3, 2));
+		assert_eq!(tilemap.at((1, 1)).map(ETile::symbol), Some('c'));
+	}
+
+	#[test]
+	fn rejects_broken_grids() {
+		assert!(parse_tilemap("...\n..").is_err());
+		assert!(parse_tilemap("..x").is_err());
+		assert!(parse_tilemap("").is_err());
+		assert!(parse_buildings("E>i").is_err());
+		assert!(parse_buildings("E>.").is_err());
+		assert!(parse_buildings("EE\nE.").is_err());
+		assert!(parse_buildings("EEE\nEEE").is_err());
+	}
+
+	#[test]
+	fn prints_buildings_over_tiles() {
+		let tilemap = parse_tilemap("ii..\n.c..\n..c.").unwrap();
+		let buildings = parse_buildings("EE>.\nEE..\n....").unwrap();
+		assert_eq!(print_world(&tilemap, &buildings), "EE>.\nEE..\n..c.");
+	}
+}
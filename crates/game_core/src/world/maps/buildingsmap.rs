@@ -1,14 +1,20 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug};
 
+#[cfg(feature = "render")]
 use sui::Layable;
 
 use crate::{
 	EResource, Map,
-	buildings::{Building, EBuilding},
+	buildings::{Building, Delivery, EBuilding},
+};
+#[cfg(feature = "render")]
+use crate::{
 	maps::SIZE,
 	render::{self, TILE_RENDER_SIZE},
 };
+#[cfg(feature = "render")]
 use textures::Textures;
+use tokio::sync::mpsc;
 use utils::{Direction, MultiMap};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
@@ -17,6 +23,8 @@ pub enum OrIndexed<T> {
 	Item(T),
 }
 
+pub type MovesQueue = BTreeMap<(i32, i32), Vec<(i32, i32)>>;
+
 #[derive(Clone, Debug)]
 /// BuildingsMap isn't just a type and an Ext type, but can be taken with [Self::take]
 pub struct BuildingsMap {
@@ -26,8 +34,9 @@ pub struct BuildingsMap {
 	/// useful to share building implementations between different 1x1 grid buildings
 	external_buildings: Vec<EBuilding>,
 
-	/// HashMap<target_position, Vec<source positions>>
-	moves_queue: HashMap<(i32, i32), Vec<(i32, i32)>>,
+	/// BTreeMap<target_position, Vec<source positions>> \
+	/// ordered, so the moves happen in the same order after a save is loaded
+	moves_queue: MovesQueue,
 }
 impl BuildingsMap {
 	pub fn new_default(width: usize, height: usize) -> Self {
@@ -62,14 +71,28 @@ impl BuildingsMap {
 		self.buildings_grid.size()
 	}
 
+	/// advances every building by one tick. `tick` is the world's tick being simulated, passed to
+	/// every [Building::tick]
 	pub fn tick(
 		&mut self,
+		tick: u64,
 		mut tile_resource_at: impl FnMut((i32, i32)) -> Option<EResource>,
 	) -> () {
 		let mut tile_resource_at = |(x, y)| tile_resource_at((x as _, y as _));
 
 		let mut target_poss_buf = Vec::new();
 
+		// let every building advance its timers. indexed buildings are ticked through
+		// external_buildings so they don't get ticked once for every grid entry they cover
+		for entry in self.buildings_grid.iter_mut() {
+			if let OrIndexed::Item(building) = entry {
+				building.tick(tick);
+			}
+		}
+		for building in self.external_buildings.iter_mut() {
+			building.tick(tick);
+		}
+
 		// warning: self.moves_queue gets taken as moves_queue and put back into self.moves_queue at the end of this function
 		let mut moves_queue = std::mem::take(&mut self.moves_queue);
 
@@ -197,12 +220,39 @@ impl BuildingsMap {
 		}
 	}
 
+	/// connects every sink, like [crate::buildings::ChannelConsumer], to `tx`
+	pub fn connect_sinks(&mut self, tx: mpsc::Sender<Delivery>) {
+		for entry in self.buildings_grid.iter_mut() {
+			if let OrIndexed::Item(building) = entry {
+				building.connect_sink(&tx);
+			}
+		}
+		for building in self.external_buildings.iter_mut() {
+			building.connect_sink(&tx);
+		}
+	}
+
+	/// collects what every building deposited for the player since the last call
+	pub fn take_deposits(&mut self) -> Vec<EResource> {
+		let mut deposits = Vec::new();
+		for entry in self.buildings_grid.iter_mut() {
+			if let OrIndexed::Item(building) = entry {
+				deposits.extend(building.take_deposits());
+			}
+		}
+		for building in self.external_buildings.iter_mut() {
+			deposits.extend(building.take_deposits());
+		}
+		deposits
+	}
+
 	pub fn iter<'a>(&'a self) -> impl Iterator<Item = ((i32, i32), &'a EBuilding)> + 'a {
 		self.buildings_grid
 			.iter_coords()
 			.map(|pos| (pos, self.at(pos).unwrap()))
 	}
 
+	#[cfg(feature = "render")]
 	pub fn render<'a, 'b: 'a>(&'a self, textures: &'b Textures) -> BuildingsRenderer<'a, 'b> {
 		BuildingsRenderer::new(self, textures)
 	}
@@ -212,13 +262,23 @@ impl BuildingsMap {
 	pub fn external_buildings(&self) -> &Vec<EBuilding> {
 		&self.external_buildings
 	}
+
+	/// the moves that'll happen on the next tick
+	pub fn moves_queue(&self) -> &MovesQueue {
+		&self.moves_queue
+	}
+	pub fn set_moves_queue(&mut self, moves_queue: MovesQueue) {
+		self.moves_queue = moves_queue;
+	}
 }
 
+#[cfg(feature = "render")]
 #[derive(Clone, Debug)]
 pub struct BuildingsRenderer<'a, 'b> {
 	textures: &'b Textures,
 	buildings: &'a BuildingsMap,
 }
+#[cfg(feature = "render")]
 impl<'a, 'b> BuildingsRenderer<'a, 'b> {
 	pub fn new(buildings: &'a BuildingsMap, textures: &'b Textures) -> Self {
 		Self {
@@ -227,6 +287,7 @@ impl<'a, 'b> BuildingsRenderer<'a, 'b> {
 		}
 	}
 }
+#[cfg(feature = "render")]
 impl<'a, 'b> Layable for BuildingsRenderer<'a, 'b> {
 	fn size(&self) -> (i32, i32) {
 		let size = SIZE as i32 * TILE_RENDER_SIZE as i32;
@@ -304,3 +365,84 @@ impl BuildingsMap {
 		self.try_place_explicit(pos, building, PlaceStrategy::DeleteAll)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Tile, maps::ascii};
+
+	/// runs the buildings on the tiles for `ticks`
+	fn run(tiles: &str, buildings: &str, ticks: usize) -> BuildingsMap {
+		let tilemap = ascii::parse_tilemap(tiles).unwrap();
+		let mut buildings = ascii::parse_buildings(buildings).unwrap();
+		for tick in 1..=ticks as u64 {
+			buildings.tick(tick, |pos| tilemap.at(pos)?.generate_resource());
+		}
+		buildings
+	}
+	fn deposits_at(buildings: &mut BuildingsMap, pos: (i32, i32)) -> usize {
+		buildings.at_mut(pos).unwrap().take_deposits().len()
+	}
+
+	#[test]
+	fn conveyors_carry_into_sinks() {
+		let mut buildings = run("ii....\nii....", "EE>>>O\nEE....", 300);
+		assert!(deposits_at(&mut buildings, (5, 0)) > 0);
+	}
+
+	#[test]
+	fn conveyors_only_take_from_behind() {
+		let mut buildings = run("ii....\nii....", "EE<<<O\nEE....", 300);
+		assert_eq!(deposits_at(&mut buildings, (5, 0)), 0);
+		assert_eq!(buildings.take_deposits().len(), 0);
+	}
+
+	#[test]
+	fn routers_split_between_outputs() {
+		let tiles = "
+			....
+			ii..
+			ii..
+		";
+		let buildings = "
+			...O
+			EE>R
+			EE.O
+		";
+		let mut buildings = run(tiles, buildings, 300);
+		assert!(deposits_at(&mut buildings, (3, 0)) > 0);
+		assert!(deposits_at(&mut buildings, (3, 2)) > 0);
+	}
+
+	#[test]
+	fn junctions_pass_straight_through() {
+		let tiles = "
+			......
+			ii....
+			ii....
+		";
+		let buildings = "
+			...O..
+			EE>J>O
+			EE.O..
+		";
+		let mut buildings = run(tiles, buildings, 300);
+		assert!(deposits_at(&mut buildings, (5, 1)) > 0);
+		assert_eq!(deposits_at(&mut buildings, (3, 0)), 0);
+		assert_eq!(deposits_at(&mut buildings, (3, 2)), 0);
+	}
+
+	#[test]
+	fn connected_sinks_deliver() {
+		let tilemap = ascii::parse_tilemap("ii..\nii..").unwrap();
+		let mut buildings = ascii::parse_buildings("EE>O\nEE..").unwrap();
+		let (tx, mut rx) = mpsc::channel(100);
+		buildings.connect_sinks(tx);
+
+		for tick in 1..=300 {
+			buildings.tick(tick, |pos| tilemap.at(pos)?.generate_resource());
+		}
+		assert!(rx.try_recv().is_ok());
+		assert!(buildings.take_deposits().len() > 0);
+	}
+}
@@ -1,9 +1,10 @@
-use anyhow::anyhow;
+use anyhow::{Context, anyhow};
 
 mod tilemap;
 pub use tilemap::*;
 mod buildingsmap;
 pub use buildingsmap::*;
+pub mod ascii;
 
 #[derive(Clone, Debug, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Map<T> {
@@ -92,6 +93,10 @@ impl<T> Map<T> {
 	pub fn iter_inner(&self) -> impl Iterator<Item = &Vec<T>> {
 		self.map.iter()
 	}
+	/// every cell, mutably, in no particular order
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+		self.map.iter_mut().flatten()
+	}
 
 	pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
 		self.iter_coords().map(|coords| (coords, self.at(coords).expect("Map<T>::iter_coords and Map<T>::iter are wrong! coordinate returned by Map<T>::iter_coords returned a coordinate that self.at() returned None for")))
@@ -107,6 +112,110 @@ impl<T> Map<T> {
 		self.iter_coords_usize().map(|(x, y)| (x as _, y as _))
 	}
 }
+/// which side of a map stays put when it's resized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+pub enum Anchor {
+	#[default]
+	TopLeft,
+	Top,
+	TopRight,
+	Left,
+	Center,
+	Right,
+	BottomLeft,
+	Bottom,
+	BottomRight,
+}
+impl Anchor {
+	/// how far along each axis the anchor is, in halves
+	fn halves(self) -> (i32, i32) {
+		match self {
+			Self::TopLeft => (0, 0),
+			Self::Top => (1, 0),
+			Self::TopRight => (2, 0),
+			Self::Left => (0, 1),
+			Self::Center => (1, 1),
+			Self::Right => (2, 1),
+			Self::BottomLeft => (0, 2),
+			Self::Bottom => (1, 2),
+			Self::BottomRight => (2, 2),
+		}
+	}
+	/// where the top left corner of a map of size `from` ends up in one of size `to`
+	pub fn offset(self, from: (usize, usize), to: (usize, usize)) -> (i32, i32) {
+		let (hx, hy) = self.halves();
+		let dw = to.0 as i32 - from.0 as i32;
+		let dh = to.1 as i32 - from.1 as i32;
+		(dw * hx / 2, dh * hy / 2)
+	}
+}
+
+impl<T: Clone> Map<T> {
+	/// a `width`x`height` map with every cell moved by `offset`. cells that end up outside are
+	/// dropped, the ones nothing ended up in are `fill`
+	pub fn reframe(
+		&self,
+		width: usize,
+		height: usize,
+		offset: (i32, i32),
+		fill: &T,
+	) -> anyhow::Result<Self> {
+		if width == 0 || height == 0 {
+			return Err(mklogger::anyhow!("a map can't be {width}x{height}"));
+		}
+		let map = (0..width as i32).map(|x| {
+			let column = (0..height as i32).map(|y| self.at((x - offset.0, y - offset.1)));
+			column.map(|cell| cell.unwrap_or(fill).clone()).collect()
+		});
+		Ok(Self {
+			width,
+			height,
+			map: map.collect(),
+		})
+	}
+
+	/// grows or shrinks the map, keeping the `anchor` side where it is
+	pub fn resize(
+		&self,
+		width: usize,
+		height: usize,
+		anchor: Anchor,
+		fill: &T,
+	) -> anyhow::Result<Self> {
+		let offset = anchor.offset(self.size(), (width, height));
+		self.reframe(width, height, offset, fill)
+	}
+
+	/// moves every cell by `offset`, filling the cells uncovered with `fill`
+	pub fn shift(&self, offset: (i32, i32), fill: &T) -> Self {
+		self.reframe(self.width, self.height, offset, fill)
+			.expect("the map already has a size that isn't 0")
+	}
+
+	/// the top left corner and the size of the rectangle with corners `a` and `b`, both
+	/// included, clipped to the map. `None` if it's outside of the map
+	pub fn selection(&self, a: (i32, i32), b: (i32, i32)) -> Option<((i32, i32), (usize, usize))> {
+		let x0 = a.0.min(b.0).max(0);
+		let y0 = a.1.min(b.1).max(0);
+		let x1 = a.0.max(b.0).min(self.width as i32 - 1);
+		let y1 = a.1.max(b.1).min(self.height as i32 - 1);
+		if x0 > x1 || y0 > y1 {
+			return None;
+		}
+		Some(((x0, y0), ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize)))
+	}
+	/// just the cells of [Self::selection]
+	pub fn crop(&self, a: (i32, i32), b: (i32, i32)) -> anyhow::Result<Self> {
+		let ((x, y), (width, height)) = self.selection(a, b).with_context(|| {
+			format!(
+				"{}, {} to {}, {} is outside of the {}x{} map",
+				a.0, a.1, b.0, b.1, self.width, self.height
+			)
+		})?;
+		let fill = &self.map[0][0];
+		self.reframe(width, height, (-x, -y), fill)
+	}
+}
 impl<TFrom> Map<TFrom> {
 	/// maps every cell in the grid to something else (returned by f)
 	pub fn map<TTo, F: FnMut(TFrom) -> TTo>(self, mut f: F) -> Map<TTo> {
@@ -122,3 +231,44 @@ impl<TFrom> Map<TFrom> {
 		Map { width, height, map }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn map(rows: &[&str]) -> Map<char> {
+		let width = rows[0].len();
+		let columns =
+			(0..width).map(|x| rows.iter().map(|row| row.as_bytes()[x] as char).collect());
+		Map::from_vec(columns.collect()).unwrap()
+	}
+	fn rows(map: &Map<char>) -> Vec<String> {
+		let row = |y| {
+			(0..map.width() as i32)
+				.map(|x| *map.at((x, y)).unwrap())
+				.collect()
+		};
+		(0..map.height() as i32).map(row).collect()
+	}
+
+	#[test]
+	fn resizes_around_anchors() {
+		let ab = map(&["ab", "cd"]);
+		let resized = |w, h, anchor| rows(&ab.resize(w, h, anchor, &'.').unwrap());
+		assert_eq!(resized(3, 3, Anchor::TopLeft), ["ab.", "cd.", "..."]);
+		assert_eq!(resized(4, 2, Anchor::Center), [".ab.", ".cd."]);
+		assert_eq!(resized(3, 3, Anchor::BottomRight), ["...", ".ab", ".cd"]);
+		assert_eq!(resized(1, 1, Anchor::BottomRight), ["d"]);
+		assert!(ab.resize(0, 2, Anchor::Center, &'.').is_err());
+	}
+
+	#[test]
+	fn crops_and_shifts() {
+		let abc = map(&["abc", "def", "ghi"]);
+		assert_eq!(rows(&abc.crop((2, 2), (1, 0)).unwrap()), ["bc", "ef", "hi"]);
+		assert_eq!(rows(&abc.crop((-5, 1), (0, 9)).unwrap()), ["d", "g"]);
+		assert!(abc.crop((4, 4), (5, 5)).is_err());
+
+		assert_eq!(rows(&abc.shift((1, -1), &'.')), [".de", ".gh", "..."]);
+	}
+}
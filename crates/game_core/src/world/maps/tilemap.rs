@@ -1,6 +1,10 @@
+#[cfg(feature = "render")]
 use sui::Layable;
 
-use crate::{ETile, render::TILE_RENDER_SIZE};
+use crate::ETile;
+#[cfg(feature = "render")]
+use crate::render::TILE_RENDER_SIZE;
+#[cfg(feature = "render")]
 use textures::Textures;
 
 /// world size in tiles
@@ -12,6 +16,7 @@ pub trait TilemapExt {
 	fn stone(width: usize, height: usize) -> Self;
 	fn from_tiles<const SIZE: usize>(tiles: [[ETile; SIZE]; SIZE]) -> Self;
 
+	#[cfg(feature = "render")]
 	fn render<'a, 'b: 'a>(&'a self, textures: &'b Textures) -> TilemapRenderer<'a>;
 }
 
@@ -29,11 +34,13 @@ impl TilemapExt for Tilemap {
 		map
 	}
 
+	#[cfg(feature = "render")]
 	fn render<'a, 'b: 'a>(&'a self, textures: &'b Textures) -> TilemapRenderer<'a> {
 		TilemapRenderer::new(self, textures, self.width, self.height)
 	}
 }
 
+#[cfg(feature = "render")]
 #[derive(Clone, Debug)]
 /// world rendering as a component
 pub struct TilemapRenderer<'a> {
@@ -43,6 +50,7 @@ pub struct TilemapRenderer<'a> {
 	textures: &'a Textures,
 	tilemap: &'a Tilemap,
 }
+#[cfg(feature = "render")]
 impl<'a> TilemapRenderer<'a> {
 	pub fn new(tilemap: &'a Tilemap, textures: &'a Textures, width: usize, height: usize) -> Self {
 		Self {
@@ -53,6 +61,7 @@ impl<'a> TilemapRenderer<'a> {
 		}
 	}
 }
+#[cfg(feature = "render")]
 impl<'a> Layable for TilemapRenderer<'a> {
 	fn size(&self) -> (i32, i32) {
 		let size = SIZE as i32 * TILE_RENDER_SIZE;
@@ -2,8 +2,9 @@ pub mod tile;
 pub use tile::{ETile, Tile, tiles};
 
 pub mod resources;
-pub use resources::{EResource, Resource};
+pub use resources::{EResource, Resource, ResourceKind};
 
+#[cfg(feature = "render")]
 pub mod render;
 
 pub mod buildings;
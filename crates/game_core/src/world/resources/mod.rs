@@ -29,6 +29,15 @@ impl EResource {
 		Self::Iron(Iron)
 	}
 }
+impl EResource {
+	pub fn kind(&self) -> ResourceKind {
+		match self {
+			Self::Coal(_) => ResourceKind::Coal,
+			Self::RawIron(_) => ResourceKind::RawIron,
+			Self::Iron(_) => ResourceKind::Iron,
+		}
+	}
+}
 impl Resource for EResource {
 	fn name(&self) -> Cow<'static, str> {
 		match self {
@@ -45,3 +54,42 @@ impl Resource for EResource {
 		}
 	}
 }
+
+/// data-less counterpart of [EResource], used where resources are counted or written by hand
+/// (inventories, costs, level files)
+#[derive(
+	Copy,
+	Clone,
+	Debug,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	strum::EnumIter,
+	serde::Serialize,
+	serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+	Coal,
+	RawIron,
+	Iron,
+}
+impl ResourceKind {
+	pub fn resource(self) -> EResource {
+		match self {
+			Self::Coal => EResource::coal(),
+			Self::RawIron => EResource::raw_iron(),
+			Self::Iron => EResource::iron(),
+		}
+	}
+	pub fn name(self) -> Cow<'static, str> {
+		self.resource().name()
+	}
+}
+impl From<&EResource> for ResourceKind {
+	fn from(resource: &EResource) -> Self {
+		resource.kind()
+	}
+}
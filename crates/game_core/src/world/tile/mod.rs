@@ -53,6 +53,23 @@ impl ETile {
 	pub fn coal_ore() -> Self {
 		Self::CoalOre(CoalOre)
 	}
+
+	/// the character standing for the tile in text exports and [crate::maps::ascii] grids
+	pub fn symbol(&self) -> char {
+		match self {
+			ETile::Stone(_) => '.',
+			ETile::IronOre(_) => 'i',
+			ETile::CoalOre(_) => 'c',
+		}
+	}
+	pub fn from_symbol(symbol: char) -> Option<Self> {
+		match symbol {
+			'.' => Some(Self::stone()),
+			'i' => Some(Self::iron_ore()),
+			'c' => Some(Self::coal_ore()),
+			_ => None,
+		}
+	}
 }
 impl Tile for ETile {
 	fn name(&self) -> Cow<'static, str> {
@@ -0,0 +1,80 @@
+// game_image <save or level> <output png> [tile size]
+
+use std::{io::Cursor, path::PathBuf};
+
+use anyhow::Context;
+use asset_provider_image::image::RgbaImage;
+use game_core::{
+	GameData, GameDataSave,
+	codec::{self, LevelFile},
+	text,
+};
+
+use crate::DirAssets;
+
+const USAGE: &str = "usage: game_image <save.cgs | level.cglf | .ron> <output.png> [tile size]";
+const DEFAULT_TILE_SIZE: u32 = 16;
+
+#[tokio::main]
+pub async fn start_with_rt() {
+	if let Err(err) = run(std::env::args().skip(1)).await {
+		mklogger::eprintln!("{err:?}");
+		std::process::exit(1);
+	}
+}
+
+pub async fn run(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+	let input = PathBuf::from(args.next().with_context(|| format!("{USAGE}"))?);
+	let output = PathBuf::from(args.next().with_context(|| format!("{USAGE}"))?);
+	let tile_size = match args.next() {
+		Some(size) => size
+			.parse()
+			.with_context(|| format!("tile size {size:?} isn't a number"))?,
+		None => DEFAULT_TILE_SIZE,
+	};
+
+	let bytes = tokio::fs::read(&input)
+		.await
+		.with_context(|| format!("while reading {}", input.display()))?;
+	let assets = DirAssets::repo();
+
+	let extension = input.extension().and_then(|ext| ext.to_str());
+	let image = match extension {
+		Some("cglf") => render_level(&assets, codec::decode_level(&bytes)?, tile_size).await?,
+		// text saves and text levels share the extension
+		Some("ron") => {
+			let text = String::from_utf8(bytes)?;
+			match GameDataSave::from_text(&text) {
+				Ok(save) => crate::render_world(&assets, &save.take()?, tile_size).await,
+				Err(save_err) => {
+					let level = text::level_from_text(&text).with_context(|| {
+						format!("{} isn't a text save either: {save_err:#}", input.display())
+					})?;
+					render_level(&assets, level, tile_size).await?
+				}
+			}
+		}
+		_ => {
+			let save = GameDataSave::load_as_either(&mut Cursor::new(bytes))?;
+			crate::render_world(&assets, &save.take()?, tile_size).await
+		}
+	};
+
+	let png = crate::encode_png(&image)?;
+	tokio::fs::write(&output, png)
+		.await
+		.with_context(|| format!("while writing {}", output.display()))?;
+	mklogger::println!("rendered {} into {}", input.display(), output.display());
+	Ok(())
+}
+
+/// the level as it looks when it starts
+async fn render_level(
+	assets: &DirAssets,
+	level: LevelFile,
+	tile_size: u32,
+) -> anyhow::Result<RgbaImage> {
+	let buildings = level.buildings_map()?;
+	let data = GameData::new(level.tilemap, buildings);
+	Ok(crate::render_world(assets, &data, tile_size).await)
+}
@@ -0,0 +1,235 @@
+// renders worlds into images on the cpu, without raylib or a display. for thumbnails, level cards
+// and image regression tests
+
+use std::{collections::HashMap, io::Cursor, path::PathBuf};
+
+use anyhow::Context;
+use asset_provider::{Asset, Assets};
+use asset_provider_image::{
+	AssetsExt,
+	image::{self, ImageFormat, Rgba, RgbaImage, imageops},
+};
+use game_core::{
+	GameData, Tile,
+	buildings::{Building, BuildingsMap, EBuilding},
+	maps::{OrIndexed, Tilemap},
+};
+use textures::TextureID;
+use utils::Direction;
+
+pub mod cli;
+
+/// what sprites that couldn't be loaded are drawn as, like in the game
+const MISSING_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// reads assets straight from a directory, like the assets folder of the repo
+#[derive(Clone, Debug)]
+pub struct DirAssets {
+	root: PathBuf,
+}
+impl DirAssets {
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self { root: root.into() }
+	}
+	/// the assets folder of this repository
+	pub fn repo() -> Self {
+		Self::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets"))
+	}
+}
+impl Assets for DirAssets {
+	async fn asset(&self, key: &str) -> anyhow::Result<Asset> {
+		let path = self.root.join(key);
+		let bytes = tokio::fs::read(&path)
+			.await
+			.with_context(|| format!("while reading asset {}", path.display()))?;
+		Ok(Asset::new(bytes))
+	}
+}
+
+/// sprites scaled to a single tile size, by texture
+#[derive(Clone, Debug)]
+pub struct Sprites {
+	tile_size: u32,
+	sprites: HashMap<TextureID, RgbaImage>,
+}
+impl Sprites {
+	/// sprites that can't be loaded are logged and drawn as [MISSING_COLOR]
+	pub async fn load<A: Assets + Sync>(
+		assets: &A,
+		ids: impl IntoIterator<Item = TextureID>,
+		tile_size: u32,
+	) -> Self {
+		let mut sprites = HashMap::new();
+		for id in ids {
+			if sprites.contains_key(&id) {
+				continue;
+			}
+			let image = match assets.asset_image(id.resource_path().as_ref()).await {
+				Ok(image) => image.to_rgba8(),
+				Err(err) => {
+					mklogger::eprintln!("failed to load sprite {id:?}: {err:?}");
+					RgbaImage::from_pixel(1, 1, MISSING_COLOR)
+				}
+			};
+			let image =
+				imageops::resize(&image, tile_size, tile_size, imageops::FilterType::Nearest);
+			sprites.insert(id, image);
+		}
+		Self { tile_size, sprites }
+	}
+	/// loads what it takes to draw `data`
+	pub async fn for_world<A: Assets + Sync>(assets: &A, data: &GameData, tile_size: u32) -> Self {
+		let tiles = data.tilemap.iter().map(|(_, tile)| tile.texture_id());
+		let buildings = data
+			.buildings
+			.iter()
+			.map(|(_, building)| building.texture_id());
+		Self::load(
+			assets,
+			tiles.chain(buildings).collect::<Vec<_>>(),
+			tile_size,
+		)
+		.await
+	}
+	pub async fn for_tilemap<A: Assets + Sync>(
+		assets: &A,
+		tilemap: &Tilemap,
+		tile_size: u32,
+	) -> Self {
+		let tiles = tilemap.iter().map(|(_, tile)| tile.texture_id());
+		Self::load(assets, tiles.collect::<Vec<_>>(), tile_size).await
+	}
+
+	fn sprite(&self, id: &TextureID) -> RgbaImage {
+		match self.sprites.get(id) {
+			Some(sprite) => sprite.clone(),
+			None => RgbaImage::from_pixel(self.tile_size, self.tile_size, MISSING_COLOR),
+		}
+	}
+
+	fn corner_of(&self, pos: (i32, i32)) -> (i64, i64) {
+		let size = self.tile_size as i64;
+		(pos.0 as i64 * size, pos.1 as i64 * size)
+	}
+
+	pub fn draw_tilemap(&self, tilemap: &Tilemap) -> RgbaImage {
+		let (w, h) = tilemap.size();
+		let mut image = RgbaImage::new(w as u32 * self.tile_size, h as u32 * self.tile_size);
+		for (pos, tile) in tilemap.iter() {
+			let (x, y) = self.corner_of(pos);
+			imageops::replace(&mut image, &self.sprite(&tile.texture_id()), x, y);
+		}
+		image
+	}
+
+	/// draws the buildings over `image`. bigger buildings are drawn at their root, like in the game
+	pub fn draw_buildings(&self, image: &mut RgbaImage, buildings: &BuildingsMap) {
+		for (pos, building) in buildings.iter() {
+			let scale = match buildings.grid_at(pos) {
+				Some(OrIndexed::Indexed { root, .. }) if *root != pos => continue,
+				Some(OrIndexed::Indexed { .. }) => 2,
+				_ => 1,
+			};
+			let id = building.texture_id();
+			if id == TextureID::Transparent {
+				continue;
+			}
+
+			let mut sprite = rotate(self.sprite(&id), facing(building));
+			if scale != 1 {
+				let size = self.tile_size * scale;
+				sprite = imageops::resize(&sprite, size, size, imageops::FilterType::Nearest);
+			}
+			let (x, y) = self.corner_of(pos);
+			imageops::overlay(image, &sprite, x, y);
+		}
+	}
+
+	pub fn draw_world(&self, data: &GameData) -> RgbaImage {
+		let mut image = self.draw_tilemap(&data.tilemap);
+		self.draw_buildings(&mut image, &data.buildings);
+		image
+	}
+}
+
+/// sprites face up, buildings with a direction are turned to face it
+fn facing(building: &EBuilding) -> Direction {
+	match building {
+		EBuilding::Conveyor(conveyor) => conveyor.dir,
+		_ => Direction::Top,
+	}
+}
+fn rotate(sprite: RgbaImage, dir: Direction) -> RgbaImage {
+	match dir {
+		Direction::Top => sprite,
+		Direction::Right => imageops::rotate90(&sprite),
+		Direction::Bottom => imageops::rotate180(&sprite),
+		Direction::Left => imageops::rotate270(&sprite),
+	}
+}
+
+pub async fn render_world<A: Assets + Sync>(
+	assets: &A,
+	data: &GameData,
+	tile_size: u32,
+) -> RgbaImage {
+	Sprites::for_world(assets, data, tile_size)
+		.await
+		.draw_world(data)
+}
+pub async fn render_tilemap<A: Assets + Sync>(
+	assets: &A,
+	tilemap: &Tilemap,
+	tile_size: u32,
+) -> RgbaImage {
+	Sprites::for_tilemap(assets, tilemap, tile_size)
+		.await
+		.draw_tilemap(tilemap)
+}
+
+pub fn encode_png(image: &RgbaImage) -> anyhow::Result<Vec<u8>> {
+	let mut bytes = Cursor::new(Vec::new());
+	image
+		.write_to(&mut bytes, ImageFormat::Png)
+		.with_context(|| format!("while encoding png"))?;
+	Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+	use game_core::maps::ascii;
+
+	use super::*;
+
+	const TILE: u32 = 8;
+
+	fn sprite(id: TextureID) -> RgbaImage {
+		let image = image::open(DirAssets::repo().root.join(id.resource_path().as_ref()));
+		let image = image.unwrap().to_rgba8();
+		imageops::resize(&image, TILE, TILE, imageops::FilterType::Nearest)
+	}
+	fn tile_of(image: &RgbaImage, (x, y): (u32, u32)) -> RgbaImage {
+		imageops::crop_imm(image, x * TILE, y * TILE, TILE, TILE).to_image()
+	}
+
+	#[tokio::test]
+	async fn renders_worlds() {
+		let tilemap = ascii::parse_tilemap("i..\n.c.").unwrap();
+		let buildings = ascii::parse_buildings(".>.\n...").unwrap();
+		let data = GameData::new(tilemap, buildings);
+
+		let image = render_world(&DirAssets::repo(), &data, TILE).await;
+		assert_eq!(image.dimensions(), (3 * TILE, 2 * TILE));
+
+		assert_eq!(tile_of(&image, (2, 0)), sprite(TextureID::Stone));
+		assert_eq!(tile_of(&image, (1, 1)), sprite(TextureID::CoalOre));
+		let conveyor = rotate(sprite(TextureID::ConveyorTop), Direction::Right);
+		let mut expected = sprite(TextureID::Stone);
+		imageops::overlay(&mut expected, &conveyor, 0, 0);
+		assert_eq!(tile_of(&image, (1, 0)), expected);
+
+		let png = encode_png(&image).unwrap();
+		let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+		assert_eq!(decoded, image);
+	}
+}
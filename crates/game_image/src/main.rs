@@ -0,0 +1,3 @@
+fn main() {
+	game_image::cli::start_with_rt();
+}
@@ -1,49 +1,113 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use arc_swap::ArcSwap;
-use game_core::{GAME_TICK_FREQUENCY, GameData, GameProvider, tool::Tool};
-use tokio::{sync::broadcast, task::JoinHandle};
+use game_core::{GameData, GameProvider, TimeControl, tool::Tool};
+use tokio::{
+	sync::{Notify, broadcast, watch},
+	task::JoinHandle,
+	time::MissedTickBehavior,
+};
+
+/// the GameData shared between the tasks and the game. readers get a snapshot, writers take turns
+/// through [Self::modify], so none of them replaces what another one just wrote
+#[derive(Debug)]
+struct SharedData {
+	data: ArcSwap<GameData>,
+	write_lock: Mutex<()>,
+}
+impl SharedData {
+	fn new(game_data: GameData) -> Self {
+		Self {
+			data: ArcSwap::new(Arc::new(game_data)),
+			write_lock: Mutex::new(()),
+		}
+	}
+	fn load(&self) -> Arc<GameData> {
+		self.data.load_full()
+	}
+	fn modify<R>(&self, f: impl FnOnce(&mut GameData) -> R) -> R {
+		// a writer that panicked left the data as it was, so the lock is still good to use
+		let _guard = self
+			.write_lock
+			.lock()
+			.unwrap_or_else(|err| err.into_inner());
+		let mut game = GameData::clone(&self.data.load());
+		let ret = f(&mut game);
+		self.data.store(Arc::new(game));
+		ret
+	}
+}
 
 #[derive(Debug)]
 /// essentially a GameData on another thread, ticked asynchronously \
 /// uses the tool_use_rx from game to do tool events
 pub struct MultithreadedGame {
 	handle: JoinHandle<()>,
-	data: Arc<ArcSwap<GameData>>,
+	data: Arc<SharedData>,
+
+	control_tx: watch::Sender<TimeControl>,
+	step: Arc<Notify>,
 }
 impl GameProvider for MultithreadedGame {
 	fn data<'a>(&'a self) -> impl std::ops::Deref<Target = GameData> + 'a {
-		self.data.load().clone()
+		self.data.load()
 	}
 	fn standard_tick(&mut self) {}
+	fn step(&mut self) {
+		self.step.notify_one();
+	}
+	fn set_time_control(&mut self, control: TimeControl) {
+		self.control_tx.send_replace(control);
+	}
 
 	fn tool_use(&mut self, _: &game_core::tool::Tool, _: (i32, i32)) {
 		// tool use handled separately with tool_use_rx
 	}
+
+	fn modify<R>(&mut self, f: impl FnOnce(&mut GameData) -> R) -> R {
+		self.data.modify(f)
+	}
 }
 
 impl MultithreadedGame {
 	pub fn new(game_data: GameData, tool_use_rx: broadcast::Receiver<(Tool, (i32, i32))>) -> Self {
-		let data = ArcSwap::new(Arc::new(game_data));
-		let data = Arc::new(data);
+		let data = Arc::new(SharedData::new(game_data));
 
-		let data = data.clone();
+		let (control_tx, control_rx) = watch::channel(TimeControl::default());
+		let step = Arc::new(Notify::new());
 
 		// the main task that drives the game forward
 		let tick_task = {
 			let data = data.clone();
+			let step = step.clone();
+			let mut control_rx = control_rx;
 
-			async move {
-				let mut interval = tokio::time::interval(GAME_TICK_FREQUENCY);
-				loop {
-					interval.tick().await;
+			// TODO: check to see if game actually changed before swapping it out
+			let tick = move || data.modify(GameData::tick);
 
-					let old_game = data.load_full();
-					let mut game = GameData::clone(&old_game);
-					game.tick();
+			async move {
+				let mut control = *control_rx.borrow_and_update();
+				let mut interval = tokio::time::interval(control.speed.tick_duration());
+				interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-					// TODO: check to see if game actually changed before swapping it out
-					data.swap(Arc::new(game));
+				loop {
+					tokio::select! {
+						_ = interval.tick(), if !control.paused => tick(),
+						_ = step.notified() => tick(),
+						changed = control_rx.changed() => {
+							if changed.is_err() {
+								break;
+							}
+							let new_control = *control_rx.borrow_and_update();
+							if new_control.speed != control.speed {
+								interval = tokio::time::interval(new_control.speed.tick_duration());
+								interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+							}
+							// don't tick right away after a resume or a speed change
+							interval.reset();
+							control = new_control;
+						}
+					}
 				}
 			}
 		};
@@ -62,12 +126,10 @@ impl MultithreadedGame {
 						}
 						Err(_) => continue,
 					};
-					let old_game = data.load_full();
-					let mut game = GameData::clone(&old_game);
-					tool.r#use(&mut game, pos);
-
-					// TODO: check to see if game actually changed before swapping it out
-					data.swap(Arc::new(game));
+					// a failed tool use leaves the data as it was
+					if let Err(err) = data.modify(|game| tool.r#use(game, pos)) {
+						mklogger::eprintln!("{err}");
+					}
 				}
 			}
 		};
@@ -80,7 +142,12 @@ impl MultithreadedGame {
 		};
 		let handle = tokio::spawn(master_task);
 
-		Self { handle, data }
+		Self {
+			handle,
+			data,
+			control_tx,
+			step,
+		}
 	}
 }
 impl Drop for MultithreadedGame {
@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 use anyhow::Context;
 use asset_provider::Assets;
 use futures::{StreamExt, stream::FuturesUnordered};
-use game_core::maps::Tilemap;
+use game_core::{codec, maps::Tilemap};
 
 #[derive(Clone, Debug)]
 /// describes a patch of a resource
@@ -14,14 +14,50 @@ pub struct Segment {
 }
 impl Segment {}
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct AssetSegment {
-	min_distance: i32,
+	pub min_distance: i32,
 }
 pub type SegmentsToml = HashMap<String, AssetSegment>;
 
-const SEGMENTS_TOML_KEY: &str = "worldgen/segments/segments.toml";
-const SEGMENT_TILEMAP_KEY: &str = "worldgen/segments/{key}.cglf";
+pub const SEGMENTS_TOML_KEY: &str = "worldgen/segments/segments.toml";
+pub const SEGMENT_TILEMAP_KEY: &str = "worldgen/segments/{key}.cglf";
+
+/// segment names end up in file names and toml keys, so they're kept to `a-z`, `0-9` and `_`
+pub fn check_segment_name(name: &str) -> anyhow::Result<()> {
+	let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_';
+	if name.is_empty() || !name.chars().all(valid) {
+		return Err(mklogger::anyhow!(
+			"segment name {name:?} can only have lowercase letters, digits and underscores"
+		));
+	}
+	Ok(())
+}
+
+/// `toml` with the fields of `name` written into its `[name]` table, or with a new table appended
+/// at the end. the file is edited as text, so comments, other fields and the order of the entries
+/// stay as they were, and an entry that didn't change leaves it as it was
+pub fn set_segment_entry(toml: &str, name: &str, segment: AssetSegment) -> anyhow::Result<String> {
+	check_segment_name(name)?;
+	let segments: SegmentsToml =
+		toml::from_str(toml).with_context(|| format!("while deserializing segments.toml"))?;
+	if segments.get(name) == Some(&segment) {
+		return Ok(toml.to_string());
+	}
+
+	let fields =
+		toml::to_string(&segment).with_context(|| format!("while serializing segment {name:?}"))?;
+	let toml = utils::toml_text::set_table_fields(toml, name, &fields);
+
+	let segments: SegmentsToml = toml::from_str(&toml)
+		.with_context(|| format!("while parsing segments.toml with {name:?}"))?;
+	if segments.get(name) != Some(&segment) {
+		return Err(mklogger::anyhow!(
+			"couldn't write {name:?} into segments.toml as a [{name}] table"
+		));
+	}
+	Ok(toml)
+}
 
 /// loads the file describing the segments
 pub async fn load_segments_toml<A: Assets>(assets: A) -> anyhow::Result<SegmentsToml> {
@@ -43,7 +79,7 @@ pub async fn load_segment_tiles<A: Assets>(assets: A, name: &str) -> anyhow::Res
 		.with_context(|| format!("while loading tilemap for segment {name}"))?;
 	let asset = asset.as_slice();
 
-	let (tilemap, _) = bincode::serde::decode_from_slice(asset, bincode::config::standard())
+	let tilemap = codec::decode_tilemap(asset)
 		.with_context(|| format!("while deserializing tilemap for segment {name}"))?;
 	Ok(tilemap)
 }
@@ -85,3 +121,47 @@ pub async fn load_segments<A: Assets + Clone>(assets: A) -> anyhow::Result<Vec<S
 
 	Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SEGMENTS: &str = "\
+# the first patch the player finds
+[iron_1]
+min_distance = 20
+weight = 3
+
+[coal_1]
+min_distance = 10
+";
+
+	fn segment(min_distance: i32) -> AssetSegment {
+		AssetSegment { min_distance }
+	}
+
+	#[test]
+	fn updates_segment_entries() {
+		let toml = set_segment_entry(SEGMENTS, "iron_1", segment(5)).unwrap();
+		assert_eq!(
+			toml,
+			SEGMENTS.replace("min_distance = 20", "min_distance = 5")
+		);
+
+		let toml = set_segment_entry(&toml, "blob", segment(7)).unwrap();
+		assert!(toml.starts_with(&SEGMENTS.replace("min_distance = 20", "min_distance = 5")));
+		assert!(toml.ends_with("[coal_1]\nmin_distance = 10\n\n[blob]\nmin_distance = 7\n"));
+
+		let segments: SegmentsToml = toml::from_str(&toml).unwrap();
+		assert_eq!(segments.len(), 3);
+		assert_eq!(segments["blob"], segment(7));
+
+		assert!(set_segment_entry("", "Iron 1", segment(5)).is_err());
+	}
+
+	#[test]
+	fn unchanged_entries_leave_the_file_alone() {
+		let toml = set_segment_entry(SEGMENTS, "iron_1", segment(20)).unwrap();
+		assert_eq!(toml, SEGMENTS);
+	}
+}
@@ -0,0 +1,236 @@
+// what the tile tools of the editor paint, as plain functions of positions and tilemaps so they can
+// be tested without a window
+
+use std::collections::{BTreeSet, VecDeque};
+
+use game::world::{
+	ETile,
+	maps::{Tilemap, TilemapExt},
+};
+
+/// mirrors everything painted across the middle of the level
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+pub enum Symmetry {
+	#[default]
+	None,
+	/// left to right, across the vertical middle line
+	X,
+	/// top to bottom, across the horizontal middle line
+	Y,
+	Both,
+}
+impl Symmetry {
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::None => "no mirror",
+			Self::X => "mirror x",
+			Self::Y => "mirror y",
+			Self::Both => "mirror xy",
+		}
+	}
+
+	/// `pos` and its mirror images on a level of `size`, without duplicates
+	pub fn mirror(self, pos: (i32, i32), size: (usize, usize)) -> Vec<(i32, i32)> {
+		let (x, y) = pos;
+		let flip_x = size.0 as i32 - 1 - x;
+		let flip_y = size.1 as i32 - 1 - y;
+
+		let mut mirrored = match self {
+			Self::None => vec![pos],
+			Self::X => vec![pos, (flip_x, y)],
+			Self::Y => vec![pos, (x, flip_y)],
+			Self::Both => vec![pos, (flip_x, y), (x, flip_y), (flip_x, flip_y)],
+		};
+		mirrored.sort();
+		mirrored.dedup();
+		mirrored
+	}
+}
+
+/// every cell within `radius` of `center`. radius 0 is just the center
+pub fn disc(center: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+	let radius = radius.max(0);
+	let mut cells = Vec::new();
+	for dx in -radius..=radius {
+		for dy in -radius..=radius {
+			if dx * dx + dy * dy <= radius * radius {
+				cells.push((center.0 + dx, center.1 + dy));
+			}
+		}
+	}
+	cells
+}
+
+/// the cells on the straight line between two cells, ends included
+pub fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+	let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+	let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+
+	let mut cells = Vec::new();
+	let (mut x, mut y) = from;
+	let mut err = dx + dy;
+	loop {
+		cells.push((x, y));
+		if (x, y) == to {
+			return cells;
+		}
+		let err2 = 2 * err;
+		if err2 >= dy {
+			err += dy;
+			x += sx;
+		}
+		if err2 <= dx {
+			err += dx;
+			y += sy;
+		}
+	}
+}
+
+/// a line of brush discs, for painting along where the mouse went between two frames
+pub fn stroke(from: (i32, i32), to: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+	let cells = line(from, to).into_iter().flat_map(|pos| disc(pos, radius));
+	cells.collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+/// every cell of the rectangle with corners `a` and `b`
+pub fn rect(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+	let (x0, x1) = (a.0.min(b.0), a.0.max(b.0));
+	let (y0, y1) = (a.1.min(b.1), a.1.max(b.1));
+	(x0..=x1)
+		.flat_map(|x| (y0..=y1).map(move |y| (x, y)))
+		.collect()
+}
+/// the border of the rectangle with corners `a` and `b`
+pub fn rect_outline(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+	let (x0, x1) = (a.0.min(b.0), a.0.max(b.0));
+	let (y0, y1) = (a.1.min(b.1), a.1.max(b.1));
+	let cells = rect(a, b).into_iter();
+	cells
+		.filter(|(x, y)| *x == x0 || *x == x1 || *y == y0 || *y == y1)
+		.collect()
+}
+
+/// the cells reachable from `start` going up, down, left and right over the same kind of tile
+pub fn flood_fill(tilemap: &Tilemap, start: (i32, i32)) -> Vec<(i32, i32)> {
+	let Some(symbol) = tilemap.at(start).map(ETile::symbol) else {
+		return Vec::new();
+	};
+
+	let mut seen = BTreeSet::from([start]);
+	let mut queue = VecDeque::from([start]);
+	while let Some((x, y)) = queue.pop_front() {
+		for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+			let same = tilemap.at(next).map(ETile::symbol) == Some(symbol);
+			if same && seen.insert(next) {
+				queue.push_back(next);
+			}
+		}
+	}
+	seen.into_iter().collect()
+}
+
+/// sets every cell and its mirror images to `tile`. cells outside of the level are skipped
+pub fn paint(
+	tilemap: &mut Tilemap,
+	cells: impl IntoIterator<Item = (i32, i32)>,
+	tile: &ETile,
+	symmetry: Symmetry,
+) {
+	let size = tilemap.size();
+	for pos in cells {
+		for pos in symmetry.mirror(pos, size) {
+			if let Some(target) = tilemap.at_mut(pos) {
+				*target = tile.clone();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use game::world::maps::ascii;
+
+	use super::*;
+
+	fn painted(tiles: &str, cells: Vec<(i32, i32)>, symmetry: Symmetry) -> String {
+		let mut tilemap = ascii::parse_tilemap(tiles).unwrap();
+		paint(&mut tilemap, cells, &ETile::iron_ore(), symmetry);
+		ascii::print_tilemap(&tilemap)
+	}
+
+	#[test]
+	fn brushes() {
+		assert_eq!(disc((3, 3), 0), [(3, 3)]);
+		assert_eq!(disc((3, 3), 1).len(), 5);
+		assert_eq!(disc((3, 3), 2).len(), 13);
+
+		let tiles = "....\n....\n....";
+		assert_eq!(
+			painted(tiles, stroke((0, 0), (3, 0), 0), Symmetry::None),
+			"iiii\n....\n...."
+		);
+		assert_eq!(
+			painted(tiles, disc((1, 1), 1), Symmetry::None),
+			".i..\niii.\n.i.."
+		);
+	}
+
+	#[test]
+	fn lines() {
+		assert_eq!(line((0, 0), (3, 3)), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+		assert_eq!(line((2, 1), (0, 1)), [(2, 1), (1, 1), (0, 1)]);
+		assert_eq!(line((1, 1), (1, 1)), [(1, 1)]);
+
+		let steep = line((0, 0), (1, 4));
+		assert_eq!(steep.len(), 5);
+		assert_eq!(steep.last(), Some(&(1, 4)));
+	}
+
+	#[test]
+	fn rectangles() {
+		let tiles = "....\n....\n....";
+		assert_eq!(
+			painted(tiles, rect((2, 1), (0, 0)), Symmetry::None),
+			"iii.\niii.\n...."
+		);
+		assert_eq!(
+			painted(tiles, rect_outline((0, 0), (3, 2)), Symmetry::None),
+			"iiii\ni..i\niiii"
+		);
+	}
+
+	#[test]
+	fn flood_fills_stop_at_other_tiles() {
+		let tilemap = ascii::parse_tilemap(
+			"
+			..c..
+			..c..
+			ccc..
+			",
+		)
+		.unwrap();
+		assert_eq!(flood_fill(&tilemap, (0, 0)).len(), 4);
+		assert_eq!(flood_fill(&tilemap, (4, 2)).len(), 6);
+		assert_eq!(flood_fill(&tilemap, (2, 0)).len(), 5);
+		assert!(flood_fill(&tilemap, (9, 9)).is_empty());
+	}
+
+	#[test]
+	fn mirrors() {
+		let tiles = "....\n....\n....";
+		assert_eq!(
+			painted(tiles, vec![(0, 0)], Symmetry::X),
+			"i..i\n....\n...."
+		);
+		assert_eq!(
+			painted(tiles, vec![(1, 0)], Symmetry::Y),
+			".i..\n....\n.i.."
+		);
+		assert_eq!(
+			painted(tiles, vec![(0, 0)], Symmetry::Both),
+			"i..i\n....\ni..i"
+		);
+		// the middle row is its own mirror image
+		assert_eq!(Symmetry::Y.mirror((0, 1), (4, 3)), [(0, 1)]);
+	}
+}
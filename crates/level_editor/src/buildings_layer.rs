@@ -0,0 +1,168 @@
+// the buildings a level starts with, kept as the placements the level file stores
+
+use game::world::{
+	buildings::PlacedBuilding,
+	maps::{BuildingsMap, Tilemap, TilemapExt},
+};
+
+#[derive(Clone, Debug, Default, Hash)]
+pub struct BuildingsLayer {
+	placed: Vec<PlacedBuilding>,
+}
+impl BuildingsLayer {
+	pub fn new(placed: Vec<PlacedBuilding>) -> Self {
+		Self { placed }
+	}
+	pub fn placed(&self) -> &[PlacedBuilding] {
+		&self.placed
+	}
+
+	/// the placement covering `pos`
+	pub fn at(&self, pos: (i32, i32)) -> Option<&PlacedBuilding> {
+		self.placed
+			.iter()
+			.find(|placed| placed.cells().any(|cell| cell == pos))
+	}
+
+	/// replaces whatever the building would overlap. fails if it doesn't fit on the tilemap \
+	/// returns false if the exact same building was already there
+	pub fn place(&mut self, placed: PlacedBuilding, tilemap: &Tilemap) -> anyhow::Result<bool> {
+		if self.placed.contains(&placed) {
+			return Ok(false);
+		}
+		if let Some((x, y)) = placed.cells().find(|pos| tilemap.at(*pos).is_none()) {
+			return Err(mklogger::anyhow!(
+				"{:?} doesn't fit at {}, {}, {x}, {y} is outside of the level",
+				placed.building,
+				placed.pos.0,
+				placed.pos.1
+			));
+		}
+		placed.building()?;
+
+		let cells = placed.cells().collect::<Vec<_>>();
+		self.placed
+			.retain(|other| !other.cells().any(|cell| cells.contains(&cell)));
+		self.placed.push(placed);
+		Ok(true)
+	}
+
+	/// returns false if there was nothing to erase
+	pub fn erase(&mut self, pos: (i32, i32)) -> bool {
+		let len = self.placed.len();
+		self.placed
+			.retain(|placed| !placed.cells().any(|cell| cell == pos));
+		self.placed.len() != len
+	}
+
+	/// flips the protection of the building at `pos`, returning whether it's protected now \
+	/// buildings that can't be protected are left as they were
+	pub fn toggle_protected(&mut self, pos: (i32, i32)) -> anyhow::Result<Option<bool>> {
+		let Some(placed) = self
+			.placed
+			.iter_mut()
+			.find(|placed| placed.cells().any(|cell| cell == pos))
+		else {
+			return Ok(None);
+		};
+
+		placed.protected = !placed.protected;
+		if let Err(err) = placed.building() {
+			placed.protected = !placed.protected;
+			return Err(err);
+		}
+		Ok(Some(placed.protected))
+	}
+
+	/// moves every building by `offset`, onto a level of `size`, like [Tilemap::reframe] does with
+	/// the tiles. the ones that don't fit anymore are dropped
+	pub fn reframe(&mut self, offset: (i32, i32), size: (usize, usize)) {
+		let fits =
+			|(x, y): (i32, i32)| x >= 0 && y >= 0 && (x as usize) < size.0 && (y as usize) < size.1;
+		for placed in self.placed.iter_mut() {
+			placed.pos = (placed.pos.0 + offset.0, placed.pos.1 + offset.1);
+		}
+		self.placed.retain(|placed| placed.cells().all(fits));
+	}
+
+	/// what the level starts with, for drawing. placements that fail are logged and left out
+	pub fn buildings_map(&self, tilemap: &Tilemap) -> BuildingsMap {
+		let (w, h) = tilemap.size();
+		let mut buildings = BuildingsMap::new_default(w, h);
+		for placed in &self.placed {
+			if let Err(err) = placed.place(&mut buildings) {
+				mklogger::eprintln!("{err:?}");
+			}
+		}
+		buildings
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use game::world::buildings::{Building, BuildingKind};
+	use utils::Direction;
+
+	use super::*;
+
+	fn place(
+		layer: &mut BuildingsLayer,
+		pos: (i32, i32),
+		building: BuildingKind,
+	) -> anyhow::Result<bool> {
+		let placed = PlacedBuilding {
+			pos,
+			building,
+			dir: Some(Direction::Right),
+			protected: false,
+		};
+		layer.place(placed, &Tilemap::stone(6, 6))
+	}
+
+	#[test]
+	fn places_over_overlapping_buildings() {
+		let mut layer = BuildingsLayer::default();
+		assert!(place(&mut layer, (1, 1), BuildingKind::Conveyor).unwrap());
+		assert!(!place(&mut layer, (1, 1), BuildingKind::Conveyor).unwrap());
+		assert!(place(&mut layer, (2, 2), BuildingKind::Router).unwrap());
+
+		// the extractor covers 1, 1 to 2, 2
+		place(&mut layer, (1, 1), BuildingKind::SmallExtractor).unwrap();
+		assert_eq!(layer.placed().len(), 1);
+		let at = layer.at((2, 2)).map(|placed| placed.building);
+		assert_eq!(at, Some(BuildingKind::SmallExtractor));
+
+		assert!(place(&mut layer, (5, 5), BuildingKind::SmallExtractor).is_err());
+		assert!(layer.erase((2, 1)));
+		assert!(!layer.erase((2, 1)));
+		assert!(layer.placed().is_empty());
+	}
+
+	#[test]
+	fn protects_what_can_be_protected() {
+		let mut layer = BuildingsLayer::default();
+		place(&mut layer, (0, 0), BuildingKind::Core).unwrap();
+		place(&mut layer, (3, 3), BuildingKind::Conveyor).unwrap();
+
+		assert_eq!(layer.toggle_protected((0, 0)).unwrap(), Some(true));
+		assert!(layer.toggle_protected((3, 3)).is_err());
+		assert!(!layer.at((3, 3)).unwrap().protected);
+		assert_eq!(layer.toggle_protected((2, 2)).unwrap(), None);
+
+		let buildings = layer.buildings_map(&Tilemap::stone(6, 6));
+		assert!(buildings.at((0, 0)).unwrap().is_protected());
+		assert!(!buildings.at((3, 3)).unwrap().is_protected());
+	}
+
+	#[test]
+	fn moves_and_clips_with_the_tiles() {
+		let mut layer = BuildingsLayer::default();
+		place(&mut layer, (0, 0), BuildingKind::SmallExtractor).unwrap();
+		place(&mut layer, (4, 4), BuildingKind::Router).unwrap();
+
+		// cropping to 1, 1 to 5, 5 cuts the extractor in half
+		layer.reframe((-1, -1), (5, 5));
+		assert_eq!(layer.placed().len(), 1);
+		assert_eq!(layer.placed()[0].pos, (3, 3));
+	}
+}
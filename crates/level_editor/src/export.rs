@@ -0,0 +1,462 @@
+// what a level's level.toml says about it, and exporting the level into the assets folder of the
+// repository as a folder the game loads, added to the campaign
+
+use std::{fmt::Debug, path::Path};
+
+use anyhow::Context;
+use game::{
+	comp::TextInput,
+	game::{Goal, Requirement},
+	levels::{self, CampaignLevel, LEVELS_TOML_KEY, LevelMetadata, Levels},
+	world::{
+		ResourceKind,
+		codec::{self, Compression, LevelFile},
+	},
+};
+use serde::{Deserialize, de::IntoDeserializer};
+use strum::IntoEnumIterator;
+use sui::{Layable, LayableExt, core::ReturnEvent};
+use utils::{persist, toml_text};
+
+use crate::assets_path;
+
+/// the most characters a field can be typed to
+const MAX_FIELD_LEN: usize = 120;
+
+/// the parts of a level's metadata the editor changes. everything else in level.toml is kept as
+/// it was, or left out for new levels
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LevelInfo {
+	pub id: String,
+	pub name: String,
+	pub desc: String,
+	/// ids of the levels that have to be completed first. only used when the level is added to
+	/// the campaign
+	pub requires: Vec<String>,
+	/// what has to be delivered, typed like `coal 20, iron 10`. left empty, the goal of the
+	/// level.toml is kept, so goals that aren't just deliveries are written there by hand
+	pub goal: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::EnumIter)]
+pub enum InfoField {
+	Id,
+	Name,
+	Desc,
+	Requires,
+	Goal,
+}
+impl InfoField {
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Id => "id",
+			Self::Name => "name",
+			Self::Desc => "description",
+			Self::Requires => "requires",
+			Self::Goal => "goal",
+		}
+	}
+}
+
+impl LevelInfo {
+	/// the field as it's typed, requires separated by commas
+	pub fn get(&self, field: InfoField) -> String {
+		match field {
+			InfoField::Id => self.id.clone(),
+			InfoField::Name => self.name.clone(),
+			InfoField::Desc => self.desc.clone(),
+			InfoField::Requires => self.requires.join(", "),
+			InfoField::Goal => self.goal.clone(),
+		}
+	}
+	pub fn set(&mut self, field: InfoField, text: String) {
+		match field {
+			InfoField::Id => self.id = text.trim().to_string(),
+			InfoField::Name => self.name = text,
+			InfoField::Desc => self.desc = text,
+			InfoField::Requires => {
+				let requires = text.split(',').map(str::trim).filter(|req| !req.is_empty());
+				self.requires = requires.map(str::to_string).collect();
+			}
+			InfoField::Goal => self.goal = text.trim().to_string(),
+		}
+	}
+
+	/// the deliveries typed into [Self::goal] as a goal, None if nothing is typed
+	pub fn parse_goal(&self) -> anyhow::Result<Option<Goal>> {
+		let entries = self
+			.goal
+			.split(',')
+			.map(str::trim)
+			.filter(|entry| !entry.is_empty());
+		let deliveries = entries.map(|entry| {
+			let (resource, amount) = entry.split_once(' ').with_context(|| {
+				format!("goal entry {entry:?} should be a resource and an amount, like \"coal 20\"")
+			})?;
+			let resource: Result<ResourceKind, serde::de::value::Error> =
+				ResourceKind::deserialize(resource.into_deserializer());
+			let resource = resource.with_context(|| format!("in goal entry {entry:?}"))?;
+			let amount = amount.trim().parse::<u32>();
+			let amount = amount.with_context(|| format!("in goal entry {entry:?}"))?;
+			anyhow::Ok((resource, amount))
+		});
+		let deliveries = deliveries.collect::<anyhow::Result<Vec<_>>>()?;
+
+		let goal = match deliveries.as_slice() {
+			[] => return Ok(None),
+			[(resource, amount)] => Goal::new(Requirement::Deliver {
+				resource: *resource,
+				amount: *amount,
+			}),
+			_ => Goal::deliver_all(deliveries),
+		};
+		Ok(Some(goal))
+	}
+
+	/// the info of the level in `dir`, from its level.toml and its entry in the campaign
+	pub async fn load(dir: &Path) -> anyhow::Result<Self> {
+		let id = dir.file_name().and_then(|name| name.to_str());
+		let id = id.with_context(|| format!("{} isn't a level folder", dir.display()))?;
+
+		let metadata = read_metadata(&dir.join("level.toml")).await?;
+		let levels = read_levels_toml().await?;
+		let levels: Levels =
+			toml::from_str(&levels).with_context(|| format!("while parsing levels/levels.toml"))?;
+		let campaign_level = levels.campaign.level(id);
+
+		Ok(Self {
+			id: id.to_string(),
+			name: metadata.level.name,
+			desc: metadata.level.desc,
+			requires: campaign_level
+				.map(|level| level.requires.clone())
+				.unwrap_or_default(),
+			goal: metadata
+				.goal
+				.as_ref()
+				.and_then(goal_text)
+				.unwrap_or_default(),
+		})
+	}
+}
+
+/// `goal` the way it's typed into [LevelInfo::goal], None if it isn't just deliveries
+fn goal_text(goal: &Goal) -> Option<String> {
+	let deliveries = match &goal.requirement {
+		Requirement::AllOf(reqs) => reqs.iter().collect(),
+		req => vec![req],
+	};
+	let entries = deliveries.into_iter().map(|req| match req {
+		Requirement::Deliver { resource, amount } => {
+			let resource = toml::Value::try_from(resource).ok()?;
+			Some(format!("{} {amount}", resource.as_str()?))
+		}
+		_ => None,
+	});
+	let entries = entries.collect::<Option<Vec<_>>>()?;
+	Some(entries.join(", "))
+}
+
+/// requests typing into a field of the [LevelInfo]
+#[derive(Clone, Debug)]
+pub struct EditInfo(pub InfoField);
+/// requests setting a field of the [LevelInfo] to what was typed
+#[derive(Clone, Debug)]
+pub struct SetInfo(pub InfoField, pub String);
+/// requests exporting the level with the [LevelInfo]
+#[derive(Clone, Debug)]
+pub struct ExportLevel;
+/// requests closing the panel
+#[derive(Clone, Debug)]
+pub struct CloseInfo;
+
+/// the fields of `info`, with `editing` typed into. listen to [EditInfo], [SetInfo],
+/// [ExportLevel] and [CloseInfo] in your component to have it working
+pub fn info_panel(info: &LevelInfo, editing: Option<InfoField>) -> impl Layable + Debug {
+	let rows = InfoField::iter().map(|field| {
+		let label = sui::Text::new(format!("{}: ", field.name()), 24).margin(4);
+		let value = match editing == Some(field) {
+			true => sui::custom_only_debug(TextInput::new(
+				info.get(field),
+				MAX_FIELD_LEN,
+				move |text| ReturnEvent::new(SetInfo(field, text)),
+			)),
+			false => {
+				// empty text can't be clicked
+				let text = match info.get(field) {
+					text if text.is_empty() => "...".to_string(),
+					text => text,
+				};
+				let text = sui::Text::new(text, 24).margin(4);
+				sui::custom_only_debug(text.clickable(move |_| EditInfo(field)))
+			}
+		};
+		sui::div_h([sui::custom_only_debug(label), value])
+	});
+	let rows = rows.collect::<Vec<_>>();
+
+	let export = comp_extra::button_explicit("export", false, || ReturnEvent::new(ExportLevel));
+	let close = comp_extra::button_explicit("close", false, || ReturnEvent::new(CloseInfo));
+	let buttons = sui::div_h([
+		sui::custom_only_debug(export),
+		sui::custom_only_debug(close),
+	]);
+
+	let title = sui::Text::new("level info", 32).margin(4);
+	let hint =
+		"click a field to type into it, enter sets it. goals are typed like coal 20, iron 10";
+	let hint = sui::Text::new(hint, 16).margin(4);
+	let panel = sui::div([
+		sui::custom_only_debug(title),
+		sui::custom_only_debug(sui::div(rows)),
+		sui::custom_only_debug(hint),
+		sui::custom_only_debug(buttons.restrict_to_size()),
+	]);
+	let panel = panel.margin(8);
+	panel.with_background(sui::comp::Color::new(sui::color(0, 0, 0, 200)))
+}
+
+async fn read_metadata(path: &Path) -> anyhow::Result<LevelMetadata> {
+	let metadata = tokio::fs::read_to_string(path)
+		.await
+		.with_context(|| format!("while reading {}", path.display()))?;
+	let metadata: LevelMetadata =
+		toml::from_str(&metadata).with_context(|| format!("while parsing {}", path.display()))?;
+	metadata
+		.check_version()
+		.with_context(|| format!("while reading {}", path.display()))?;
+	Ok(metadata)
+}
+async fn read_levels_toml() -> anyhow::Result<String> {
+	let path = assets_path(LEVELS_TOML_KEY);
+	tokio::fs::read_to_string(&path)
+		.await
+		.with_context(|| format!("while reading {}", path.display()))
+}
+
+/// the level.toml of the level with `info`, from the `old` one if there is one. None if the old
+/// one doesn't have to change \
+/// only the name, description and goal are changed, and they're edited as text, so comments and
+/// the order of everything stay. a typed goal replaces the requirement of the old goal and keeps
+/// its deadline
+fn level_toml(old: Option<&str>, info: &LevelInfo) -> anyhow::Result<Option<String>> {
+	let goal = info.parse_goal()?;
+	let old_metadata = match old {
+		Some(old) => {
+			let metadata: LevelMetadata =
+				toml::from_str(old).with_context(|| format!("while parsing level.toml"))?;
+			metadata.check_version()?;
+			Some(metadata)
+		}
+		None => None,
+	};
+
+	let mut metadata = old_metadata
+		.clone()
+		.unwrap_or_else(|| LevelMetadata::new("", ""));
+	metadata.level.name = info.name.clone();
+	metadata.level.desc = info.desc.clone();
+	if let Some(mut goal) = goal {
+		// the deadline isn't typed into the panel, the one of the level.toml is kept
+		goal.deadline_secs = metadata.goal.as_ref().and_then(|old| old.deadline_secs);
+		metadata.goal = Some(goal);
+	}
+	let goal = metadata.goal().with_context(|| {
+		format!(
+			"level {:?} needs a goal, type what has to be delivered into it",
+			info.id
+		)
+	})?;
+
+	let (Some(old), Some(old_metadata)) = (old, old_metadata) else {
+		let toml = toml::to_string(&metadata)
+			.with_context(|| format!("while serializing the metadata of level {:?}", info.id))?;
+		return Ok(Some(toml));
+	};
+
+	let mut toml = old.to_string();
+	let old_level = &old_metadata.level;
+	if old_level.name != metadata.level.name || old_level.desc != metadata.level.desc {
+		let fields = toml::to_string(&metadata.level)
+			.with_context(|| format!("while serializing the name of level {:?}", info.id))?;
+		toml = toml_text::set_table_fields(&toml, "level", &fields);
+	}
+	if old_metadata.goal.as_ref() != Some(goal) {
+		let requirement = toml::Value::try_from(&goal.requirement)
+			.with_context(|| format!("while serializing the goal of level {:?}", info.id))?;
+		toml = toml_text::set_table_fields(&toml, "goal", &format!("requirement = {requirement}"));
+	}
+	if toml == old {
+		return Ok(None);
+	}
+
+	let edited: LevelMetadata = toml::from_str(&toml)
+		.with_context(|| format!("while parsing the edited level.toml of level {:?}", info.id))?;
+	let same = edited.level.name == metadata.level.name
+		&& edited.level.desc == metadata.level.desc
+		&& edited.goal == metadata.goal;
+	if !same {
+		return Err(mklogger::anyhow!(
+			"couldn't write the info of level {:?} into its level.toml, edit it by hand",
+			info.id
+		));
+	}
+	Ok(Some(toml))
+}
+
+/// writes `level.cglf` and `level.toml` into the level's folder in the assets, and adds the level
+/// to the end of the campaign, or updates its requires if it's in it already \
+/// an existing level.toml is edited in place, see [level_toml]. levels without a goal could
+/// never be completed, so they aren't exported
+pub async fn export_level(info: &LevelInfo, level: &LevelFile) -> anyhow::Result<()> {
+	levels::check_level_id(&info.id)?;
+	if info.name.trim().is_empty() {
+		return Err(mklogger::anyhow!("level {:?} needs a name", info.id));
+	}
+
+	let dir = assets_path(&levels::level_dir_key(&info.id));
+	let metadata_path = dir.join("level.toml");
+	let old = match tokio::fs::try_exists(&metadata_path).await? {
+		true => Some(
+			tokio::fs::read_to_string(&metadata_path)
+				.await
+				.with_context(|| format!("while reading {}", metadata_path.display()))?,
+		),
+		false => None,
+	};
+	let metadata = level_toml(old.as_deref(), info)
+		.with_context(|| format!("while writing {}", metadata_path.display()))?;
+
+	// checked before anything is written, so a bad requirement doesn't leave half a level behind
+	let campaign_level = CampaignLevel {
+		id: info.id.clone(),
+		requires: info.requires.clone(),
+	};
+	let levels_toml = Levels::register(&read_levels_toml().await?, &campaign_level)?;
+
+	let bytes = codec::encode_level(level, Compression::Zstd)?;
+	tokio::fs::create_dir_all(&dir)
+		.await
+		.with_context(|| format!("while creating {}", dir.display()))?;
+
+	let level_path = dir.join("level.cglf");
+	tokio::task::spawn_blocking(move || {
+		persist::write_atomic(&level_path, &bytes)?;
+		if let Some(metadata) = metadata {
+			persist::write_atomic(&metadata_path, metadata.as_bytes())?;
+		}
+		if let Some(levels_toml) = levels_toml {
+			persist::write_atomic(&assets_path(LEVELS_TOML_KEY), levels_toml.as_bytes())?;
+		}
+		anyhow::Ok(())
+	})
+	.await??;
+
+	mklogger::println!("exported level {} to {}", info.id, dir.display());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requires_are_typed_as_a_list() {
+		let mut info = LevelInfo::default();
+		info.set(InfoField::Requires, " tutorial,coal_run ,, ".to_string());
+		assert_eq!(info.requires, ["tutorial", "coal_run"]);
+		assert_eq!(info.get(InfoField::Requires), "tutorial, coal_run");
+
+		info.set(InfoField::Id, " smelting ".to_string());
+		assert_eq!(info.id, "smelting");
+	}
+
+	const LEVEL_TOML: &str = "\
+version = 2
+# the four sinks in the middle
+sinks = [[15, 7], [15, 8], [16, 7], [16, 8]]
+
+[level]
+name = \"Coal Run\"
+desc = \"deliver coal\"
+
+[timer]
+secs = 180
+
+[goal]
+requirement = { deliver = { resource = \"coal\", amount = 20 } }
+deadline_secs = 60.0
+";
+
+	fn coal_run() -> LevelInfo {
+		LevelInfo {
+			id: "coal_run".to_string(),
+			name: "Coal Run".to_string(),
+			desc: "deliver coal".to_string(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn level_tomls_are_edited_in_place() {
+		let mut info = coal_run();
+		assert_eq!(level_toml(Some(LEVEL_TOML), &info).unwrap(), None);
+		info.set(InfoField::Goal, "coal 20".to_string());
+		assert_eq!(level_toml(Some(LEVEL_TOML), &info).unwrap(), None);
+
+		info.set(InfoField::Name, "Coal Rush".to_string());
+		info.set(InfoField::Goal, "coal 30".to_string());
+		let toml = level_toml(Some(LEVEL_TOML), &info).unwrap().unwrap();
+		let expected = LEVEL_TOML.replace("\"Coal Run\"", "\"Coal Rush\"").replace(
+			"{ resource = \"coal\", amount = 20 }",
+			"{ amount = 30, resource = \"coal\" }",
+		);
+		assert_eq!(toml, expected);
+
+		let metadata: LevelMetadata = toml::from_str(&toml).unwrap();
+		let goal = metadata.goal().unwrap();
+		assert_eq!(goal.deadline_secs, Some(60.0));
+	}
+
+	#[test]
+	fn levels_need_a_goal() {
+		let mut info = coal_run();
+		assert!(level_toml(None, &info).is_err());
+		let no_goal = "[level]\nname = \"Coal Run\"\ndesc = \"\"\n";
+		assert!(level_toml(Some(no_goal), &info).is_err());
+
+		info.set(InfoField::Goal, "coal 20".to_string());
+		let toml = level_toml(None, &info).unwrap().unwrap();
+		let metadata: LevelMetadata = toml::from_str(&toml).unwrap();
+		assert!(metadata.goal().is_ok());
+
+		let toml = level_toml(Some(no_goal), &info).unwrap().unwrap();
+		assert!(toml.ends_with(
+			"\n\n[goal]\nrequirement = { deliver = { amount = 20, resource = \"coal\" } }\n"
+		));
+	}
+
+	#[test]
+	fn goals_are_typed_as_deliveries() {
+		let mut info = LevelInfo::default();
+		assert_eq!(info.parse_goal().unwrap(), None);
+
+		info.set(InfoField::Goal, "coal 20".to_string());
+		let goal = info.parse_goal().unwrap().unwrap();
+		let coal = Requirement::Deliver {
+			resource: ResourceKind::Coal,
+			amount: 20,
+		};
+		assert_eq!(goal.requirement, coal);
+		assert_eq!(goal_text(&goal).unwrap(), "coal 20");
+
+		info.set(InfoField::Goal, "raw_iron 5, iron 10 ".to_string());
+		let goal = info.parse_goal().unwrap().unwrap();
+		assert_eq!(goal_text(&goal).unwrap(), "raw_iron 5, iron 10");
+
+		for typo in ["coal", "coal many", "gold 5"] {
+			info.set(InfoField::Goal, typo.to_string());
+			assert!(info.parse_goal().is_err(), "{typo:?}");
+		}
+	}
+}
@@ -0,0 +1,231 @@
+// undo and redo for the editor. a step is everything that changed between pressing the mouse and
+// letting go of it
+
+use std::collections::VecDeque;
+
+use game::world::{ETile, maps::Tilemap};
+
+use crate::buildings_layer::BuildingsLayer;
+
+/// how many tiles the history remembers, before the oldest steps are forgotten. a step counts
+/// the tiles it changed, twice for the ones that changed the whole tilemap
+pub const MAX_HISTORY_TILES: usize = 1 << 20;
+
+/// the level as it was when a step started
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+	tilemap: Tilemap,
+	buildings: BuildingsLayer,
+}
+impl Snapshot {
+	pub fn new(tilemap: &Tilemap, buildings: &BuildingsLayer) -> Self {
+		Self {
+			tilemap: tilemap.clone(),
+			buildings: buildings.clone(),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+enum TilesChange {
+	/// position, before and after, of every tile that changed
+	Tiles(Vec<((i32, i32), ETile, ETile)>),
+	/// for changes of the size of the tilemap
+	Whole(Tilemap, Tilemap),
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+	tiles: TilesChange,
+	/// before and after, if the buildings changed
+	buildings: Option<(BuildingsLayer, BuildingsLayer)>,
+}
+impl Step {
+	fn between(before: Snapshot, tilemap: &Tilemap, buildings: &BuildingsLayer) -> Option<Self> {
+		let tiles = match before.tilemap.size() == tilemap.size() {
+			true => {
+				let changed = tilemap.iter().filter_map(|(pos, tile)| {
+					let old = before.tilemap.at(pos)?;
+					let changed = old.symbol() != tile.symbol();
+					changed.then(|| (pos, old.clone(), tile.clone()))
+				});
+				TilesChange::Tiles(changed.collect())
+			}
+			false => TilesChange::Whole(before.tilemap, tilemap.clone()),
+		};
+		let buildings = match before.buildings.placed() == buildings.placed() {
+			true => None,
+			false => Some((before.buildings, buildings.clone())),
+		};
+
+		let step = Self { tiles, buildings };
+		(step.cost() > 0).then_some(step)
+	}
+
+	fn cost(&self) -> usize {
+		let tiles = match &self.tiles {
+			TilesChange::Tiles(tiles) => tiles.len(),
+			TilesChange::Whole(before, after) => {
+				before.width() * before.height() + after.width() * after.height()
+			}
+		};
+		let buildings = match &self.buildings {
+			Some((before, after)) => before.placed().len() + after.placed().len(),
+			None => 0,
+		};
+		tiles + buildings
+	}
+
+	fn apply(&self, tilemap: &mut Tilemap, buildings: &mut BuildingsLayer, forward: bool) {
+		match &self.tiles {
+			TilesChange::Tiles(tiles) => {
+				for (pos, before, after) in tiles {
+					if let Some(target) = tilemap.at_mut(*pos) {
+						*target = if forward { after } else { before }.clone();
+					}
+				}
+			}
+			TilesChange::Whole(before, after) => {
+				*tilemap = if forward { after } else { before }.clone();
+			}
+		}
+		if let Some((before, after)) = &self.buildings {
+			*buildings = if forward { after } else { before }.clone();
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct History {
+	undo: VecDeque<Step>,
+	redo: Vec<Step>,
+	max_tiles: usize,
+}
+impl Default for History {
+	fn default() -> Self {
+		Self::new(MAX_HISTORY_TILES)
+	}
+}
+impl History {
+	pub fn new(max_tiles: usize) -> Self {
+		Self {
+			undo: VecDeque::new(),
+			redo: Vec::new(),
+			max_tiles,
+		}
+	}
+
+	/// remembers what changed since `before` as a step, if anything did. forgets what could be
+	/// redone, and the oldest steps if there are too many tiles
+	pub fn record(&mut self, before: Snapshot, tilemap: &Tilemap, buildings: &BuildingsLayer) {
+		let Some(step) = Step::between(before, tilemap, buildings) else {
+			return;
+		};
+		self.redo.clear();
+		self.undo.push_back(step);
+
+		let mut cost = self.undo.iter().map(Step::cost).sum::<usize>();
+		while cost > self.max_tiles {
+			let Some(oldest) = self.undo.pop_front() else {
+				break;
+			};
+			cost -= oldest.cost();
+		}
+	}
+
+	/// returns false if there was nothing to undo
+	pub fn undo(&mut self, tilemap: &mut Tilemap, buildings: &mut BuildingsLayer) -> bool {
+		let Some(step) = self.undo.pop_back() else {
+			return false;
+		};
+		step.apply(tilemap, buildings, false);
+		self.redo.push(step);
+		true
+	}
+	/// returns false if there was nothing to redo
+	pub fn redo(&mut self, tilemap: &mut Tilemap, buildings: &mut BuildingsLayer) -> bool {
+		let Some(step) = self.redo.pop() else {
+			return false;
+		};
+		step.apply(tilemap, buildings, true);
+		self.undo.push_back(step);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use game::world::maps::ascii;
+
+	use super::*;
+
+	fn tiles(tilemap: &Tilemap) -> String {
+		ascii::print_tilemap(tilemap)
+	}
+
+	#[test]
+	fn undoes_and_redoes_steps() {
+		let mut tilemap = ascii::parse_tilemap("...\n...").unwrap();
+		let mut buildings = BuildingsLayer::default();
+		let mut history = History::default();
+
+		let before = Snapshot::new(&tilemap, &buildings);
+		*tilemap.at_mut((0, 0)).unwrap() = ETile::iron_ore();
+		*tilemap.at_mut((1, 0)).unwrap() = ETile::iron_ore();
+		history.record(before, &tilemap, &buildings);
+
+		let before = Snapshot::new(&tilemap, &buildings);
+		*tilemap.at_mut((2, 1)).unwrap() = ETile::coal_ore();
+		history.record(before, &tilemap, &buildings);
+
+		// nothing changed, so no step
+		let before = Snapshot::new(&tilemap, &buildings);
+		history.record(before, &tilemap, &buildings);
+
+		assert!(history.undo(&mut tilemap, &mut buildings));
+		assert_eq!(tiles(&tilemap), "ii.\n...");
+		assert!(history.undo(&mut tilemap, &mut buildings));
+		assert_eq!(tiles(&tilemap), "...\n...");
+		assert!(!history.undo(&mut tilemap, &mut buildings));
+
+		assert!(history.redo(&mut tilemap, &mut buildings));
+		assert_eq!(tiles(&tilemap), "ii.\n...");
+
+		// a new step can't be followed by what was undone before it
+		let before = Snapshot::new(&tilemap, &buildings);
+		*tilemap.at_mut((0, 1)).unwrap() = ETile::coal_ore();
+		history.record(before, &tilemap, &buildings);
+		assert!(!history.redo(&mut tilemap, &mut buildings));
+		assert_eq!(tiles(&tilemap), "ii.\nc..");
+	}
+
+	#[test]
+	fn undoes_resizes() {
+		let mut tilemap = ascii::parse_tilemap("..\n..").unwrap();
+		let mut buildings = BuildingsLayer::default();
+		let mut history = History::default();
+
+		let before = Snapshot::new(&tilemap, &buildings);
+		tilemap = ascii::parse_tilemap("...").unwrap();
+		history.record(before, &tilemap, &buildings);
+
+		assert!(history.undo(&mut tilemap, &mut buildings));
+		assert_eq!(tiles(&tilemap), "..\n..");
+	}
+
+	#[test]
+	fn forgets_the_oldest_steps() {
+		let mut tilemap = ascii::parse_tilemap("....").unwrap();
+		let mut buildings = BuildingsLayer::default();
+		let mut history = History::new(3);
+
+		for x in 0..4 {
+			let before = Snapshot::new(&tilemap, &buildings);
+			*tilemap.at_mut((x, 0)).unwrap() = ETile::iron_ore();
+			history.record(before, &tilemap, &buildings);
+		}
+
+		while history.undo(&mut tilemap, &mut buildings) {}
+		assert_eq!(tiles(&tilemap), "i...");
+	}
+}
@@ -5,8 +5,11 @@ use game::{
 	utils::ReturnEvents,
 	world::{
 		ETile,
-		maps::{Tilemap, TilemapExt},
+		buildings::PlacedBuilding,
+		codec::{self, Compression, LevelFile},
+		maps::{Anchor, BuildingsMap, Tilemap, TilemapExt},
 		render::TILE_RENDER_SIZE,
+		text,
 	},
 };
 use rfd::AsyncFileDialog;
@@ -23,19 +26,63 @@ use sui::{
 	core::{Event, KeyboardEvent, MouseEvent, ReturnEvent},
 	raylib::ffi::KeyboardKey,
 };
+use utils::{Direction, persist};
+
+use crate::{
+	brush::{self, Symmetry},
+	buildings_layer::BuildingsLayer,
+	export::{self, CloseInfo, EditInfo, ExportLevel, InfoField, LevelInfo, SetInfo},
+	history::{History, Snapshot},
+	segments::{self, SegmentTarget},
+	tools::{self, AnchorChange, OpenInfo, Shape, ShapeChange, SymmetryChange, Tool, ToolChange},
+};
 
-use crate::tools::{self, TileChange};
+/// the biggest [Shape::Brush] radius
+const MAX_RADIUS: i32 = 16;
 
 #[derive(Debug)]
 pub struct LevelEditor {
 	textures: Textures,
 	pub tilemap: Tilemap,
+	pub buildings: BuildingsLayer,
+	/// [Self::buildings] as the game would have them, for drawing
+	buildings_map: BuildingsMap,
 
 	saving_handle: Option<tokio::task::JoinHandle<anyhow::Result<()>>>,
 	last_save_hash: Arc<ArcSwap<u64>>,
 
 	toolbar: DynamicLayable<'static>,
-	placing: ETile,
+	placing: Tool,
+	/// the direction buildings are placed facing, turned with R
+	facing: Direction,
+	rotate_held: bool,
+	/// how tile tools paint
+	shape: Shape,
+	/// of the [Shape::Brush] disc, changed with [ and ]
+	radius: i32,
+	radius_held: bool,
+	symmetry: Symmetry,
+	/// where the mouse was first and last held, while it is
+	stroke: Option<((i32, i32), (i32, i32))>,
+	/// the side that stays put when resizing with ctrl + the arrow keys
+	anchor: Anchor,
+	canvas_held: bool,
+	/// the worldgen segment ctrl + s saves into instead of asking for a level file
+	segment: Option<String>,
+	/// what segments are saved with, changed with - and =
+	min_distance: i32,
+	distance_held: bool,
+	/// what the level is exported with, edited in the level info panel opened with I
+	info: LevelInfo,
+	info_panel: Option<DynamicLayable<'static>>,
+	/// the field of [Self::info] being typed into
+	editing_info: Option<InfoField>,
+
+	history: History,
+	/// the level as it was when the mouse was pressed, recorded into the history once it's let go
+	pending: Option<Snapshot>,
+	undo_held: bool,
+	redo_held: bool,
 
 	/// camera center position in world coordinates
 	camera_at: (f32, f32),
@@ -50,15 +97,41 @@ impl LevelEditor {
 		Self::from_tilemap(tilemap, textures)
 	}
 	pub fn from_tilemap(tilemap: Tilemap, textures: Textures) -> Self {
-		let (width, height) = tilemap.size();
+		Self::from_level(LevelFile::new(tilemap), textures)
+	}
+	pub fn from_level(level: LevelFile, textures: Textures) -> Self {
+		let (width, height) = level.tilemap.size();
+		let buildings = BuildingsLayer::new(level.buildings);
+		let buildings_map = buildings.buildings_map(&level.tilemap);
 
 		let level_editor = Self {
 			textures,
-			tilemap,
+			tilemap: level.tilemap,
+			buildings,
+			buildings_map,
 			saving_handle: None,
 			last_save_hash: Arc::new(ArcSwap::from_pointee(0)),
 			toolbar: DynamicLayable::new(tools::toolbar()),
-			placing: ETile::stone(),
+			placing: Tool::Tile(ETile::stone()),
+			facing: Direction::default(),
+			rotate_held: false,
+			shape: Shape::default(),
+			radius: 0,
+			radius_held: false,
+			symmetry: Symmetry::default(),
+			stroke: None,
+			anchor: Anchor::default(),
+			canvas_held: false,
+			segment: None,
+			min_distance: segments::DEFAULT_MIN_DISTANCE,
+			distance_held: false,
+			info: LevelInfo::default(),
+			info_panel: None,
+			editing_info: None,
+			history: History::default(),
+			pending: None,
+			undo_held: false,
+			redo_held: false,
 			camera_at: (width as f32 / 2.0, height as f32 / 2.0),
 			camera_velocity: (0.0, 0.0),
 			scale: 1.0,
@@ -69,12 +142,30 @@ impl LevelEditor {
 		level_editor
 	}
 
+	/// makes ctrl + s save into the segment instead
+	pub fn with_segment(mut self, segment: Option<SegmentTarget>) -> Self {
+		if let Some(segment) = segment {
+			self.segment = Some(segment.name);
+			self.min_distance = segment.min_distance;
+		}
+		self
+	}
+
+	pub fn with_info(mut self, info: Option<LevelInfo>) -> Self {
+		if let Some(info) = info {
+			self.info = info;
+		}
+		self
+	}
+
 	fn hash_get(&self) -> u64 {
 		let mut hasher = DefaultHasher::default();
 		self.tilemap.hash(&mut hasher);
+		self.buildings.hash(&mut hasher);
+		self.info.hash(&mut hasher);
 		hasher.finish()
 	}
-	/// hashes the tiles and saves them into self.last_save_hash \
+	/// hashes the level and its info and saves them into self.last_save_hash \
 	/// so essentially make it so the game thinks you just saved
 	fn hash_tiles(&self) {
 		self.last_save_hash.store(Arc::new(self.hash_get()));
@@ -94,15 +185,297 @@ impl LevelEditor {
 	fn real_scale(&self) -> f32 {
 		(1.1 as f32).powf(self.scale)
 	}
+
+	/// the tile under a mouse event
+	fn world_pos(&self, event: Event, det: Details, scale: f32) -> anyhow::Result<(i32, i32)> {
+		let mut world = self.wrap_as_world(ReturnEvents, det);
+
+		let ret = world.pass_events_simple(std::iter::once(event), det, scale).into_iter().next().ok_or_else(|| mklogger::anyhow!(
+				"ReturnEvents didn't actually return an event\nneeded to calculate world position of mouse click"))?;
+
+		let ret: Event = ret
+			.take()
+			.ok_or_else(|| mklogger::anyhow!("ReturnEvents didn't return a sui::core::Event"))?;
+
+		match ret {
+			Event::MouseEvent(m_event) => {
+				let (x, y) = m_event.at();
+				Ok((x / TILE_RENDER_SIZE, y / TILE_RENDER_SIZE))
+			}
+			_ => Err(mklogger::anyhow!(
+				"expected MouseEvent::MouseClick, got {ret:?}"
+			)),
+		}
+	}
+
+	/// remembers the level as it is before the first change of a mouse press
+	fn begin_step(&mut self) {
+		if self.pending.is_none() {
+			self.pending = Some(Snapshot::new(&self.tilemap, &self.buildings));
+		}
+	}
+	/// the save hash is of the level itself, so undoing back to what was saved counts as saved
+	fn undo(&mut self) {
+		match self.history.undo(&mut self.tilemap, &mut self.buildings) {
+			true => self.buildings_map = self.buildings.buildings_map(&self.tilemap),
+			false => mklogger::println!("nothing to undo"),
+		}
+	}
+	fn redo(&mut self) {
+		match self.history.redo(&mut self.tilemap, &mut self.buildings) {
+			true => self.buildings_map = self.buildings.buildings_map(&self.tilemap),
+			false => mklogger::println!("nothing to redo"),
+		}
+	}
+
+	/// uses the selected tool at `pos`, as long as the mouse is held
+	fn paint(&mut self, pos: (i32, i32)) {
+		let changed = match &self.placing {
+			Tool::Tile(tile) => {
+				// the other shapes are painted on click, or once the mouse is let go
+				if self.shape == Shape::Brush {
+					let (_, last) = self.stroke.unwrap_or((pos, pos));
+					let cells = brush::stroke(last, pos, self.radius);
+					brush::paint(&mut self.tilemap, cells, tile, self.symmetry);
+				}
+				false
+			}
+			Tool::Building(kind) => {
+				let placed = PlacedBuilding {
+					pos,
+					building: *kind,
+					dir: Some(self.facing),
+					protected: false,
+				};
+				match self.buildings.place(placed, &self.tilemap) {
+					Ok(changed) => changed,
+					Err(err) => {
+						mklogger::eprintln!("{err}");
+						false
+					}
+				}
+			}
+			Tool::EraseBuilding => self.buildings.erase(pos),
+			// toggled on click, so holding the mouse doesn't flip it every frame
+			Tool::Protect => false,
+			// cropped or saved once the mouse is let go
+			Tool::Crop | Tool::Segment => false,
+		};
+		if changed {
+			self.buildings_map = self.buildings.buildings_map(&self.tilemap);
+		}
+	}
+	/// uses the selected tool at `pos`, once per click
+	fn click(&mut self, pos: (i32, i32)) {
+		match self.placing.clone() {
+			Tool::Protect => self.toggle_protected(pos),
+			Tool::Tile(tile) if self.shape == Shape::Fill => {
+				let cells = brush::flood_fill(&self.tilemap, pos);
+				brush::paint(&mut self.tilemap, cells, &tile, self.symmetry);
+			}
+			_ => {}
+		}
+	}
+	/// paints the shapes dragged out from `start` to `end`
+	fn finish_stroke(&mut self, start: (i32, i32), end: (i32, i32)) {
+		match self.placing.clone() {
+			Tool::Tile(tile) => {
+				let cells = match self.shape {
+					Shape::Rect => brush::rect(start, end),
+					Shape::RectOutline => brush::rect_outline(start, end),
+					Shape::Line => brush::line(start, end),
+					Shape::Brush | Shape::Fill => return,
+				};
+				brush::paint(&mut self.tilemap, cells, &tile, self.symmetry);
+			}
+			Tool::Crop => self.crop(start, end),
+			Tool::Segment => self.save_segment(start, end),
+			_ => {}
+		}
+	}
+
+	fn toggle_info_panel(&mut self) {
+		if self.info_panel.is_some() {
+			self.info_panel = None;
+		} else {
+			self.rebuild_info_panel();
+		}
+	}
+	fn rebuild_info_panel(&mut self) {
+		let panel = export::info_panel(&self.info, self.editing_info);
+		self.info_panel = Some(sui::custom_only_debug(panel));
+	}
+	fn info_panel_det(&self, det: Details) -> Option<Details> {
+		let panel = self.info_panel.as_ref()?;
+		let (w, h) = panel.size();
+		let l_det = Details {
+			x: det.x + (det.aw - w).max(0) / 2,
+			y: det.y + (det.ah - h).max(0) / 2,
+			aw: w.min(det.aw),
+			ah: h.min(det.ah),
+		};
+		Some(l_det)
+	}
+	fn info_response(&mut self, response: ReturnEvent) {
+		match response {
+			response if response.can_take::<EditInfo>() => {
+				if let Some(EditInfo(field)) = response.take() {
+					self.editing_info = Some(field);
+					self.rebuild_info_panel();
+				}
+			}
+			response if response.can_take::<SetInfo>() => {
+				if let Some(SetInfo(field, text)) = response.take() {
+					self.info.set(field, text);
+					self.editing_info = None;
+					self.rebuild_info_panel();
+				}
+			}
+			response if response.can_take::<ExportLevel>() => self.export(),
+			response if response.can_take::<CloseInfo>() => {
+				self.editing_info = None;
+				self.info_panel = None;
+			}
+			other => mklogger::println!("non-info panel return event: {other:?}"),
+		}
+	}
+
+	/// writes the level into the game's levels with [Self::info]
+	fn export(&mut self) {
+		if self.is_saving() {
+			mklogger::eprintln!("still saving, try again once it's done");
+			return;
+		}
+		let info = self.info.clone();
+		let level = LevelFile {
+			tilemap: self.tilemap.clone(),
+			buildings: self.buildings.placed().to_vec(),
+		};
+		let current_hash = self.hash_get();
+		let save_hash = self.last_save_hash.clone();
+
+		let handle = tokio::spawn(async move {
+			// nothing looks at what the task returns, so the reason it failed is logged here
+			match export::export_level(&info, &level).await {
+				Ok(()) => {
+					save_hash.swap(Arc::new(current_hash));
+				}
+				Err(err) => mklogger::eprintln!("{err:?}"),
+			}
+			Ok(())
+		});
+		self.saving_handle = Some(handle);
+	}
+
+	fn is_saving(&self) -> bool {
+		let handle = self.saving_handle.as_ref();
+		handle.is_some_and(|handle| !handle.is_finished())
+	}
+	/// asks for a name, then saves the tiles from `start` to `end` as a worldgen segment. only
+	/// the name of the file picked matters, segments always go into the segments folder
+	fn save_segment(&mut self, start: (i32, i32), end: (i32, i32)) {
+		if self.is_saving() {
+			mklogger::eprintln!("still saving, try again once it's done");
+			return;
+		}
+		let tiles = match self.tilemap.crop(start, end) {
+			Ok(tiles) => tiles,
+			Err(err) => {
+				mklogger::eprintln!("{err:?}");
+				return;
+			}
+		};
+		let min_distance = self.min_distance;
+
+		let handle = tokio::spawn(async move {
+			let file = AsyncFileDialog::new()
+				.add_filter("segment", &["cglf"])
+				.set_directory(segments::segments_dir())
+				.set_title("saving segment")
+				.set_file_name("new_segment.cglf")
+				.save_file()
+				.await;
+			let Some(file) = file else {
+				mklogger::eprintln!("file saving dialog didn't return a file handle");
+				return Ok(());
+			};
+
+			let name = file.path().file_stem().and_then(|stem| stem.to_str());
+			let name = name.with_context(|| format!("{:?} isn't a segment name", file.path()))?;
+			let target = SegmentTarget {
+				name: name.to_string(),
+				min_distance,
+			};
+			segments::save_segment(&target, tiles).await
+		});
+		self.saving_handle = Some(handle);
+	}
+
+	/// swaps in a tilemap of another size, moving the buildings by `offset` along with the tiles
+	fn change_canvas(&mut self, tilemap: anyhow::Result<Tilemap>, offset: (i32, i32)) {
+		let tilemap = match tilemap {
+			Ok(tilemap) => tilemap,
+			Err(err) => {
+				mklogger::eprintln!("{err:?}");
+				return;
+			}
+		};
+		self.buildings.reframe(offset, tilemap.size());
+		self.tilemap = tilemap;
+		self.buildings_map = self.buildings.buildings_map(&self.tilemap);
+
+		let (w, h) = self.tilemap.size();
+		mklogger::println!("level is {w}x{h}");
+	}
+	fn resize(&mut self, (dw, dh): (i32, i32)) {
+		let (w, h) = self.tilemap.size();
+		let width = (w as i32 + dw).max(1) as usize;
+		let height = (h as i32 + dh).max(1) as usize;
+		let offset = self.anchor.offset((w, h), (width, height));
+		let tilemap = self
+			.tilemap
+			.resize(width, height, self.anchor, &ETile::stone());
+		self.change_canvas(tilemap, offset);
+	}
+	fn shift(&mut self, offset: (i32, i32)) {
+		let tilemap = self.tilemap.shift(offset, &ETile::stone());
+		self.change_canvas(Ok(tilemap), offset);
+	}
+	fn crop(&mut self, start: (i32, i32), end: (i32, i32)) {
+		let Some(((x, y), _)) = self.tilemap.selection(start, end) else {
+			mklogger::eprintln!("the selection is outside of the level");
+			return;
+		};
+		let tilemap = self.tilemap.crop(start, end);
+		self.change_canvas(tilemap, (-x, -y));
+	}
+
+	fn toggle_protected(&mut self, pos: (i32, i32)) {
+		match self.buildings.toggle_protected(pos) {
+			Ok(Some(protected)) => {
+				mklogger::println!("building at {}, {} protected: {protected}", pos.0, pos.1);
+				self.buildings_map = self.buildings.buildings_map(&self.tilemap);
+			}
+			Ok(None) => {}
+			Err(err) => mklogger::eprintln!("{err}"),
+		}
+	}
 }
 impl Layable for LevelEditor {
 	fn size(&self) -> (i32, i32) {
 		(0, 0)
 	}
 	fn render(&self, d: &mut sui::Handle, det: sui::Details, scale: f32) {
-		self.wrap_as_world(self.tilemap.render(&self.textures), det)
+		let world = self.tilemap.render(&self.textures);
+		let world = world.overlay(self.buildings_map.render(&self.textures));
+		self.wrap_as_world(world, det)
 			.overlay(self.toolbar.immutable_wrap())
 			.render(d, det, scale);
+
+		if let Some(info_panel) = &self.info_panel {
+			let l_det = self.info_panel_det(det).unwrap();
+			info_panel.render(d, l_det, 1.0);
+		}
 	}
 
 	fn tick(&mut self) {
@@ -163,15 +536,29 @@ impl Layable for LevelEditor {
 		scale: f32,
 		ret_events: &mut Vec<ReturnEvent>,
 	) {
+		// the level info panel takes every event while it's open, so typing doesn't paint or move
+		if let Some(l_det) = self.info_panel_det(det) {
+			let info_panel = self.info_panel.as_mut().unwrap();
+			let responses = info_panel.pass_events_simple(events, l_det, 1.0);
+			for response in responses {
+				self.info_response(response);
+			}
+			return;
+		}
+
 		let move_amount = 0.1;
 
-		let (mut ctrl, mut s, mut esc) = (false, false, false);
+		let (mut ctrl, mut s, mut esc, mut rotate) = (false, false, false, false);
+		let (mut held, mut radius_change) = (false, 0);
+		let (mut z, mut y) = (false, false);
+		let (mut alt, mut arrow) = (false, None);
+		let mut distance_change = 0;
 		for event in events {
 			match event {
 				Event::MouseEvent(MouseEvent::Scroll { amount, .. }) => {
 					self.scale_velocity += amount / 2.0
 				}
-				Event::MouseEvent(MouseEvent::MouseClick { y, .. }) => {
+				Event::MouseEvent(MouseEvent::MouseClick { x, y }) => {
 					let (_, toolbar_h) = self.toolbar.size();
 
 					if y <= toolbar_h {
@@ -181,13 +568,38 @@ impl Layable for LevelEditor {
 							.into_iter()
 							.next()
 						{
-							Some(toolbar_resp) if toolbar_resp.can_take::<TileChange>() => {
-								if let Some(TileChange(tool)) = toolbar_resp.take() {
+							Some(toolbar_resp) if toolbar_resp.can_take::<ToolChange>() => {
+								if let Some(ToolChange(tool)) = toolbar_resp.take() {
 									mklogger::println!("selected {tool:?}");
 									self.placing = tool;
 									continue;
 								}
 							}
+							Some(toolbar_resp) if toolbar_resp.can_take::<ShapeChange>() => {
+								if let Some(ShapeChange(shape)) = toolbar_resp.take() {
+									mklogger::println!("painting with {}", shape.name());
+									self.shape = shape;
+									continue;
+								}
+							}
+							Some(toolbar_resp) if toolbar_resp.can_take::<AnchorChange>() => {
+								if let Some(AnchorChange(anchor)) = toolbar_resp.take() {
+									mklogger::println!("anchor {}", tools::anchor_name(anchor));
+									self.anchor = anchor;
+									continue;
+								}
+							}
+							Some(toolbar_resp) if toolbar_resp.can_take::<OpenInfo>() => {
+								self.toggle_info_panel();
+								continue;
+							}
+							Some(toolbar_resp) if toolbar_resp.can_take::<SymmetryChange>() => {
+								if let Some(SymmetryChange(symmetry)) = toolbar_resp.take() {
+									mklogger::println!("{}", symmetry.name());
+									self.symmetry = symmetry;
+									continue;
+								}
+							}
 							Some(other_event) => {
 								mklogger::println!(
 									"non-SelectTool ui return event: {other_event:?}"
@@ -195,30 +607,20 @@ impl Layable for LevelEditor {
 							}
 							None => {}
 						}
+					} else {
+						match self.world_pos(event, det, scale).with_context(|| {
+							format!("while handling {self:?} click at screen (x,y) ({x}, {y})")
+						}) {
+							Ok(world_pos) => {
+								self.begin_step();
+								self.click(world_pos);
+							}
+							Err(err) => mklogger::eprintln!("{err}"),
+						}
 					}
 				}
 				Event::MouseEvent(MouseEvent::MouseHeld { x, y }) => {
-					let world_pos = || {
-						let mut world = self.wrap_as_world(ReturnEvents, det);
-
-						let ret = world.pass_events_simple(std::iter::once(event), det, scale).into_iter().next().ok_or_else(|| mklogger::anyhow!(
-								"ReturnEvents didn't actually return an event\nneeded to calculate world position of mouse click"))?;
-
-						let ret: Event = ret.take().ok_or_else(|| {
-							mklogger::anyhow!("ReturnEvents didn't return a sui::core::Event")
-						})?;
-
-						match ret {
-							Event::MouseEvent(m_event) => {
-								let (x, y) = m_event.at();
-								Ok((x / TILE_RENDER_SIZE, y / TILE_RENDER_SIZE))
-							}
-							_ => Err(mklogger::anyhow!(
-								"expected MouseEvent::MouseClick, got {ret:?}"
-							)),
-						}
-					};
-					let world_pos = world_pos().with_context(|| {
+					let world_pos = self.world_pos(event, det, scale).with_context(|| {
 						format!("while handling {self:?} use action at screen (x,y) ({x}, {y})")
 					});
 
@@ -230,9 +632,11 @@ impl Layable for LevelEditor {
 						}
 					};
 
-					if let Some(target) = self.tilemap.at_mut(world_pos) {
-						*target = self.placing.clone();
-					}
+					self.begin_step();
+					self.paint(world_pos);
+					let start = self.stroke.map(|(start, _)| start).unwrap_or(world_pos);
+					self.stroke = Some((start, world_pos));
+					held = true;
 				}
 
 				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_W)) => {
@@ -249,6 +653,46 @@ impl Layable for LevelEditor {
 					self.camera_velocity.0 += move_amount;
 				}
 
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_R)) => {
+					rotate = true;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_UP)) => {
+					arrow = Some(Direction::Top);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_DOWN)) => {
+					arrow = Some(Direction::Bottom);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_LEFT)) => {
+					arrow = Some(Direction::Left);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_RIGHT)) => {
+					arrow = Some(Direction::Right);
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_LEFT_ALT)) => {
+					alt = true;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::CharPressed('i' | 'I')) => {
+					self.toggle_info_panel();
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_MINUS)) => {
+					distance_change = -1;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_EQUAL)) => {
+					distance_change = 1;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_Z)) => {
+					z = true;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_Y)) => {
+					y = true;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_LEFT_BRACKET)) => {
+					radius_change = -1;
+				}
+				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_RIGHT_BRACKET)) => {
+					radius_change = 1;
+				}
+
 				Event::KeyboardEvent(_, KeyboardEvent::KeyDown(KeyboardKey::KEY_LEFT_CONTROL)) => {
 					ctrl = true;
 				}
@@ -261,6 +705,58 @@ impl Layable for LevelEditor {
 			}
 		}
 
+		// KeyDown comes every frame the key is held, turn once per press
+		if rotate && !self.rotate_held {
+			self.facing = self.facing.rotate_r();
+			mklogger::println!("placing buildings facing {:?}", self.facing);
+		}
+		self.rotate_held = rotate;
+		if radius_change != 0 && !self.radius_held {
+			self.radius = (self.radius + radius_change).clamp(0, MAX_RADIUS);
+			mklogger::println!("brush radius {}", self.radius);
+		}
+		self.radius_held = radius_change != 0;
+		if distance_change != 0 && !self.distance_held {
+			self.min_distance = (self.min_distance + distance_change).max(0);
+			mklogger::println!("segments are saved with min_distance {}", self.min_distance);
+		}
+		self.distance_held = distance_change != 0;
+
+		// ctrl + arrows grow the level right and down and shrink it left and up, alt + arrows move
+		// everything on it
+		let canvas = arrow.filter(|_| ctrl || alt);
+		if let Some(dir) = canvas.filter(|_| !self.canvas_held) {
+			self.begin_step();
+			match ctrl {
+				true => self.resize(dir.rel()),
+				false => self.shift(dir.rel()),
+			}
+		}
+		self.canvas_held = canvas.is_some();
+
+		// there's no event for letting go of the mouse, it's let go once a frame has no MouseHeld
+		if !held {
+			if let Some((start, end)) = self.stroke.take() {
+				self.finish_stroke(start, end);
+			}
+			if let Some(before) = self.pending.take() {
+				self.history.record(before, &self.tilemap, &self.buildings);
+			}
+		}
+
+		// undoing in the middle of a stroke would be recorded into it, so wait for it to end
+		let undo = ctrl && z && !self.undo_held;
+		let redo = ctrl && y && !self.redo_held;
+		self.undo_held = ctrl && z;
+		self.redo_held = ctrl && y;
+		if self.pending.is_none() {
+			if undo {
+				self.undo();
+			} else if redo {
+				self.redo();
+			}
+		}
+
 		if esc {
 			let saved = *self.last_save_hash.load().deref().deref() == self.hash_get();
 			match (saved, ctrl) {
@@ -277,52 +773,63 @@ impl Layable for LevelEditor {
 			}
 		}
 
-		if ctrl && s {
-			if self
-				.saving_handle
-				.as_ref()
-				.map(|h| h.is_finished())
-				.unwrap_or(true)
-			{
-				let tilemap = self.tilemap.clone();
-				let current_hash = self.hash_get();
-				let save_hash = self.last_save_hash.clone();
+		if ctrl && s && !self.is_saving() {
+			let current_hash = self.hash_get();
+			let save_hash = self.last_save_hash.clone();
+
+			if let Some(name) = &self.segment {
+				let target = SegmentTarget {
+					name: name.clone(),
+					min_distance: self.min_distance,
+				};
+				if !self.buildings.placed().is_empty() {
+					mklogger::eprintln!("segments are only tiles, the buildings aren't saved");
+				}
+				let tiles = self.tilemap.clone();
 
 				let handle = tokio::spawn(async move {
-					let files = AsyncFileDialog::new()
-						.add_filter("level file", &["cglf"])
-						.set_directory(std::env::current_dir()?)
-						.set_title("saving level")
-						.set_file_name("new-level.cglf")
-						.save_file()
-						.await;
-
-					if let Some(files) = files {
-						let path = PathBuf::from(files.path());
-						mklogger::println!("saving to {path:?}");
-
-						let file = tokio::fs::OpenOptions::new()
-							.write(true)
-							.create(true)
-							.open(&path)
-							.await?;
-						let mut file = file.into_std().await;
-
-						bincode::serde::encode_into_std_write(
-							&tilemap,
-							&mut file,
-							bincode::config::standard(),
-						)?;
-
-						save_hash.swap(Arc::new(current_hash));
-					} else {
-						mklogger::eprintln!("file saving dialog didn't return a file handle");
-					}
+					segments::save_segment(&target, tiles).await?;
+					save_hash.swap(Arc::new(current_hash));
 					Ok(())
 				});
-
 				self.saving_handle = Some(handle);
+				return;
 			}
+
+			let level = LevelFile {
+				tilemap: self.tilemap.clone(),
+				buildings: self.buildings.placed().to_vec(),
+			};
+
+			let handle = tokio::spawn(async move {
+				let files = AsyncFileDialog::new()
+					.add_filter("level file", &["cglf"])
+					.add_filter("text level file", &["ron"])
+					.set_directory(std::env::current_dir()?)
+					.set_title("saving level")
+					.set_file_name("new-level.cglf")
+					.save_file()
+					.await;
+
+				if let Some(files) = files {
+					let path = PathBuf::from(files.path());
+					mklogger::println!("saving to {path:?}");
+
+					let level = match path.extension().is_some_and(|ext| ext == "ron") {
+						true => text::level_to_text(&level)?.into_bytes(),
+						false => codec::encode_level(&level, Compression::Zstd)?,
+					};
+					tokio::task::spawn_blocking(move || persist::write_atomic(&path, &level))
+						.await??;
+
+					save_hash.swap(Arc::new(current_hash));
+				} else {
+					mklogger::eprintln!("file saving dialog didn't return a file handle");
+				}
+				Ok(())
+			});
+
+			self.saving_handle = Some(handle);
 		}
 	}
 }
@@ -1,6 +1,7 @@
 use std::{
 	borrow::Cow,
 	fmt::{Debug, Display},
+	path::PathBuf,
 	sync::Arc,
 };
 
@@ -8,12 +9,24 @@ use anyhow::Context;
 use game::{
 	assets::GameAssets,
 	textures,
-	world::maps::{Tilemap, TilemapExt},
+	world::{
+		codec::{self, LevelFile},
+		maps::{Tilemap, TilemapExt},
+		text,
+	},
 };
 use utils::SilentUnwrap;
 
+pub mod brush;
+pub mod buildings_layer;
+pub mod export;
+pub mod history;
 pub mod level_editor;
+pub mod png_import;
+pub mod segments;
+use export::LevelInfo;
 use level_editor::LevelEditor;
+use segments::SegmentTarget;
 use stage_manager::StageChange;
 use stage_manager_loaders::Loader;
 use sui::{Compatible, Layable, LayableExt, core::Store, form::typable::TypableData};
@@ -79,6 +92,12 @@ fn creation_screen() -> impl Layable + Debug {
 		),
 		sui::custom(sui::text("or", 32).center_y().margin(32)),
 		sui::custom_only_debug(sui::text("load from file", 32).clickable(move |_| open_screen())),
+		sui::custom_only_debug(
+			sui::text("import from png", 32).clickable(move |_| import_png_screen()),
+		),
+		sui::custom_only_debug(
+			sui::text("open segment", 32).clickable(move |_| open_segment_screen()),
+		),
 	])
 	.centered()
 }
@@ -136,7 +155,7 @@ fn open_screen() -> StageChange<'static> {
 			use rfd::AsyncFileDialog;
 
 			let picker = AsyncFileDialog::new()
-				.add_filter("level file", &["cglf"])
+				.add_filter("level file", &["cglf", "ron"])
 				.set_directory(std::env::current_dir()?)
 				.set_title("select level to load")
 				.pick_file()
@@ -145,32 +164,130 @@ fn open_screen() -> StageChange<'static> {
 				.with_context(|| format!("AsyncFileDialog didn't return a file handle"))
 				.with_context(|| format!("failed to open file"))?;
 
-			let file = tokio::fs::OpenOptions::new()
-				.read(true)
-				.open(file.path())
-				.await?;
-			let mut file = file.into_std().await;
-
-			let tilemap: Tilemap =
-				bincode::serde::decode_from_std_read(&mut file, bincode::config::standard())?;
+			let level = tokio::fs::read(file.path())
+				.await
+				.with_context(|| format!("while reading {}", file.path().display()))?;
+			let level = match file.path().extension().is_some_and(|ext| ext == "ron") {
+				true => text::level_from_text(&String::from_utf8(level)?)?,
+				false => codec::decode_level(&level)?,
+			};
 
-			anyhow::Ok(tilemap)
-		},
-		|p| match p {
-			Ok(tilemap) => textures::load_as_scene(GameAssets::default(), move |tex| match tex {
-				Ok(tex) => {
-					let level_editor = LevelEditor::from_tilemap(tilemap.clone(), tex);
-					sui::DynamicLayable::new_only_debug(level_editor)
+			// levels of the game come with their metadata next to them
+			let mut opened = Opened::level(level);
+			let dir = file.path().parent().unwrap_or(file.path());
+			if tokio::fs::try_exists(dir.join("level.toml")).await? {
+				match LevelInfo::load(dir).await {
+					Ok(info) => opened.info = Some(info),
+					Err(err) => mklogger::eprintln!("{err:?}"),
 				}
-				Err(err) => sui::custom_only_debug(err_page(err)),
-			}),
-			Err(err) => StageChange::Simple(sui::custom_only_debug(err_page(err))),
+			}
+			anyhow::Ok(opened)
 		},
+		editor_for,
 	);
 
 	loading
 }
 
+fn import_png_screen() -> StageChange<'static> {
+	Loader::new_overlay(
+		sui::text("select image on file picker", 32).centered(),
+		async {
+			use rfd::AsyncFileDialog;
+
+			let picker = AsyncFileDialog::new()
+				.add_filter("image", &["png"])
+				.set_directory(std::env::current_dir()?)
+				.set_title("select image to import")
+				.pick_file()
+				.await;
+			let file = picker
+				.with_context(|| format!("AsyncFileDialog didn't return a file handle"))
+				.with_context(|| format!("failed to open file"))?;
+
+			let bytes = tokio::fs::read(file.path())
+				.await
+				.with_context(|| format!("while reading {}", file.path().display()))?;
+			let image = asset_provider_image::image::load_from_memory(&bytes)
+				.with_context(|| format!("while decoding {}", file.path().display()))?
+				.to_rgba8();
+			let palette = png_import::Palette::for_image(file.path()).await?;
+
+			let tilemap = png_import::tilemap_from_image(&image, &palette)
+				.with_context(|| format!("while importing {}", file.path().display()))?;
+			anyhow::Ok(Opened::level(LevelFile::new(tilemap)))
+		},
+		editor_for,
+	)
+}
+
+fn open_segment_screen() -> StageChange<'static> {
+	Loader::new_overlay(
+		sui::text("select segment on file picker", 32).centered(),
+		async {
+			use rfd::AsyncFileDialog;
+
+			let picker = AsyncFileDialog::new()
+				.add_filter("segment", &["cglf"])
+				.set_directory(segments::segments_dir())
+				.set_title("select segment to edit")
+				.pick_file()
+				.await;
+			let file = picker
+				.with_context(|| format!("AsyncFileDialog didn't return a file handle"))
+				.with_context(|| format!("failed to open file"))?;
+
+			let name = file.path().file_stem().and_then(|stem| stem.to_str());
+			let name = name.with_context(|| format!("{:?} isn't a segment", file.path()))?;
+			let (tilemap, target) = segments::open_segment(name).await?;
+			let mut opened = Opened::level(LevelFile::new(tilemap));
+			opened.segment = Some(target);
+			anyhow::Ok(opened)
+		},
+		editor_for,
+	)
+}
+
+/// a level that was just loaded, with what the editor knows about where it came from
+struct Opened {
+	level: LevelFile,
+	/// ctrl + s saves back into it
+	segment: Option<SegmentTarget>,
+	/// what the level info panel starts with
+	info: Option<LevelInfo>,
+}
+impl Opened {
+	fn level(level: LevelFile) -> Self {
+		Self {
+			level,
+			segment: None,
+			info: None,
+		}
+	}
+}
+
+/// opens the editor on a level that was just loaded, or shows why it couldn't be
+fn editor_for(opened: anyhow::Result<Opened>) -> StageChange<'static> {
+	match opened {
+		Ok(opened) => textures::load_as_scene(GameAssets::default(), move |tex| match tex {
+			Ok(tex) => {
+				let level_editor = LevelEditor::from_level(opened.level.clone(), tex);
+				let level_editor = level_editor.with_segment(opened.segment.clone());
+				let level_editor = level_editor.with_info(opened.info.clone());
+				sui::DynamicLayable::new_only_debug(level_editor)
+			}
+			Err(err) => sui::custom_only_debug(err_page(err)),
+		}),
+		Err(err) => StageChange::Simple(sui::custom_only_debug(err_page(err))),
+	}
+}
+
+/// where `key` is in the assets folder of the repository, which segments and levels are written
+/// into so they ship with the game
+pub(crate) fn assets_path(key: &str) -> PathBuf {
+	PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets")).join(key)
+}
+
 fn err_page<E: Debug + Display>(err: E) -> impl Layable + Debug {
 	comp_extra::err_page_customizable(
 		err,
@@ -0,0 +1,163 @@
+// turns images drawn in any paint program into tilemaps, one pixel per tile
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	path::Path,
+};
+
+use anyhow::Context;
+use asset_provider_image::image::RgbaImage;
+use game::world::{ETile, maps::Tilemap};
+
+/// the name of the palette file looked for next to imported images
+pub const PALETTE_FILE: &str = "palette.toml";
+/// used when there's no [PALETTE_FILE] next to the image
+const DEFAULT_PALETTE: &str = r##"
+[tiles]
+"#808080" = "."
+"#c08040" = "i"
+"#202020" = "c"
+"##;
+
+/// how many unknown colours are listed before the rest are only counted
+const LISTED_UNKNOWN: usize = 8;
+
+/// the layout of [PALETTE_FILE]: `#rrggbb` pixel colours to [ETile::symbol]s
+#[derive(Clone, Debug, serde::Deserialize)]
+struct PaletteToml {
+	tiles: BTreeMap<String, char>,
+}
+
+/// which tile each pixel colour stands for
+#[derive(Clone, Debug)]
+pub struct Palette {
+	tiles: HashMap<[u8; 3], ETile>,
+}
+impl Default for Palette {
+	fn default() -> Self {
+		Self::from_toml(DEFAULT_PALETTE).expect("the default palette is broken")
+	}
+}
+impl Palette {
+	pub fn from_toml(toml: &str) -> anyhow::Result<Self> {
+		let palette: PaletteToml =
+			toml::from_str(toml).with_context(|| format!("while parsing palette"))?;
+
+		let tiles = palette.tiles.into_iter().map(|(color, symbol)| {
+			let tile = ETile::from_symbol(symbol)
+				.with_context(|| format!("{color} stands for unknown tile symbol {symbol:?}"))?;
+			anyhow::Ok((parse_color(&color)?, tile))
+		});
+		let tiles = tiles.collect::<anyhow::Result<_>>()?;
+		Ok(Self { tiles })
+	}
+
+	/// the [PALETTE_FILE] next to the image, or the default palette if there's none
+	pub async fn for_image(image: &Path) -> anyhow::Result<Self> {
+		let path = image.with_file_name(PALETTE_FILE);
+		if !tokio::fs::try_exists(&path).await? {
+			return Ok(Self::default());
+		}
+		let toml = tokio::fs::read_to_string(&path)
+			.await
+			.with_context(|| format!("while reading {}", path.display()))?;
+		Self::from_toml(&toml).with_context(|| format!("in {}", path.display()))
+	}
+}
+
+fn parse_color(color: &str) -> anyhow::Result<[u8; 3]> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	let channel = |i: usize| {
+		let digits = hex.get(i * 2..i * 2 + 2);
+		digits.and_then(|digits| u8::from_str_radix(digits, 16).ok())
+	};
+	match (hex.len(), channel(0), channel(1), channel(2)) {
+		(6, Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+		_ => Err(mklogger::anyhow!("{color:?} isn't a #rrggbb colour")),
+	}
+}
+fn format_color([r, g, b]: [u8; 3]) -> String {
+	format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// transparency is ignored. fails listing every colour the palette doesn't have, with where it
+/// first shows up
+pub fn tilemap_from_image(image: &RgbaImage, palette: &Palette) -> anyhow::Result<Tilemap> {
+	let (width, height) = image.dimensions();
+
+	// colour -> first position and how many pixels have it
+	let mut unknown = BTreeMap::<[u8; 3], ((u32, u32), usize)>::new();
+	let mut columns = Vec::with_capacity(width as usize);
+	for x in 0..width {
+		let mut column = Vec::with_capacity(height as usize);
+		for y in 0..height {
+			let [r, g, b, _] = image.get_pixel(x, y).0;
+			match palette.tiles.get(&[r, g, b]) {
+				Some(tile) => column.push(tile.clone()),
+				None => unknown.entry([r, g, b]).or_insert(((x, y), 0)).1 += 1,
+			}
+		}
+		columns.push(column);
+	}
+
+	if !unknown.is_empty() {
+		let mut listed = unknown
+			.iter()
+			.take(LISTED_UNKNOWN)
+			.map(|(color, ((x, y), count))| {
+				format!("{} at {x}, {y} ({count} pixels)", format_color(*color))
+			})
+			.collect::<Vec<_>>();
+		if unknown.len() > LISTED_UNKNOWN {
+			listed.push(format!("and {} more", unknown.len() - LISTED_UNKNOWN));
+		}
+		return Err(mklogger::anyhow!(
+			"the image has colours the palette doesn't:\n{}",
+			listed.join("\n")
+		));
+	}
+	Tilemap::from_vec(columns)
+}
+
+#[cfg(test)]
+mod tests {
+	use asset_provider_image::image::Rgba;
+
+	use super::*;
+
+	const STONE: Rgba<u8> = Rgba([0x80, 0x80, 0x80, 255]);
+	const IRON: Rgba<u8> = Rgba([0xc0, 0x80, 0x40, 255]);
+
+	#[test]
+	fn maps_pixels_to_tiles() {
+		let mut image = RgbaImage::from_pixel(3, 2, STONE);
+		image.put_pixel(2, 1, IRON);
+
+		let tilemap = tilemap_from_image(&image, &Palette::default()).unwrap();
+		assert_eq!(tilemap.size(), (3, 2));
+		assert_eq!(tilemap.at((2, 1)).map(ETile::symbol), Some('i'));
+		assert_eq!(tilemap.at((0, 0)).map(ETile::symbol), Some('.'));
+	}
+
+	#[test]
+	fn reports_unknown_colours() {
+		let mut image = RgbaImage::from_pixel(4, 4, STONE);
+		image.put_pixel(3, 1, Rgba([255, 0, 0, 255]));
+		image.put_pixel(1, 2, Rgba([255, 0, 0, 255]));
+
+		let err = tilemap_from_image(&image, &Palette::default()).unwrap_err();
+		let err = format!("{err}");
+		assert!(err.contains("#ff0000 at 1, 2 (2 pixels)"), "{err}");
+	}
+
+	#[test]
+	fn reads_palettes() {
+		let palette = Palette::from_toml("[tiles]\n\"#ff0000\" = \"c\"").unwrap();
+		let image = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 255]));
+		let tilemap = tilemap_from_image(&image, &palette).unwrap();
+		assert_eq!(tilemap.at((0, 0)).map(ETile::symbol), Some('c'));
+
+		assert!(Palette::from_toml("[tiles]\n\"#ff00\" = \"c\"").is_err());
+		assert!(Palette::from_toml("[tiles]\n\"#ff0000\" = \"x\"").is_err());
+	}
+}
@@ -0,0 +1,87 @@
+// writing worldgen segments straight into the assets folder of the repository, so they're made
+// with the same tool as levels
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use game::world::{
+	codec::{self, Compression, LevelFile},
+	maps::Tilemap,
+};
+use game_worldgen::{AssetSegment, SEGMENT_TILEMAP_KEY, SEGMENTS_TOML_KEY};
+use utils::persist;
+
+use crate::assets_path;
+
+/// what new segments are saved with, the same as most of the existing ones
+pub const DEFAULT_MIN_DISTANCE: i32 = 10;
+
+/// the segment a [crate::level_editor::LevelEditor] saves into
+#[derive(Clone, Debug)]
+pub struct SegmentTarget {
+	pub name: String,
+	pub min_distance: i32,
+}
+
+pub fn segments_dir() -> PathBuf {
+	assets_path(SEGMENTS_TOML_KEY)
+		.parent()
+		.expect("segments.toml is in a folder")
+		.to_path_buf()
+}
+fn tilemap_path(name: &str) -> PathBuf {
+	assets_path(&SEGMENT_TILEMAP_KEY.replace("{key}", name))
+}
+
+/// writes the tiles of the segment and its entry in segments.toml. segments are only tiles, where
+/// stone is nothing
+pub async fn save_segment(target: &SegmentTarget, tiles: Tilemap) -> anyhow::Result<()> {
+	game_worldgen::check_segment_name(&target.name)?;
+
+	let bytes = codec::encode_level(&LevelFile::new(tiles), Compression::Zstd)?;
+	let path = tilemap_path(&target.name);
+	tokio::task::spawn_blocking(move || persist::write_atomic(&path, &bytes)).await??;
+
+	let toml_path = assets_path(SEGMENTS_TOML_KEY);
+	let toml = tokio::fs::read_to_string(&toml_path)
+		.await
+		.with_context(|| format!("while reading {}", toml_path.display()))?;
+	let segment = AssetSegment {
+		min_distance: target.min_distance,
+	};
+	let toml = game_worldgen::set_segment_entry(&toml, &target.name, segment)?;
+	tokio::task::spawn_blocking(move || persist::write_atomic(&toml_path, toml.as_bytes()))
+		.await??;
+
+	mklogger::println!(
+		"saved segment {} with min_distance {}",
+		target.name,
+		target.min_distance
+	);
+	Ok(())
+}
+
+/// the tiles of a segment and where it's saved back to. segments missing from segments.toml get
+/// [DEFAULT_MIN_DISTANCE]
+pub async fn open_segment(name: &str) -> anyhow::Result<(Tilemap, SegmentTarget)> {
+	let path = tilemap_path(name);
+	let bytes = tokio::fs::read(&path)
+		.await
+		.with_context(|| format!("while reading {}", path.display()))?;
+	let tiles =
+		codec::decode_tilemap(&bytes).with_context(|| format!("while decoding segment {name}"))?;
+
+	let toml_path = assets_path(SEGMENTS_TOML_KEY);
+	let toml = tokio::fs::read(&toml_path)
+		.await
+		.with_context(|| format!("while reading {}", toml_path.display()))?;
+	let segments: game_worldgen::SegmentsToml = toml::from_slice(&toml)
+		.with_context(|| format!("while deserializing {}", toml_path.display()))?;
+	let min_distance = segments.get(name).map(|segment| segment.min_distance);
+
+	let target = SegmentTarget {
+		name: name.to_string(),
+		min_distance: min_distance.unwrap_or(DEFAULT_MIN_DISTANCE),
+	};
+	Ok((tiles, target))
+}
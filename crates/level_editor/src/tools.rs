@@ -1,29 +1,174 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug};
 
-use game::world::{ETile, Tile};
+use game::world::{
+	ETile, Tile,
+	buildings::{Building, BuildingKind},
+	maps::Anchor,
+};
 use strum::IntoEnumIterator;
 use sui::{Layable, LayableExt};
+use utils::Direction;
 
-/// requests the changing of the tile we're placing
+use crate::brush::Symmetry;
+
+/// what clicking on the level does
 #[derive(Clone, Debug)]
-pub struct TileChange(pub ETile);
+pub enum Tool {
+	Tile(ETile),
+	/// placed facing the editor's current direction
+	Building(BuildingKind),
+	EraseBuilding,
+	/// toggles the protection of the building clicked
+	Protect,
+	/// cuts the level down to the rectangle dragged out
+	Crop,
+	/// saves the rectangle dragged out as a worldgen segment
+	Segment,
+}
+impl Tool {
+	pub fn name(&self) -> Cow<'static, str> {
+		match self {
+			Self::Tile(tile) => tile.name(),
+			Self::Building(BuildingKind::ChannelConsumer) => "sink".into(),
+			Self::Building(kind) => match kind.building(Direction::Top) {
+				Some(building) => building.name(),
+				None => format!("{kind:?}").into(),
+			},
+			Self::EraseBuilding => "erase".into(),
+			Self::Protect => "protect".into(),
+			Self::Crop => "crop".into(),
+			Self::Segment => "save segment".into(),
+		}
+	}
+}
 
-fn tools() -> impl Iterator<Item = ETile> {
-	ETile::iter()
+/// how tile tools paint. buildings are always placed one at a time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+pub enum Shape {
+	/// a disc of the brush radius wherever the mouse goes
+	#[default]
+	Brush,
+	/// every connected tile of the same kind as the one clicked
+	Fill,
+	/// from where the mouse was pressed to where it's let go
+	Rect,
+	RectOutline,
+	Line,
+}
+impl Shape {
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Brush => "brush",
+			Self::Fill => "fill",
+			Self::Rect => "rectangle",
+			Self::RectOutline => "outline",
+			Self::Line => "line",
+		}
+	}
 }
 
-/// creates the toolbar layout. listen to [SelectTool] in your component to have it working
+/// requests the changing of the tool we're using
+#[derive(Clone, Debug)]
+pub struct ToolChange(pub Tool);
+/// requests the changing of how tiles are painted
+#[derive(Clone, Debug)]
+pub struct ShapeChange(pub Shape);
+/// requests the changing of the mirroring of everything painted
+#[derive(Clone, Debug)]
+pub struct SymmetryChange(pub Symmetry);
+/// requests the changing of the side the level grows and shrinks from
+#[derive(Clone, Debug)]
+pub struct AnchorChange(pub Anchor);
+/// requests opening the panel for the name and description of the level
+#[derive(Clone, Debug)]
+pub struct OpenInfo;
+
+pub fn anchor_name(anchor: Anchor) -> &'static str {
+	match anchor {
+		Anchor::TopLeft => "nw",
+		Anchor::Top => "n",
+		Anchor::TopRight => "ne",
+		Anchor::Left => "w",
+		Anchor::Center => "center",
+		Anchor::Right => "e",
+		Anchor::BottomLeft => "sw",
+		Anchor::Bottom => "s",
+		Anchor::BottomRight => "se",
+	}
+}
+
+fn tile_tools() -> impl Iterator<Item = Tool> {
+	ETile::iter().map(Tool::Tile)
+}
+fn building_tools() -> impl Iterator<Item = Tool> {
+	let kinds = BuildingKind::iter().filter(|kind| *kind != BuildingKind::Nothing);
+	let kinds = kinds.map(Tool::Building);
+	kinds.chain([Tool::EraseBuilding, Tool::Protect])
+}
+
+/// creates the toolbar layout: tiles, buildings, the ways to paint, then the canvas. listen to
+/// [ToolChange], [ShapeChange], [SymmetryChange], [AnchorChange] and [OpenInfo] in your component
+/// to have it working
 pub fn toolbar() -> impl Layable + Clone + Debug {
-	toolbar_from_tools(tools())
+	sui::comp::div::SpaceBetween::new(vec![
+		sui::custom(toolbar_from_tools(tile_tools())),
+		sui::custom(toolbar_from_tools(building_tools())),
+		sui::custom(shapes_toolbar()),
+		sui::custom(canvas_toolbar()),
+	])
+}
+fn shapes_toolbar() -> impl Layable + Clone + Debug {
+	let shapes = Shape::iter().map(|shape| {
+		sui::custom(
+			sui::Text::new(shape.name(), 24)
+				.margin(4)
+				.clickable(move |_| ShapeChange(shape)),
+		)
+	});
+	let symmetries = Symmetry::iter().map(|symmetry| {
+		sui::custom(
+			sui::Text::new(symmetry.name(), 24)
+				.margin(4)
+				.clickable(move |_| SymmetryChange(symmetry)),
+		)
+	});
+
+	sui::comp::div::SpaceBetween::new_horizontal(shapes.chain(symmetries).collect::<Vec<_>>())
 }
-pub fn toolbar_from_tools(tools: impl Iterator<Item = ETile>) -> impl Layable + Clone + Debug {
+pub fn toolbar_from_tools(tools: impl Iterator<Item = Tool>) -> impl Layable + Clone + Debug {
 	let toolbar = tools.map(|tool| {
 		sui::Text::new(tool.name(), 24)
 			.margin(4)
-			.clickable(move |_| TileChange(tool.clone()))
+			.clickable(move |_| ToolChange(tool.clone()))
 	});
 
 	let toolbar = sui::comp::div::SpaceBetween::new_horizontal(toolbar.collect::<Vec<_>>());
 
 	toolbar
 }
+/// resizing itself is done with ctrl + the arrow keys, shifting with alt + the arrow keys
+fn canvas_toolbar() -> impl Layable + Clone + Debug {
+	let selections = [Tool::Crop, Tool::Segment].map(|tool| {
+		sui::custom(
+			sui::Text::new(tool.name(), 24)
+				.margin(4)
+				.clickable(move |_| ToolChange(tool.clone())),
+		)
+	});
+	let anchors = Anchor::iter().map(|anchor| {
+		sui::custom(
+			sui::Text::new(format!("anchor {}", anchor_name(anchor)), 24)
+				.margin(4)
+				.clickable(move |_| AnchorChange(anchor)),
+		)
+	});
+
+	let info = sui::custom(
+		sui::Text::new("level info", 24)
+			.margin(4)
+			.clickable(|_| OpenInfo),
+	);
+
+	let items = selections.into_iter().chain(anchors).chain([info]);
+	sui::comp::div::SpaceBetween::new_horizontal(items.collect::<Vec<_>>())
+}
@@ -16,7 +16,7 @@ use tokio::sync::mpsc::Sender;
 use super::TextureID;
 use super::Textures;
 
-use super::INTERNAL_CACHE;
+use super::render::INTERNAL_CACHE;
 
 #[derive(Debug)]
 pub enum TextureLoaderPacket {
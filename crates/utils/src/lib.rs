@@ -6,3 +6,5 @@ mod nodebug;
 pub use nodebug::*;
 mod silent_unwrap;
 pub use silent_unwrap::*;
+pub mod persist;
+pub mod toml_text;
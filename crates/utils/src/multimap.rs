@@ -1,4 +1,7 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+	collections::{BTreeMap, HashMap},
+	hash::Hash,
+};
 
 pub trait MultiMap<K, V> {
 	/// returns the new length of the array
@@ -39,3 +42,18 @@ impl<K: PartialEq + Eq + Hash, V> MultiMap<K, V> for HashMap<K, Vec<V>> {
 		self.iter_mut().map(|(key, value)| (key, value.drain(..)))
 	}
 }
+/// drains in key order, unlike the HashMap implementation
+impl<K: Ord, V> MultiMap<K, V> for BTreeMap<K, Vec<V>> {
+	fn multimap_insert(&mut self, key: K, value: V) -> usize {
+		let values = self.entry(key).or_default();
+		values.push(value);
+		values.len()
+	}
+
+	fn multimap_drain<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, impl Iterator<Item = V>)>
+	where
+		K: 'a,
+	{
+		self.iter_mut().map(|(key, value)| (key, value.drain(..)))
+	}
+}
@@ -0,0 +1,138 @@
+use std::{
+	ffi::OsString,
+	fs::{self, File},
+	io::Write,
+	path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+/// `path` with `suffix` appended to the file name, e.g. `save.cgs` -> `save.cgs.bak`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+	let mut name = OsString::from(path.as_os_str());
+	name.push(suffix);
+	PathBuf::from(name)
+}
+/// where [write_atomic] keeps the previous version of `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+	with_suffix(path, ".bak")
+}
+
+/// replaces the file at `path` without ever leaving a half written file behind \
+/// the bytes go into a temp file next to it, get synced to disk, then the temp file is renamed
+/// over `path`. the previous version of `path` is kept at [backup_path]
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+	let temp = with_suffix(path, ".tmp");
+	{
+		let mut file = File::create(&temp)
+			.with_context(|| format!("while creating temp file {}", temp.display()))?;
+		file.write_all(bytes)
+			.with_context(|| format!("while writing temp file {}", temp.display()))?;
+		file.sync_all()
+			.with_context(|| format!("while syncing temp file {}", temp.display()))?;
+	}
+
+	if path.exists() {
+		let backup = backup_path(path);
+		fs::rename(path, &backup)
+			.with_context(|| format!("while moving {} to {}", path.display(), backup.display()))?;
+	}
+	fs::rename(&temp, path)
+		.with_context(|| format!("while moving {} to {}", temp.display(), path.display()))?;
+
+	sync_parent(path);
+	Ok(())
+}
+
+/// makes the renames durable. not every platform lets directories be opened, so it's best effort
+fn sync_parent(path: &Path) {
+	let parent = match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => parent,
+		_ => Path::new("."),
+	};
+	if let Ok(dir) = File::open(parent) {
+		let _ = dir.sync_all();
+	}
+}
+
+/// reads and decodes `path`, falling back to the backup [write_atomic] keeps if the file is
+/// missing or `decode` fails on it
+pub fn read_with_backup<T>(
+	path: &Path,
+	decode: impl Fn(&[u8]) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+	let read = |path: &Path| {
+		let bytes = fs::read(path).with_context(|| format!("while reading {}", path.display()))?;
+		decode(&bytes).with_context(|| format!("while decoding {}", path.display()))
+	};
+
+	let err = match read(path) {
+		Ok(decoded) => return Ok(decoded),
+		Err(err) => err,
+	};
+
+	let backup = backup_path(path);
+	if !backup.exists() {
+		return Err(err);
+	}
+	match read(&backup) {
+		Ok(decoded) => {
+			mklogger::eprintln!(
+				"{} couldn't be loaded, using its backup instead:\n{err:?}",
+				path.display()
+			);
+			Ok(decoded)
+		}
+		Err(backup_err) => Err(err.context(format!("the backup failed too: {backup_err:?}"))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("utils-persist-{name}-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+	fn decode_utf8(bytes: &[u8]) -> anyhow::Result<String> {
+		Ok(String::from_utf8(bytes.to_vec())?)
+	}
+
+	#[test]
+	fn overwrites_and_keeps_backup() {
+		let dir = test_dir("backup");
+		let path = dir.join("state");
+
+		write_atomic(&path, b"a longer first version").unwrap();
+		write_atomic(&path, b"second").unwrap();
+
+		assert_eq!(fs::read(&path).unwrap(), b"second");
+		assert_eq!(
+			fs::read(backup_path(&path)).unwrap(),
+			b"a longer first version"
+		);
+		assert!(!with_suffix(&path, ".tmp").exists());
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn falls_back_to_backup() {
+		let dir = test_dir("fallback");
+		let path = dir.join("state");
+
+		write_atomic(&path, b"good").unwrap();
+		write_atomic(&path, b"good too").unwrap();
+		fs::write(&path, [0xff, 0xfe]).unwrap();
+		assert_eq!(read_with_backup(&path, decode_utf8).unwrap(), "good");
+
+		fs::remove_file(&path).unwrap();
+		assert_eq!(read_with_backup(&path, decode_utf8).unwrap(), "good");
+
+		fs::write(backup_path(&path), [0xff]).unwrap();
+		assert!(read_with_backup(&path, decode_utf8).is_err());
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
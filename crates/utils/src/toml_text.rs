@@ -0,0 +1,81 @@
+// editing toml files as text, for files people write by hand and whose comments have to stay
+
+/// `toml` with the `key = value` lines of `fields` written into its `[table]`, replacing the lines
+/// with the same keys and adding the others at the top of the table. a table that isn't there is
+/// appended at the end \
+/// everything else, comments and the order of the lines included, stays as it was. doesn't check
+/// the result is valid toml, parse it to be sure
+pub fn set_table_fields(toml: &str, table: &str, fields: &str) -> String {
+	let header = format!("[{table}]");
+	let is_header = |line: &str| {
+		let rest = line.trim().strip_prefix(header.as_str());
+		rest.is_some_and(|rest| rest.trim().is_empty() || rest.trim().starts_with('#'))
+	};
+
+	match toml.lines().position(is_header) {
+		Some(start) => set_fields_at(toml, start, fields),
+		None if toml.trim().is_empty() => format!("{header}\n{}\n", fields.trim_end()),
+		None => format!("{}\n\n{header}\n{}\n", toml.trim_end(), fields.trim_end()),
+	}
+}
+
+/// like [set_table_fields], for the table whose header is line number `start` of `toml`. works
+/// for the entries of arrays of tables too, which can't be found by their header alone
+pub fn set_fields_at(toml: &str, start: usize, fields: &str) -> String {
+	let mut lines = toml.lines().map(str::to_string).collect::<Vec<_>>();
+
+	// the table goes on until the next header
+	let mut end = lines[start + 1..]
+		.iter()
+		.position(|line| line.trim_start().starts_with('['))
+		.map_or(lines.len(), |len| start + 1 + len);
+
+	for field in fields.lines() {
+		let Some((key, _)) = field.split_once('=') else {
+			continue;
+		};
+		let same_key = |line: &String| {
+			line.split_once('=')
+				.is_some_and(|(other, _)| other.trim() == key.trim())
+		};
+		match lines[start + 1..end].iter().position(same_key) {
+			Some(i) => lines[start + 1 + i] = field.to_string(),
+			None => {
+				lines.insert(start + 1, field.to_string());
+				end += 1;
+			}
+		}
+	}
+
+	let mut toml = lines.join("\n");
+	toml.push('\n');
+	toml
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TOML: &str = "\
+# comments stay
+[a]
+x = 1 # so do these
+y = 2
+
+[b]
+x = 3
+";
+
+	#[test]
+	fn sets_fields_in_place() {
+		let toml = set_table_fields(TOML, "a", "y = 5\nz = 6\n");
+		assert_eq!(
+			toml,
+			"# comments stay\n[a]\nz = 6\nx = 1 # so do these\ny = 5\n\n[b]\nx = 3\n"
+		);
+
+		let toml = set_table_fields(TOML, "c", "x = 7\n");
+		assert_eq!(toml, format!("{TOML}\n[c]\nx = 7\n"));
+		assert_eq!(set_table_fields("", "c", "x = 7"), "[c]\nx = 7\n");
+	}
+}